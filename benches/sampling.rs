@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use vose_alias::VoseAlias;
+
+
+fn probabilities(size:u32) -> Vec<f32> {
+    let total = (size * (size + 1) / 2) as f32;
+    let mut probabilities:Vec<f32> = (1..=size).map(|w| w as f32 / total).collect();
+    // make sure the probabilities sum exactly to 1
+    let sum:f32 = probabilities[1..].iter().sum();
+    probabilities[0] = 1.0 - sum;
+    probabilities
+}
+
+
+fn distribution(size:u32) -> VoseAlias<u32> {
+    VoseAlias::new((0..size).collect(), probabilities(size))
+}
+
+
+fn bench_sampling(c: &mut Criterion) {
+    let va = distribution(200);
+
+    c.bench_function("sample", |b| b.iter(|| black_box(va.sample())));
+
+    let mut rng = rand::thread_rng();
+    c.bench_function("sample_with", |b| b.iter(|| black_box(va.sample_with(&mut rng))));
+
+    c.bench_function("sample_n 1000", |b| b.iter(|| black_box(va.sample_n(1000))));
+
+    let va16:VoseAlias<u32, u16> = VoseAlias::new_with_index((0..200).collect(), probabilities(200));
+    c.bench_function("sample_with u16 index", |b| b.iter(|| black_box(va16.sample_with(&mut rng))));
+
+    let mut buffered = vose_alias::BufferedRng::new(rand::thread_rng());
+    c.bench_function("sample_with buffered", |b| b.iter(|| black_box(va.sample_with(&mut buffered))));
+
+    let padded = va.padded().unwrap();
+    c.bench_function("sample_with padded", |b| b.iter(|| black_box(padded.sample_with(&mut rng))));
+}
+
+
+criterion_group!(benches, bench_sampling);
+criterion_main!(benches);
@@ -0,0 +1,201 @@
+//! A weighted sampler learning the weights of its elements from observed outcomes.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{check_weights, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// The rule used by an `AdaptiveSampler` to compute the new weight of an element from its current weight and an observed outcome.
+///
+/// Negative or non-finite new weights are treated as 0.
+#[derive(Debug, Clone, Copy)]
+pub enum UpdateRule {
+    /// Exponential moving average: the new weight is `(1 - rate) * weight + rate * outcome`, with `rate` in [0, 1].
+    ExponentialMovingAverage { rate:f64 },
+    /// A custom rule, called with the current weight and the outcome.
+    Custom(fn(f64, f64) -> f64),
+}
+
+
+impl UpdateRule {
+
+    /// Returns the new weight of an element of weight `weight` after observing `outcome`.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::UpdateRule;
+    ///
+    /// let ema = UpdateRule::ExponentialMovingAverage { rate: 0.25 };
+    /// assert_eq!(ema.apply(1.0, 0.0), 0.75);
+    ///
+    /// let additive = UpdateRule::Custom(|weight, outcome| weight + outcome);
+    /// assert_eq!(additive.apply(1.0, 2.0), 3.0);
+    /// ```
+    pub fn apply(&self, weight:f64, outcome:f64) -> f64 {
+        let new_weight = match *self {
+            UpdateRule::ExponentialMovingAverage { rate } => (1.0 - rate) * weight + rate * outcome,
+            UpdateRule::Custom(rule) => rule(weight, outcome),
+        };
+        if new_weight.is_finite() && new_weight > 0.0 { new_weight } else { 0.0 }
+    }
+}
+
+
+/// A weighted sampler whose weights are learned from feedback, such as click-through rates of recommendation candidates.
+///
+/// Each observed outcome updates the weight of an element with an `UpdateRule`. The alias tables are rebuilt, in O(n), every `rebuild_every` observations, or on demand with `AdaptiveSampler::rebuild()`: in between, samples are drawn in constant time from slightly outdated weights.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::{AdaptiveSampler, UpdateRule};
+///
+/// let rule = UpdateRule::ExponentialMovingAverage { rate: 0.1 };
+/// let mut sampler = AdaptiveSampler::new(vec!["banner_a", "banner_b"], vec![0.5, 0.5], rule, 100).unwrap();
+///
+/// let shown = sampler.sample().unwrap();
+/// let clicked = shown == "banner_b";
+/// sampler.observe(&shown, if clicked { 1.0 } else { 0.0 }).unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveSampler<T> where T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    index:HashMap<T, usize>,
+    weights:Vec<f64>,
+    rule:UpdateRule,
+    rebuild_every:usize,
+    // the number of observations since the last rebuild
+    pending:usize,
+    // built over the elements of positive weight only, `None` when all the weights are 0
+    table:Option<VoseAlias<T>>,
+}
+
+
+impl<T> AdaptiveSampler<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns a sampler over the given elements and initial weights, updating the weights with `rule` and rebuilding its tables every `rebuild_every` observations.
+    ///
+    /// An error is returned if both vectors do not have the same size, if the element vector is empty or contains duplicates, or if a weight is negative or not finite. A `rebuild_every` of 0 is treated as 1.
+    pub fn new(element_vector:Vec<T>, weight_vector:Vec<f64>, rule:UpdateRule, rebuild_every:usize) -> Result<AdaptiveSampler<T>, VoseAliasError> {
+        // all the weights can be 0
+        match check_weights(element_vector.len(), &weight_vector) {
+            Ok(_) | Err(VoseAliasError::ZeroTotalWeight) => (),
+            Err(e) => return Err(e),
+        }
+        let mut index:HashMap<T, usize> = HashMap::with_capacity(element_vector.len());
+        for (i, e) in element_vector.iter().enumerate() {
+            if index.insert(*e, i).is_some() {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+
+        let table = VoseAlias::from_positive_weights(&element_vector, &weight_vector);
+        Ok(AdaptiveSampler {
+            elements: element_vector,
+            index,
+            weights: weight_vector,
+            rule,
+            rebuild_every: rebuild_every.max(1),
+            pending: 0,
+            table,
+        })
+    }
+
+
+    /// Returns the elements of the sampler.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the current weight of an element, or `None` if it is not in the sampler. The weight may not be used by the tables yet.
+    pub fn weight(&self, element:&T) -> Option<f64> {
+        self.index.get(element).map(|i| self.weights[*i])
+    }
+
+
+    /// Updates the weight of an element from an observed outcome, and returns its previous weight. The tables are rebuilt if this is the `rebuild_every`-th observation since the last rebuild.
+    ///
+    /// An error is returned if the outcome is not finite. If the element is not in the sampler, `Ok(None)` is returned and nothing is changed.
+    pub fn observe(&mut self, element:&T, outcome:f64) -> Result<Option<f64>, VoseAliasError> {
+        if !outcome.is_finite() {
+            return Err(VoseAliasError::InvalidWeight(outcome));
+        }
+        let i = match self.index.get(element) {
+            Some(i) => *i,
+            None => return Ok(None),
+        };
+
+        let previous = self.weights[i];
+        self.weights[i] = self.rule.apply(previous, outcome);
+        self.pending += 1;
+        if self.pending >= self.rebuild_every {
+            self.rebuild();
+        }
+        Ok(Some(previous))
+    }
+
+
+    /// Rebuilds the tables from the current weights, in O(n).
+    pub fn rebuild(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("vose_alias.rebuild", sampler = "adaptive", size = self.elements.len()).entered();
+        self.table = VoseAlias::from_positive_weights(&self.elements, &self.weights);
+        self.pending = 0;
+    }
+
+
+    /// Returns an element sampled according to the weights of the last rebuild, or `None` if they were all 0.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> Option<T> {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns an element sampled according to the weights of the last rebuild using the given generator, or `None` if they were all 0.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<T> {
+        self.table.as_ref().map(|table| table.sample_with(rng))
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn periodic_rebuild() {
+        let rule = UpdateRule::ExponentialMovingAverage { rate: 1.0 };
+        let mut sampler = AdaptiveSampler::new(vec![1, 2], vec![1.0, 0.0], rule, 2).unwrap();
+        assert_eq!(sampler.observe(&1, 0.0), Ok(Some(1.0)));
+        // the tables still use the initial weights
+        assert_eq!(sampler.sample(), Some(1));
+        assert_eq!(sampler.observe(&2, 1.0), Ok(Some(0.0)));
+        for _ in 0..100 {
+            assert_eq!(sampler.sample(), Some(2));
+        }
+        assert_eq!(sampler.observe(&3, 1.0), Ok(None));
+        assert!(sampler.observe(&1, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn custom_rule() {
+        let rule = UpdateRule::Custom(|weight, outcome| weight - outcome);
+        let mut sampler = AdaptiveSampler::new(vec!['a'], vec![1.0], rule, 1).unwrap();
+        sampler.observe(&'a', 5.0).unwrap();
+        assert_eq!(sampler.weight(&'a'), Some(0.0));
+        assert_eq!(sampler.sample(), None);
+    }
+}
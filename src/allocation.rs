@@ -0,0 +1,81 @@
+//! Deterministic allocation of a batch of draws proportionally to the probabilities.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{SlotIndex, VoseAlias};
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Splits `n` draws between the elements proportionally to their probabilities, without any randomness, using the largest remainder method.
+    ///
+    /// Each element first gets the integer part of `n` × p, and the draws left are given one by one to the elements with the largest fractional parts (the first ones in `elements` in case of a tie). The allocated counts sum to `n`, and each count differs from its exact share by less than 1.
+    /// Every element is present in the returned map, with a count of 0 if needed.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let shares = VoseAlias::new(vec!["campaign A", "campaign B", "campaign C"], vec![0.5, 0.3, 0.2]);
+    /// let impressions = shares.allocate(7);
+    /// // exact shares: 3.5, 2.1, 1.4
+    /// assert_eq!(impressions["campaign A"], 4);
+    /// assert_eq!(impressions["campaign B"], 2);
+    /// assert_eq!(impressions["campaign C"], 1);
+    /// ```
+    pub fn allocate(&self, n:u64) -> HashMap<T, u64> {
+        let total = n as f64;
+        let mut counts:Vec<u64> = Vec::with_capacity(self.elements.len());
+        let mut remainders:Vec<(usize, f64)> = Vec::with_capacity(self.elements.len());
+        for (i, p) in self.probabilities.iter().enumerate() {
+            let share = p * total;
+            counts.push(share.floor() as u64);
+            remainders.push((i, share - share.floor()));
+        }
+
+        // the probabilities may not sum exactly to 1, so the integer parts alone can slightly exceed n
+        let mut allocated:u64 = counts.iter().sum();
+        while allocated > n {
+            let largest = (0..counts.len()).filter(|i| counts[*i] > 0).max_by_key(|i| counts[*i]).unwrap_or(0);
+            counts[largest] -= 1;
+            allocated -= 1;
+        }
+        // stable sort, so that ties keep the order of the elements
+        remainders.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        for (i, _) in remainders.iter().cycle().take((n - allocated) as usize) {
+            counts[*i] += 1;
+        }
+
+        self.elements.iter().copied().zip(counts).collect()
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn largest_remainders() {
+        let va = VoseAlias::new(vec![1, 2, 3], vec![0.25, 0.25, 0.5]);
+        let counts = va.allocate(2);
+        // 0.5, 0.5 and 1: the tie between 1 and 2 goes to the first element
+        assert_eq!((counts[&1], counts[&2], counts[&3]), (1, 0, 1));
+        assert_eq!(va.allocate(0).values().sum::<u64>(), 0);
+        assert_eq!(va.allocate(1_000_001).values().sum::<u64>(), 1_000_001);
+
+        let va = VoseAlias::new(vec!['a', 'b', 'c'], vec![0.2, 0.3, 0.5]);
+        for n in 0..50 {
+            let counts = va.allocate(n);
+            assert_eq!(counts.values().sum::<u64>(), n);
+            assert!(counts.iter().all(|(e, c)| (*c as f64 - va.probabilities[va.index[e]] * n as f64).abs() < 1.0));
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Alias tables stored in a custom allocator, through the allocator API of `allocator-api2` (feature `allocator-api2`).
+
+use std::fmt::Debug;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::vec::Vec;
+use rand_core::RngCore;
+
+use crate::{build_slots, check_weights, die_and_coin, SlotStack, VoseAliasError, COIN_SCALE};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// Alias tables whose elements, probabilities and slot tables are stored in the allocator `A`, such as an arena or a pool holding the rest of the data of a frame.
+///
+/// The allocator API is the one of the `allocator-api2` crate, which works on stable Rust and, with its `nightly` feature, forwards to the unstable `allocator_api` of the standard library, so that the allocators of both are accepted. The work buffers of the construction are also allocated in `A`. Sampling draws one 64-bit value, like `VoseAlias::sample_with()`.
+///
+/// Unlike `VoseAlias`, the elements are not indexed, as the index would be stored in the global allocator. The duplicates are detected at construction by sorting the hashes of the elements in `A`.
+///
+/// `VoseAliasIn` is a separate and minimal type: it builds the tables from weights, samples from them, and gives the elements and their probabilities. The rest of the API of `VoseAlias` (lookups by element, observers, updates, serialization, statistics...) is not available on it, and `VoseAlias` itself always stores its tables in the global allocator.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use allocator_api2::alloc::Global;
+/// use vose_alias::VoseAliasIn;
+///
+/// let va = VoseAliasIn::from_weights_in(&["sword", "shield"], &[3.0, 1.0], Global).unwrap();
+/// assert_eq!(va.probabilities(), &[0.75, 0.25]);
+/// assert!(["sword", "shield"].contains(&va.sample()));
+/// # }
+/// ```
+pub struct VoseAliasIn<T, A = Global> where T: Copy + Hash + Eq + Debug, A: Allocator + Clone {
+    elements:Vec<T, A>,
+    probabilities:Vec<f64, A>,
+    slot_prob:Vec<f32, A>,
+    slot_alias:Vec<u32, A>,
+}
+
+
+impl<T, A> VoseAliasIn<T, A>
+where T: Copy + Hash + Eq + Debug, A: Allocator + Clone {
+
+    /// Returns the tables sampling the elements with a probability proportional to their weights, allocated in `alloc`.
+    ///
+    /// # Errors
+    /// An error is returned if both slices do not have the same size, if there is no element or more than 2^32 elements, if an element appears twice, if a weight is negative or not finite, or if all the weights are 0.
+    pub fn from_weights_in(elements:&[T], weights:&[f64], alloc:A) -> Result<VoseAliasIn<T, A>, VoseAliasError> {
+        let total = check_weights(elements.len(), weights)?;
+        if elements.len() > u32::MAX as usize {
+            return Err(VoseAliasError::TooManyElements { elements: elements.len(), capacity: u32::MAX as usize });
+        }
+        check_duplicates(elements, alloc.clone())?;
+
+        let n = elements.len();
+        let mut element_vector = Vec::with_capacity_in(n, alloc.clone());
+        element_vector.extend_from_slice(elements);
+        let mut probabilities = Vec::with_capacity_in(n, alloc.clone());
+        probabilities.extend(weights.iter().map(|w| w / total));
+        let mut scaled:Vec<f32, A> = Vec::with_capacity_in(n, alloc.clone());
+        scaled.extend(probabilities.iter().map(|p| (p * n as f64) as f32));
+
+        let mut slot_prob = Vec::with_capacity_in(n, alloc.clone());
+        slot_prob.resize(n, 0.0);
+        let mut slot_alias:Vec<usize, A> = Vec::with_capacity_in(n, alloc.clone());
+        slot_alias.resize(n, 0);
+        let mut small:Vec<usize, A> = Vec::with_capacity_in(n, alloc.clone());
+        let mut large:Vec<usize, A> = Vec::with_capacity_in(n, alloc.clone());
+        build_slots(&mut scaled, &mut slot_prob, &mut slot_alias, &mut small, &mut large);
+        let mut slot_alias_u32 = Vec::with_capacity_in(n, alloc);
+        slot_alias_u32.extend(slot_alias.iter().map(|a| *a as u32));
+
+        Ok(VoseAliasIn {
+            elements: element_vector,
+            probabilities,
+            slot_prob,
+            slot_alias: slot_alias_u32,
+        })
+    }
+
+
+    /// Returns the elements, in the order given at construction.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the normalized weights of the elements, in the order of `elements()`.
+    pub fn probabilities(&self) -> &[f64] {
+        &self.probabilities
+    }
+
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+
+    /// Returns `true` if there is no element, which cannot happen once built.
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+
+    /// Returns the allocator storing the tables.
+    pub fn allocator(&self) -> &A {
+        self.elements.allocator()
+    }
+
+
+    /// Returns an element sampled with the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> T {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns an element sampled with the given generator.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> T {
+        let (die, coin) = die_and_coin(rng, self.elements.len());
+        let index = if (coin as f32) < self.slot_prob[die] * COIN_SCALE {
+            die
+        }
+        else {
+            self.slot_alias[die] as usize
+        };
+        self.elements[index]
+    }
+}
+
+
+/// Returns an error with the first duplicated element found, by sorting the hashes of the elements in a buffer allocated in `alloc` and comparing the elements sharing a hash.
+fn check_duplicates<T, A>(elements:&[T], alloc:A) -> Result<(), VoseAliasError>
+where T: Copy + Hash + Eq + Debug, A: Allocator {
+    let mut hashes:Vec<(u64, usize), A> = Vec::with_capacity_in(elements.len(), alloc);
+    hashes.extend(elements.iter().enumerate().map(|(i, e)| {
+        let mut hasher = DefaultHasher::new();
+        e.hash(&mut hasher);
+        (hasher.finish(), i)
+    }));
+    hashes.sort_unstable();
+    for (start, (hash, i)) in hashes.iter().enumerate() {
+        for (_, j) in hashes[start + 1..].iter().take_while(|(h, _)| h == hash) {
+            if elements[*i] == elements[*j] {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", elements[*i])));
+            }
+        }
+    }
+    Ok(())
+}
+
+
+impl<T, A> Clone for VoseAliasIn<T, A>
+where T: Copy + Hash + Eq + Debug, A: Allocator + Clone {
+    /// Copies the tables in the same allocator.
+    fn clone(&self) -> Self {
+        VoseAliasIn {
+            elements: self.elements.clone(),
+            probabilities: self.probabilities.clone(),
+            slot_prob: self.slot_prob.clone(),
+            slot_alias: self.slot_alias.clone(),
+        }
+    }
+}
+
+
+impl<T, A> Debug for VoseAliasIn<T, A>
+where T: Copy + Hash + Eq + Debug, A: Allocator + Clone {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VoseAliasIn")
+            .field("elements", &self.elements.len())
+            .finish()
+    }
+}
+
+
+impl<A> SlotStack for Vec<usize, A>
+where A: Allocator {
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+
+    fn push(&mut self, i:usize) {
+        Vec::push(self, i)
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        Vec::pop(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        Vec::is_empty(self)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VoseAlias;
+
+    #[test]
+    fn same_tables_as_vose_alias() {
+        let weights = [1.0, 4.0, 2.0, 0.0, 1.0];
+        let va = VoseAliasIn::from_weights_in(&[1, 2, 3, 4, 5], &weights, Global).unwrap();
+        let reference:VoseAlias<i32> = VoseAlias::from_weights(vec![1, 2, 3, 4, 5], weights.to_vec()).unwrap();
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0x9e37_79b9_7f4a_7c15);
+        assert_eq!(&va.slot_prob[..], &reference.slot_prob[..]);
+        assert_eq!(&va.slot_alias[..], &reference.slot_alias[..]);
+        for _ in 0..100 {
+            assert_ne!(va.clone().sample_with(&mut rng), 4);
+        }
+        assert_eq!(VoseAliasIn::from_weights_in(&[1], &[0.0], Global).err(), Some(VoseAliasError::ZeroTotalWeight));
+        assert!(matches!(VoseAliasIn::<u8>::from_weights_in(&[1], &[], Global), Err(VoseAliasError::SizeMismatch { .. })));
+        assert_eq!(VoseAliasIn::from_weights_in(&[1, 2, 3, 2], &[1.0; 4], Global).err(), Some(VoseAliasError::DuplicateElement("2".to_string())));
+    }
+
+    #[cfg(feature = "bumpalo")]
+    #[test]
+    fn duplicates_in_arena() {
+        let arena = bumpalo::Bump::new();
+        let elements:std::vec::Vec<u32> = (0..1000).chain(Some(500)).collect();
+        let weights = vec![1.0; elements.len()];
+        assert_eq!(VoseAliasIn::from_weights_in(&elements, &weights, &arena).err(), Some(VoseAliasError::DuplicateElement("500".to_string())));
+        let used = arena.allocated_bytes();
+        assert!(used > 0);
+
+        let va = VoseAliasIn::from_weights_in(&elements[..1000], &weights[..1000], &arena).unwrap();
+        assert_eq!(va.len(), 1000);
+        assert!(arena.allocated_bytes() > used);
+    }
+}
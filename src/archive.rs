@@ -0,0 +1,337 @@
+//! Zero-copy archiving of the tables with `rkyv`, enabled by the `rkyv` feature.
+//!
+//! An archive holds a format version, the elements, the probability and alias tables, and the probabilities given at construction. It can be validated and sampled in place, for instance from a memory-mapped file, without deserializing the elements.
+//! The archives of the version `1`, without the probabilities, and of the version `2`, with the probabilities as `f32`, can still be read.
+//! The archive should be stored at an address aligned like its widest field (at least 4 bytes): memory maps and `rkyv::util::AlignedVec` are.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+use rkyv::api::high::{HighDeserializer, HighSerializer, HighValidator};
+use rkyv::bytecheck::CheckBytes;
+use rkyv::rancor;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Archived, Deserialize, Portable, Serialize};
+
+use crate::{die_and_coin, SlotIndex, VoseAlias, VoseAliasError, COIN_SCALE, TABLE_TOLERANCE};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// The version of the archive format written by this version of the crate.
+const ARCHIVE_VERSION:u32 = 3;
+
+/// The version of the archives storing the probabilities given at construction as `f32`.
+const F32_ARCHIVE_VERSION:u32 = 2;
+
+/// The version of the archives without the probabilities given at construction.
+const LEGACY_ARCHIVE_VERSION:u32 = 1;
+
+
+#[derive(Archive, Serialize, Deserialize)]
+struct TablesRepr<T> {
+    version: u32,
+    elements: Vec<T>,
+    prob: Vec<f32>,
+    // the index of the alias of each element, or its own index if it has no alias
+    alias: Vec<u32>,
+    probabilities: Vec<f64>,
+}
+
+
+/// The layout of the version `2`.
+#[derive(Archive, Serialize, Deserialize)]
+struct F32TablesRepr<T> {
+    version: u32,
+    elements: Vec<T>,
+    prob: Vec<f32>,
+    alias: Vec<u32>,
+    probabilities: Vec<f32>,
+}
+
+
+/// The layout of the version `1`.
+#[derive(Archive, Serialize, Deserialize)]
+struct LegacyTablesRepr<T> {
+    version: u32,
+    elements: Vec<T>,
+    prob: Vec<f32>,
+    alias: Vec<u32>,
+}
+
+
+/// The probabilities given at construction, as stored by the version of the archive.
+#[derive(Clone, Copy)]
+enum ArchivedProbabilities<'a> {
+    Missing,
+    F32(&'a [Archived<f32>]),
+    F64(&'a [Archived<f64>]),
+}
+
+
+/// A view of the tables of an archive, with the version of the archive and the version its layout was written with.
+type Layout<'a, T> = Result<(u32, u32, ArchivedVoseAlias<'a, T>), rancor::Error>;
+
+/// Reads an archive with one of the layouts.
+type LayoutReader<'a, T> = fn(&'a [u8]) -> Layout<'a, T>;
+
+
+/// A view of the tables of a Vose-Alias object archived with `VoseAlias::to_archive()`, sampling directly from the archived bytes.
+///
+/// The tables are checked when the view is created, but the elements are not: two equal elements in a corrupt archive are sampled as distinct ones. `ArchivedVoseAlias::to_vose_alias()` performs all the checks.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::{ArchivedVoseAlias, VoseAlias};
+///
+/// let va = VoseAlias::new(vec![1u16, 2, 3], vec![0.5, 0.25, 0.25]);
+/// let bytes = va.to_archive();
+///
+/// // `bytes` could have been memory-mapped from an asset file
+/// let archived = ArchivedVoseAlias::<u16>::from_bytes(&bytes).unwrap();
+/// let sampled = archived.sample();
+/// assert!(archived.elements().contains(sampled));
+/// # }
+/// ```
+pub struct ArchivedVoseAlias<'a, T: Archive> {
+    elements:&'a [T::Archived],
+    prob:&'a [Archived<f32>],
+    alias:&'a [Archived<u32>],
+    probabilities:ArchivedProbabilities<'a>,
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug + for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>, I: SlotIndex {
+
+    /// Returns the archive of the elements and of the tables, to be read back with `ArchivedVoseAlias::from_bytes()`.
+    ///
+    /// # Panics
+    /// This function panics if an element cannot be archived.
+    pub fn to_archive(&self) -> AlignedVec {
+        let repr = TablesRepr {
+            version: ARCHIVE_VERSION,
+            elements: self.elements.clone(),
+            prob: self.slot_prob.clone(),
+            alias: self.slot_alias.iter().map(|a| a.to_usize() as u32).collect(),
+            probabilities: self.probabilities.clone(),
+        };
+        match rkyv::to_bytes::<rancor::Error>(&repr) {
+            Ok(bytes) => bytes,
+            Err(e) => panic!("The tables cannot be archived: {}", e),
+        }
+    }
+}
+
+
+impl<'a, T> ArchivedVoseAlias<'a, T>
+where T: Archive + 'a, T::Archived: for<'b> CheckBytes<HighValidator<'b, rancor::Error>> {
+
+    /// Returns a view of the archived tables stored in `bytes`, after checking that they are well-formed and respect the invariants of the Vose-Alias method.
+    ///
+    /// An error is returned if the bytes are not a valid archive (including when they are misaligned), if the archive was written with an unsupported format version, or if the tables are corrupt.
+    pub fn from_bytes(bytes:&'a [u8]) -> Result<ArchivedVoseAlias<'a, T>, VoseAliasError> {
+        // the version is a field of the archive, whose layout depends on the version: each layout is tried, the newest first
+        let layouts:[LayoutReader<'a, T>; 3] = [ArchivedVoseAlias::current_layout, ArchivedVoseAlias::f32_layout, ArchivedVoseAlias::legacy_layout];
+        // the error of the layout of the version of the archive, over the one of another version, over an unreadable archive
+        let mut error = (u8::MAX, VoseAliasError::CorruptTable(String::from("invalid archive")));
+        for layout in &layouts {
+            let (rank, e) = match layout(bytes) {
+                Ok((version, expected, view)) if version == expected => match view.check() {
+                    Ok(()) => return Ok(view),
+                    Err(e) => (0, e),
+                },
+                Ok((version, _, _)) => (1, VoseAliasError::UnsupportedVersion(version)),
+                Err(e) => (2, VoseAliasError::CorruptTable(format!("invalid archive ({})", e))),
+            };
+            if rank < error.0 {
+                error = (rank, e);
+            }
+        }
+        Err(error.1)
+    }
+
+
+    fn current_layout(bytes:&'a [u8]) -> Layout<'a, T> {
+        let tables = access::<ArchivedTablesRepr<T>>(bytes)?;
+        Ok((tables.version.to_native(), ARCHIVE_VERSION, ArchivedVoseAlias {
+            elements: tables.elements.as_slice(),
+            prob: tables.prob.as_slice(),
+            alias: tables.alias.as_slice(),
+            probabilities: ArchivedProbabilities::F64(tables.probabilities.as_slice()),
+        }))
+    }
+
+
+    fn f32_layout(bytes:&'a [u8]) -> Layout<'a, T> {
+        let tables = access::<ArchivedF32TablesRepr<T>>(bytes)?;
+        Ok((tables.version.to_native(), F32_ARCHIVE_VERSION, ArchivedVoseAlias {
+            elements: tables.elements.as_slice(),
+            prob: tables.prob.as_slice(),
+            alias: tables.alias.as_slice(),
+            probabilities: ArchivedProbabilities::F32(tables.probabilities.as_slice()),
+        }))
+    }
+
+
+    fn legacy_layout(bytes:&'a [u8]) -> Layout<'a, T> {
+        let tables = access::<ArchivedLegacyTablesRepr<T>>(bytes)?;
+        Ok((tables.version.to_native(), LEGACY_ARCHIVE_VERSION, ArchivedVoseAlias {
+            elements: tables.elements.as_slice(),
+            prob: tables.prob.as_slice(),
+            alias: tables.alias.as_slice(),
+            probabilities: ArchivedProbabilities::Missing,
+        }))
+    }
+
+
+    /// Checks the invariants of the tables.
+    fn check(&self) -> Result<(), VoseAliasError> {
+        let size = self.elements.len();
+        if size == 0 {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        if self.prob.len() != size || self.alias.len() != size {
+            return Err(VoseAliasError::CorruptTable(format!("expected {} probabilities and aliases, found {} and {}", size, self.prob.len(), self.alias.len())));
+        }
+        for i in 0..size {
+            let p = self.prob[i].to_native();
+            let a = self.alias[i].to_native() as usize;
+            if !p.is_finite() || !(-TABLE_TOLERANCE..=1.0 + TABLE_TOLERANCE).contains(&p) {
+                return Err(VoseAliasError::CorruptTable(format!("probability {} of element {} is not in [0, 1]", p, i)));
+            }
+            if a >= size {
+                return Err(VoseAliasError::CorruptTable(format!("alias index {} of element {} is out of bounds", a, i)));
+            }
+            if a == i && p < 1.0 - TABLE_TOLERANCE {
+                return Err(VoseAliasError::CorruptTable(format!("element {} has a probability lower than 1 but no alias", i)));
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Accesses the root of an archive with the layout `R`, after validating it.
+fn access<R>(bytes:&[u8]) -> Result<&R, rancor::Error>
+where R: Portable + for<'b> CheckBytes<HighValidator<'b, rancor::Error>> {
+    rkyv::access::<R, rancor::Error>(bytes)
+}
+
+
+impl<'a, T> ArchivedVoseAlias<'a, T>
+where T: Archive {
+
+    /// Returns the archived elements.
+    pub fn elements(&self) -> &'a [T::Archived] {
+        self.elements
+    }
+
+
+    /// Returns a reference to a sampled archived element, using the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> &'a T::Archived {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a reference to a sampled archived element, using the given generator. The draws are the same as `VoseAlias::sample_with()` on the original object.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> &'a T::Archived {
+        let (die, coin) = die_and_coin(rng, self.elements.len());
+        let index = if (coin as f32) < self.prob[die].to_native() * COIN_SCALE {
+            die
+        }
+        else {
+            self.alias[die].to_native() as usize
+        };
+        &self.elements[index]
+    }
+
+
+    /// Deserializes the archive into a `VoseAlias` object, checking that the elements are distinct.
+    pub fn to_vose_alias<I: SlotIndex>(&self) -> Result<VoseAlias<T, I>, VoseAliasError>
+    where T: Copy + Hash + Eq + Debug, T::Archived: Deserialize<T, HighDeserializer<rancor::Error>> {
+        let mut elements:Vec<T> = Vec::with_capacity(self.elements.len());
+        for e in self.elements.iter() {
+            match rkyv::deserialize::<T, rancor::Error>(e) {
+                Ok(e) => elements.push(e),
+                Err(e) => return Err(VoseAliasError::CorruptTable(format!("invalid archived element ({})", e))),
+            }
+        }
+        let prob:Vec<f32> = self.prob.iter().map(|p| p.to_native()).collect();
+        let alias:Vec<Option<usize>> = self.alias.iter().enumerate()
+            .map(|(i, a)| Some(a.to_native() as usize).filter(|a| *a != i))
+            .collect();
+        let probabilities:Option<Vec<f64>> = match self.probabilities {
+            ArchivedProbabilities::Missing => None,
+            ArchivedProbabilities::F32(p) => Some(p.iter().map(|p| f64::from(p.to_native())).collect()),
+            ArchivedProbabilities::F64(p) => Some(p.iter().map(|p| p.to_native()).collect()),
+        };
+        VoseAlias::from_tables_with_probabilities(elements, prob, alias, probabilities)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn round_trip() {
+        let va = VoseAlias::new(vec!['a', 'b', 'c', 'd'], vec![0.1, 0.2, 0.3, 0.4]);
+        let bytes = va.to_archive();
+        let archived = ArchivedVoseAlias::<char>::from_bytes(&bytes).unwrap();
+        assert_eq!(archived.elements().len(), 4);
+        for _ in 0..20 {
+            assert!(archived.elements().contains(archived.sample()));
+        }
+        let va2:VoseAlias<char> = archived.to_vose_alias().unwrap();
+        assert_eq!(va, va2);
+        assert_eq!(va.prob, va2.prob);
+        assert_eq!(va.probabilities(), va2.probabilities());
+    }
+
+    #[test]
+    fn legacy_archives() {
+        // archives of the elements [1, 2] with the probabilities [0.25, 0.75], written by the versions 1 and 2 of the format
+        const V1:[u8; 52] = [1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 63, 0, 0, 128, 63, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 228, 255, 255, 255, 2, 0, 0, 0, 228, 255, 255, 255, 2, 0, 0, 0, 228, 255, 255, 255, 2, 0, 0, 0];
+        const V2:[u8; 68] = [1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 63, 0, 0, 128, 63, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 128, 62, 0, 0, 64, 63, 2, 0, 0, 0, 220, 255, 255, 255, 2, 0, 0, 0, 220, 255, 255, 255, 2, 0, 0, 0, 220, 255, 255, 255, 2, 0, 0, 0, 220, 255, 255, 255, 2, 0, 0, 0];
+        for legacy in [&V1[..], &V2[..]].iter() {
+            let mut bytes:AlignedVec = AlignedVec::new();
+            bytes.extend_from_slice(legacy);
+            let archived = ArchivedVoseAlias::<u32>::from_bytes(&bytes).unwrap();
+            assert_eq!(archived.elements().len(), 2);
+            let va:VoseAlias<u32> = archived.to_vose_alias().unwrap();
+            assert_eq!(va.elements, vec![1, 2]);
+            assert_eq!(va.probabilities(), &[0.25, 0.75]);
+        }
+    }
+
+    #[test]
+    fn corrupt_archives() {
+        let archive = |version:u32, prob:Vec<f32>, alias:Vec<u32>| {
+            let repr = TablesRepr { version, elements: vec![1u32, 2], prob, alias, probabilities: vec![0.5, 0.5] };
+            rkyv::to_bytes::<rancor::Error>(&repr).unwrap()
+        };
+        let bytes = archive(4, vec![1.0, 1.0], vec![0, 1]);
+        assert_eq!(ArchivedVoseAlias::<u32>::from_bytes(&bytes).err(), Some(VoseAliasError::UnsupportedVersion(4)));
+        let bytes = archive(3, vec![0.5, 1.0], vec![5, 1]);
+        assert!(matches!(ArchivedVoseAlias::<u32>::from_bytes(&bytes), Err(VoseAliasError::CorruptTable(_))));
+        let bytes = archive(3, vec![0.5, 1.0], vec![0, 1]);
+        assert!(matches!(ArchivedVoseAlias::<u32>::from_bytes(&bytes), Err(VoseAliasError::CorruptTable(_))));
+        let bytes = archive(3, vec![1.0], vec![0, 1]);
+        assert!(matches!(ArchivedVoseAlias::<u32>::from_bytes(&bytes), Err(VoseAliasError::CorruptTable(_))));
+        assert!(matches!(ArchivedVoseAlias::<u32>::from_bytes(&[1, 2, 3]), Err(VoseAliasError::CorruptTable(_))));
+        // the stored probabilities are not the ones of the tables
+        let bytes = archive(3, vec![0.5, 1.0], vec![1, 1]);
+        assert!(ArchivedVoseAlias::<u32>::from_bytes(&bytes).unwrap().to_vose_alias::<u32>().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Construction of the tables with the work buffers allocated in a [bumpalo](https://docs.rs/bumpalo) arena (feature `bumpalo`).
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use allocator_api2::vec::Vec as ArenaVec;
+use bumpalo::Bump;
+
+use crate::{build_slots, check_weights, traced_construction, VoseAlias, VoseAliasError, Weight};
+
+
+impl<T> VoseAlias<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns the Vose-Alias object sampling each element proportionally to its weight, like `VoseAlias::try_from_weights()`, allocating the work buffers of the construction in `arena`.
+    ///
+    /// The scaled probabilities, the small and large stacks and the scratch alias table are allocated in the arena: only the returned object is allocated in the global allocator. Constructing many small tables, such as thousands per frame of a game loop, then stops going through the global allocator for its temporary buffers.
+    /// The arena is not reset by this function, as it may hold other data: reset it once the constructions are done (for instance, once per frame) to reuse its memory. `&Bump` also implements the allocator API of `allocator-api2`, so the tables themselves can be stored in the arena with `VoseAliasIn`.
+    ///
+    /// # Errors
+    /// An error is returned if both vectors do not have the same size, if the element vector is empty or contains duplicates, if one of the weights is negative or not finite, or if all the weights are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use bumpalo::Bump;
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let mut arena = Bump::new();
+    /// for _frame in 0..3 {
+    ///     for npc in 0..1000_u32 {
+    ///         let action = VoseAlias::try_from_weights_in_arena(vec!["idle", "walk", "talk"], &[1.0, 2.0, f64::from(npc % 2)], &arena).unwrap();
+    ///         assert!(action.prob_of(&"walk") >= 0.5);
+    ///     }
+    ///     arena.reset();
+    /// }
+    /// ```
+    pub fn try_from_weights_in_arena<W: Weight>(element_vector:Vec<T>, weight_vector:&[W], arena:&Bump) -> Result<VoseAlias<T>, VoseAliasError> {
+        let size = element_vector.len();
+        traced_construction("weights", size, || VoseAlias::build_in_arena(element_vector, weight_vector, arena))
+    }
+
+
+    fn build_in_arena<W: Weight>(element_vector:Vec<T>, weight_vector:&[W], arena:&Bump) -> Result<VoseAlias<T>, VoseAliasError> {
+        let size = element_vector.len();
+        let total = check_weights(size, weight_vector)?;
+        if size > u32::MAX as usize {
+            return Err(VoseAliasError::TooManyElements { elements: size, capacity: u32::MAX as usize });
+        }
+
+        // the requested probabilities and the probability table are kept by the returned object
+        let requested:Vec<f64> = weight_vector.iter().map(|w| w.to_f64() / total).collect();
+        let mut slot_prob:Vec<f32> = vec![0.0; size];
+        let mut scaled:ArenaVec<f32, &Bump> = ArenaVec::with_capacity_in(size, arena);
+        scaled.extend(requested.iter().map(|p| (p * size as f64) as f32));
+        let mut slot_alias:ArenaVec<usize, &Bump> = ArenaVec::with_capacity_in(size, arena);
+        slot_alias.resize(size, 0);
+        let mut small:ArenaVec<usize, &Bump> = ArenaVec::with_capacity_in(size, arena);
+        let mut large:ArenaVec<usize, &Bump> = ArenaVec::with_capacity_in(size, arena);
+        build_slots(&mut scaled, &mut slot_prob, &mut slot_alias, &mut small, &mut large);
+
+        let va:VoseAlias<T> = VoseAlias::from_slots(element_vector, slot_prob, &slot_alias, Some(requested));
+        // the index of the returned object detects the duplicates, instead of a temporary set
+        if va.index.len() != size {
+            if let Some((_, e)) = va.elements.iter().enumerate().find(|(i, e)| va.index[*e] != *i) {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+        Ok(va)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_tables_as_try_from_weights() {
+        let arena = Bump::new();
+        let weights = [3_u32, 0, 1, 4, 2];
+        let va = VoseAlias::try_from_weights_in_arena(vec!['a', 'b', 'c', 'd', 'e'], &weights, &arena).unwrap();
+        let reference = VoseAlias::try_from_weights(vec!['a', 'b', 'c', 'd', 'e'], weights.to_vec()).unwrap();
+        assert_eq!(va, reference);
+        assert_eq!((va.slot_prob.clone(), va.slot_alias.clone(), va.alias.clone()), (reference.slot_prob.clone(), reference.slot_alias.clone(), reference.alias.clone()));
+        assert!(arena.allocated_bytes() > 0);
+
+        assert!(matches!(VoseAlias::try_from_weights_in_arena(vec!['a', 'b', 'a'], &[1.0, 1.0, 1.0], &arena), Err(VoseAliasError::DuplicateElement(_))));
+        assert_eq!(VoseAlias::try_from_weights_in_arena(vec!['a'], &[0.0], &arena).err(), Some(VoseAliasError::ZeroTotalWeight));
+        assert_eq!(VoseAlias::<char>::try_from_weights_in_arena(vec![], &[0_u8; 0], &arena).err(), Some(VoseAliasError::EmptyDistribution));
+    }
+}
@@ -0,0 +1,96 @@
+//! Integration with `ndarray`: construction from array views and batch sampling into arrays (feature `ndarray`).
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use ndarray::{Array, ArrayBase, ArrayView1, DataMut, Dimension, ShapeBuilder};
+#[cfg(feature = "thread-rng")]
+use ndarray::{Array1, Array2};
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+impl VoseAlias<usize> {
+
+    /// Returns the Vose-Alias object sampling the indexes of `weights`, each proportionally to its weight. The weights do not need to sum to 1.
+    ///
+    /// An error is returned if `weights` is empty, if a weight is negative or not finite, or if all the weights are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use ndarray::array;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let weights = array![1.0f32, 2.0, 3.0];
+    /// let va = VoseAlias::from_array(weights.view()).unwrap();
+    /// let draws = va.sample_array1(1000);
+    /// assert_eq!(draws.len(), 1000);
+    /// assert!(draws.iter().all(|i| *i < 3));
+    /// # }
+    /// ```
+    pub fn from_array(weights:ArrayView1<f32>) -> Result<VoseAlias<usize>, VoseAliasError> {
+        let elements:Vec<usize> = (0..weights.len()).collect();
+        VoseAlias::from_weights(elements, weights.iter().map(|w| f64::from(*w)).collect())
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns an array of `n` sampled indexes in `elements`, using the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_array1(&self, n:usize) -> Array1<usize> {
+        rng::with_default_rng(|rng| self.sample_array_with(n, rng))
+    }
+
+
+    /// Returns a `rows`×`cols` array of sampled indexes in `elements`, using the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_array2(&self, rows:usize, cols:usize) -> Array2<usize> {
+        rng::with_default_rng(|rng| self.sample_array_with((rows, cols), rng))
+    }
+
+
+    /// Returns an array of the given shape filled with sampled indexes in `elements`, using the given generator.
+    pub fn sample_array_with<Sh, D, R>(&self, shape:Sh, rng:&mut R) -> Array<usize, D>
+    where Sh: ShapeBuilder<Dim = D>, D: Dimension, R: RngCore + ?Sized {
+        let mut array = Array::zeros(shape);
+        self.fill_indices_with(&mut array, rng);
+        array
+    }
+
+
+    /// Overwrites every value of `out` with a sampled index in `elements`, using the given generator. This avoids allocating a new array for each batch.
+    pub fn fill_indices_with<S, D, R>(&self, out:&mut ArrayBase<S, D>, rng:&mut R)
+    where S: DataMut<Elem = usize>, D: Dimension, R: RngCore + ?Sized {
+        for value in out.iter_mut() {
+            let (die, coin) = self.roll_die_and_flip_coin(rng);
+            *value = self.select_index(die, coin);
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn batch_sampling() {
+        let va = VoseAlias::from_array(array![1.0f32, 2.0, 2.0].view()).unwrap();
+        let draws = va.sample_array2(10, 20);
+        assert_eq!(draws.dim(), (10, 20));
+        assert!(draws.iter().all(|i| *i < 3));
+        assert!(VoseAlias::from_array(array![0.0f32, 0.0].view()).is_err());
+    }
+}
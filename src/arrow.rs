@@ -0,0 +1,128 @@
+//! Interoperability with Apache Arrow: construction from Arrow columns and export of the samples as Arrow arrays (feature `arrow`).
+
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::sync::Arc;
+
+use arrow_array::types::UInt32Type;
+use arrow_array::{Array, DictionaryArray, Float64Array, GenericStringArray, OffsetSizeTrait, StringArray, UInt32Array};
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+impl<'a> VoseAlias<&'a str> {
+
+    /// Returns the Vose-Alias object sampling the strings of the `elements` column, each proportionally to the value of the `weights` column in the same row. The weights do not need to sum to 1.
+    ///
+    /// The elements borrow the string column, so no string is copied. Both `StringArray` and `LargeStringArray` columns are accepted.
+    ///
+    /// An error is returned if a value of either column is null, or in the same cases as `VoseAlias::from_counts()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arrow_array::{Float64Array, StringArray};
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let elements = StringArray::from(vec!["red", "green", "blue"]);
+    /// let weights = Float64Array::from(vec![2.0, 1.0, 1.0]);
+    /// let va = VoseAlias::from_arrow(&elements, &weights).unwrap();
+    /// assert_eq!(va.elements, vec!["red", "green", "blue"]);
+    /// ```
+    pub fn from_arrow<O: OffsetSizeTrait>(elements:&'a GenericStringArray<O>, weights:&Float64Array) -> Result<VoseAlias<&'a str>, VoseAliasError> {
+        if elements.len() != weights.len() {
+            return Err(VoseAliasError::SizeMismatch { elements: elements.len(), probabilities: weights.len() });
+        }
+        if let Some(row) = (0..elements.len()).find(|i| elements.is_null(*i) || weights.is_null(*i)) {
+            return Err(VoseAliasError::NullValue { row });
+        }
+        VoseAlias::from_weights(elements.iter().flatten().collect(), weights.values().to_vec())
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Display + Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns `n` sampled elements as an Arrow dictionary array, using the default generator of the crate.
+    ///
+    /// See `VoseAlias::sample_arrow_with()` for more details.
+    ///
+    /// # Examples
+    /// ```
+    /// use arrow_array::Array;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.25, 0.25]);
+    /// let column = va.sample_arrow(100);
+    /// assert_eq!(column.len(), 100);
+    /// assert_eq!(column.values().len(), 3);
+    /// ```
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_arrow(&self, n:usize) -> DictionaryArray<UInt32Type> {
+        rng::with_default_rng(|rng| self.sample_arrow_with(n, rng))
+    }
+
+
+    /// Returns `n` sampled elements as an Arrow dictionary array, using the given generator.
+    ///
+    /// The keys of the array are the sampled indexes in `elements`, and its values are the elements in their `Display` form, in the order of `elements`. Each element is converted to a string once, whatever the number of samples.
+    ///
+    /// # Panics
+    /// This function panics if the distribution contains more than `u32::MAX` elements.
+    pub fn sample_arrow_with<R: RngCore + ?Sized>(&self, n:usize, rng:&mut R) -> DictionaryArray<UInt32Type> {
+        if self.elements.len() > u32::MAX as usize {
+            panic!("{}", VoseAliasError::TooManyElements { elements: self.elements.len(), capacity: u32::MAX as usize });
+        }
+        let keys:UInt32Array = (0..n).map(|_| {
+            let (die, coin) = self.roll_die_and_flip_coin(rng);
+            self.select_index(die, coin) as u32
+        }).collect::<Vec<u32>>().into();
+        let values = StringArray::from_iter_values(self.elements.iter().map(|e| e.to_string()));
+
+        match DictionaryArray::try_new(keys, Arc::new(values)) {
+            Ok(array) => array,
+            Err(e) => panic!("Internal error. The sampled keys do not match the dictionary ({}). If this happened, please fill in an issue report.", e),
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::LargeStringArray;
+
+    #[test]
+    fn from_columns() {
+        let elements = LargeStringArray::from(vec!["a", "b"]);
+        let va = VoseAlias::from_arrow(&elements, &Float64Array::from(vec![3.0, 1.0])).unwrap();
+        assert_eq!(va.elements, vec!["a", "b"]);
+
+        let elements = StringArray::from(vec![Some("a"), None]);
+        let result = VoseAlias::from_arrow(&elements, &Float64Array::from(vec![3.0, 1.0]));
+        assert_eq!(result.err(), Some(VoseAliasError::NullValue { row: 1 }));
+        let elements = StringArray::from(vec!["a", "b"]);
+        let result = VoseAlias::from_arrow(&elements, &Float64Array::from(vec![Some(1.0), None]));
+        assert_eq!(result.err(), Some(VoseAliasError::NullValue { row: 1 }));
+        let result = VoseAlias::from_arrow(&elements, &Float64Array::from(vec![1.0]));
+        assert!(matches!(result, Err(VoseAliasError::SizeMismatch { .. })));
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn sampled_dictionary() {
+        let va = VoseAlias::new(vec!['x', 'y'], vec![0.5, 0.5]);
+        let column = va.sample_arrow(50);
+        assert_eq!(column.len(), 50);
+        assert!(column.keys().values().iter().all(|k| *k < 2));
+        let values = column.values().as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(values.iter().flatten().collect::<Vec<&str>>(), vec!["x", "y"]);
+    }
+}
@@ -0,0 +1,217 @@
+//! A bank of distributions over the same elements, addressed by key, storing the elements only once.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{build_slots, check_weights, die_and_coin, VoseAliasError, COIN_SCALE};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A bank of distributions over the same elements, such as per-user or per-region weights over a catalog, each distribution being addressed by a key of type `K`.
+///
+/// The elements are stored once for the whole bank, and the tables of all the distributions are stored in two contiguous arrays, one row of n slots per distribution, like `VoseAliasMatrix`. Storing a `VoseAlias` per key would copy the element vector (and its index) for every key.
+/// Unlike `VoseAliasMatrix`, distributions can be inserted, replaced and removed after the construction. The weights of each distribution are normalized, so they do not need to sum exactly to 1.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::SamplerBank;
+///
+/// let mut bank = SamplerBank::new(vec!["news", "sports", "music"]).unwrap();
+/// bank.insert("alice", &[1.0, 0.0, 0.0]).unwrap();
+/// bank.insert("bob", &[0.0, 2.0, 2.0]).unwrap();
+///
+/// assert_eq!(bank.sample(&"alice"), Some("news"));
+/// assert_ne!(bank.sample(&"bob"), Some("news"));
+/// assert_eq!(bank.sample(&"carol"), None);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SamplerBank<K, T> where K: Hash + Eq + Clone, T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    // the row of each key, and the key of each row
+    rows:HashMap<K, usize>,
+    keys:Vec<K>,
+    // row-major tables, row r uses the slots [r * n, (r + 1) * n)
+    prob:Vec<f32>,
+    alias:Vec<u32>,
+}
+
+
+impl<K, T> SamplerBank<K, T>
+where K: Hash + Eq + Clone, T: Copy + Hash + Eq + Debug {
+
+    /// Returns an empty bank of distributions over `elements`.
+    ///
+    /// An error is returned if `elements` is empty, contains duplicates, or contains more than 2^32 elements.
+    pub fn new(elements:Vec<T>) -> Result<SamplerBank<K, T>, VoseAliasError> {
+        if elements.is_empty() {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        if elements.len() > u32::MAX as usize {
+            return Err(VoseAliasError::TooManyElements { elements: elements.len(), capacity: u32::MAX as usize });
+        }
+        let mut seen:HashSet<T> = HashSet::with_capacity(elements.len());
+        for e in &elements {
+            if !seen.insert(*e) {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+        Ok(SamplerBank {
+            elements,
+            rows: HashMap::new(),
+            keys: Vec::new(),
+            prob: Vec::new(),
+            alias: Vec::new(),
+        })
+    }
+
+
+    /// Returns the elements shared by all the distributions.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the number of distributions in the bank.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+
+    /// Returns `true` if the bank does not contain any distribution.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+
+    /// Returns `true` if the bank contains a distribution for `key`.
+    pub fn contains_key(&self, key:&K) -> bool {
+        self.rows.contains_key(key)
+    }
+
+
+    /// Builds the distribution of `key`, the element at the position `i` of `elements()` having a probability proportional to `weights[i]`. The previous distribution of `key` is replaced, if any.
+    ///
+    /// An error is returned, and the bank is left unchanged, if `weights` does not contain one weight per element, if a weight is negative or not finite, or if all the weights are 0.
+    pub fn insert(&mut self, key:K, weights:&[f64]) -> Result<(), VoseAliasError> {
+        let n = self.elements.len();
+        let total = check_weights(n, weights)?;
+
+        let row = match self.rows.get(&key) {
+            Some(row) => *row,
+            None => {
+                self.rows.insert(key.clone(), self.keys.len());
+                self.keys.push(key);
+                self.prob.resize(self.prob.len() + n, 0.0);
+                self.alias.resize(self.alias.len() + n, 0);
+                self.keys.len() - 1
+            },
+        };
+        let mut scaled:Vec<f32> = weights.iter().map(|w| (w / total * n as f64) as f32).collect();
+        let mut row_alias:Vec<usize> = vec![0; n];
+        build_slots(&mut scaled, &mut self.prob[row * n..(row + 1) * n], &mut row_alias, &mut Vec::new(), &mut Vec::new());
+        for (a, r) in self.alias[row * n..(row + 1) * n].iter_mut().zip(row_alias) {
+            *a = r as u32;
+        }
+        Ok(())
+    }
+
+
+    /// Removes the distribution of `key`. Returns `false` if the bank did not contain it.
+    ///
+    /// The tables of the last distribution are moved to the row of the removed one, so that the rows stay contiguous.
+    pub fn remove(&mut self, key:&K) -> bool {
+        let row = match self.rows.remove(key) {
+            Some(row) => row,
+            None => return false,
+        };
+        let n = self.elements.len();
+        let last = self.keys.len() - 1;
+        if row != last {
+            self.prob.copy_within(last * n.., row * n);
+            self.alias.copy_within(last * n.., row * n);
+            self.rows.insert(self.keys[last].clone(), row);
+        }
+        self.keys.swap_remove(row);
+        self.prob.truncate(last * n);
+        self.alias.truncate(last * n);
+        true
+    }
+
+
+    /// Returns an element sampled from the distribution of `key` with the default generator of the crate, or `None` if the bank does not contain it.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self, key:&K) -> Option<T> {
+        rng::with_default_rng(|rng| self.sample_with(key, rng))
+    }
+
+
+    /// Returns an element sampled from the distribution of `key` with the given generator, or `None` if the bank does not contain it. A single 64-bit value is drawn, like `VoseAlias::sample_with()`.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, key:&K, rng:&mut R) -> Option<T> {
+        let row = *self.rows.get(key)?;
+        let n = self.elements.len();
+        let (die, coin) = die_and_coin(rng, n);
+        let slot = row * n + die;
+        let index = if (coin as f32) < self.prob[slot] * COIN_SCALE {
+            die
+        }
+        else {
+            self.alias[slot] as usize
+        };
+        Some(self.elements[index])
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VoseAlias;
+
+    #[test]
+    fn same_tables_as_vose_alias() {
+        let probabilities = [0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125];
+        let mut bank = SamplerBank::new((0..7).collect()).unwrap();
+        bank.insert(1, &[1.0; 7]).unwrap();
+        bank.insert(2, &probabilities).unwrap();
+        let va = VoseAlias::new((0..7).collect::<Vec<usize>>(), probabilities.iter().map(|p| *p as f32).collect());
+        for c in 0..7 {
+            assert!((bank.prob[7 + c] - va.slot_prob[c]).abs() < 0.00001);
+            assert_eq!(bank.alias[7 + c], va.slot_alias[c]);
+        }
+        assert!(matches!(bank.insert(3, &[1.0; 6]), Err(VoseAliasError::SizeMismatch { .. })));
+        assert_eq!(bank.insert(3, &[0.0; 7]), Err(VoseAliasError::ZeroTotalWeight));
+        assert_eq!(bank.len(), 2);
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn insert_and_remove() {
+        let mut bank = SamplerBank::new(vec!['a', 'b', 'c']).unwrap();
+        bank.insert("first", &[1.0, 0.0, 0.0]).unwrap();
+        bank.insert("second", &[0.0, 1.0, 0.0]).unwrap();
+        bank.insert("third", &[0.0, 0.0, 1.0]).unwrap();
+        bank.insert("first", &[0.0, 1.0, 0.0]).unwrap();
+        assert_eq!(bank.len(), 3);
+        assert_eq!(bank.sample(&"first"), Some('b'));
+
+        assert!(bank.remove(&"first"));
+        assert!(!bank.remove(&"first"));
+        assert_eq!((bank.len(), bank.prob.len()), (2, 6));
+        assert_eq!(bank.sample(&"first"), None);
+        for _ in 0..100 {
+            assert_eq!(bank.sample(&"second"), Some('b'));
+            assert_eq!(bank.sample(&"third"), Some('c'));
+        }
+        assert!(SamplerBank::<u8, char>::new(vec!['a', 'a']).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! Command line tool sampling from a distribution described in a text file, to check a distribution without writing any Rust.
+//!
+//! Each line of the input holds an element and its weight, separated by whitespace; blank lines and lines starting with `#` are ignored. The weights do not need to sum to 1.
+//!
+//! ```text
+//! vose-alias [FILE] [-n SAMPLES] [--seed SEED] [--histogram] [--help]
+//! ```
+//!
+//! Without `FILE`, or with `-`, the distribution is read from the standard input. The samples are printed one per line, or summarized in a histogram of the counts and frequencies of each element with `--histogram`.
+
+use std::collections::HashMap;
+use std::io::{self, BufWriter, Read, Write};
+use std::process;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use vose_alias::VoseAlias;
+
+
+const USAGE:&str = "usage: vose-alias [FILE] [-n SAMPLES] [--seed SEED] [--histogram] [--help]";
+
+
+/// The parsed command line arguments.
+#[derive(Debug, PartialEq)]
+struct Options {
+    path:Option<String>,
+    samples:usize,
+    seed:Option<u64>,
+    histogram:bool,
+    help:bool,
+}
+
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => exit_with(&message),
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let mut input = String::new();
+    let read = match options.path.as_deref() {
+        None | Some("-") => io::stdin().read_to_string(&mut input).map(|_| ()),
+        Some(path) => std::fs::read_to_string(path).map(|content| input = content),
+    };
+    if let Err(e) = read {
+        exit_with(&format!("cannot read the distribution: {}", e));
+    }
+    let va = match parse_distribution(&input) {
+        Ok(va) => va,
+        Err(message) => exit_with(&message),
+    };
+
+    let result = match options.seed {
+        Some(seed) => run(&va, &options, &mut StdRng::seed_from_u64(seed)),
+        None => run(&va, &options, &mut rand::thread_rng()),
+    };
+    // a closed pipe (as with `| head`) is not an error
+    if let Err(e) = result {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            exit_with(&format!("cannot write the samples: {}", e));
+        }
+    }
+}
+
+
+/// Prints the samples or their histogram on the standard output.
+fn run<R: Rng>(va:&VoseAlias<&str>, options:&Options, rng:&mut R) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if options.histogram {
+        let index:HashMap<&str, usize> = va.elements.iter().enumerate().map(|(i, e)| (*e, i)).collect();
+        let mut counts = vec![0_usize; va.elements.len()];
+        for _ in 0..options.samples {
+            counts[index[va.sample_with(rng)]] += 1;
+        }
+        for (element, count) in va.elements.iter().zip(counts) {
+            let frequency = if options.samples == 0 { 0.0 } else { count as f64 / options.samples as f64 };
+            writeln!(out, "{}\t{}\t{:.6}", element, count, frequency)?;
+        }
+    }
+    else {
+        for _ in 0..options.samples {
+            writeln!(out, "{}", va.sample_with(rng))?;
+        }
+    }
+    out.flush()
+}
+
+
+/// Parses the command line arguments, the program name excluded.
+fn parse_args<A: Iterator<Item = String>>(mut args:A) -> Result<Options, String> {
+    let mut options = Options { path: None, samples: 10, seed: None, histogram: false, help: false };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-n" | "--samples" => {
+                let value = args.next().ok_or("missing value for -n")?;
+                options.samples = value.parse().map_err(|_| format!("invalid number of samples: {}", value))?;
+            },
+            "--seed" => {
+                let value = args.next().ok_or("missing value for --seed")?;
+                options.seed = Some(value.parse().map_err(|_| format!("invalid seed: {}", value))?);
+            },
+            "--histogram" => options.histogram = true,
+            "-h" | "--help" => options.help = true,
+            _ if arg.starts_with('-') && arg != "-" => return Err(format!("unknown option: {}\n{}", arg, USAGE)),
+            _ if options.path.is_none() => options.path = Some(arg),
+            _ => return Err(format!("unexpected argument: {}\n{}", arg, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+
+/// Parses the `element weight` lines of the input into a distribution.
+fn parse_distribution(input:&str) -> Result<VoseAlias<&str>, String> {
+    let mut elements:Vec<&str> = Vec::new();
+    let mut weights:Vec<f64> = Vec::new();
+    for (number, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(element), Some(weight), None) => {
+                let weight:f64 = weight.parse().map_err(|_| format!("line {}: invalid weight {}", number + 1, weight))?;
+                elements.push(element);
+                weights.push(weight);
+            },
+            _ => return Err(format!("line {}: expected an element and a weight", number + 1)),
+        }
+    }
+    VoseAlias::try_from_weights(elements, weights).map_err(|e| e.to_string())
+}
+
+
+/// Prints the message on the standard error and exits with an error code.
+fn exit_with(message:&str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2);
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let va = parse_distribution("# loot table\ncommon 7\n\nrare 3\n").unwrap();
+        assert_eq!(va.elements, vec!["common", "rare"]);
+        assert!(parse_distribution("common seven").is_err());
+        assert!(parse_distribution("common 1 2").is_err());
+        assert!(parse_distribution("").is_err());
+        assert!(parse_distribution("common 1\ncommon 2").is_err());
+        assert!(parse_distribution("common 0").is_err());
+
+        let args = ["loot.txt", "-n", "100", "--seed", "42", "--histogram"].iter().map(|a| a.to_string());
+        assert_eq!(parse_args(args), Ok(Options { path: Some("loot.txt".to_string()), samples: 100, seed: Some(42), histogram: true, help: false }));
+        assert!(parse_args(["loot.txt", "--help"].iter().map(|a| a.to_string())).unwrap().help);
+        assert!(parse_args(["-n", "many"].iter().map(|a| a.to_string())).is_err());
+    }
+}
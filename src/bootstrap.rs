@@ -0,0 +1,93 @@
+//! Weighted bootstrap: repeated resamples drawn from the distribution.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias};
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Draws `reps` resamples of `n` elements each, and returns the value of `statistic` on each resample, in the order of the resamples.
+    ///
+    /// The same buffer is reused for all the resamples, so `statistic` receives a slice that is only valid during the call. The spread of the returned values estimates the sampling variability of the statistic, for instance through its percentiles.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// // observed response times, weighted by their number of occurrences
+    /// let va = VoseAlias::from_counts(vec![120, 150, 400], vec![50, 40, 10]).unwrap();
+    /// let mut means = va.bootstrap(100, 200, &mut rand::thread_rng(), |resample| {
+    ///     resample.iter().map(|t| *t as f64).sum::<f64>() / resample.len() as f64
+    /// });
+    /// means.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    /// // 95% percentile confidence interval of the mean
+    /// let (low, high) = (means[5], means[194]);
+    /// assert!(low <= high);
+    /// # }
+    /// ```
+    pub fn bootstrap<R, S, F>(&self, n:usize, reps:usize, rng:&mut R, mut statistic:F) -> Vec<S>
+    where R: RngCore + ?Sized, F: FnMut(&[T]) -> S {
+        let mut resample:Vec<T> = Vec::with_capacity(n);
+        (0..reps).map(|_| {
+            resample.clear();
+            resample.extend((0..n).map(|_| self.sample_with(rng)));
+            statistic(&resample)
+        }).collect()
+    }
+
+
+    /// Draws `reps` resamples of `n` elements each, and returns the number of times each element appears in each resample: the row `r` holds the counts of the resample `r`, in the order of `elements`.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["a", "b"], vec![0.5, 0.5]);
+    /// let counts = va.bootstrap_counts(10, 3, &mut rand::thread_rng());
+    /// assert_eq!(counts.len(), 3);
+    /// assert!(counts.iter().all(|row| row.iter().sum::<u64>() == 10));
+    /// # }
+    /// ```
+    pub fn bootstrap_counts<R: RngCore + ?Sized>(&self, n:usize, reps:usize, rng:&mut R) -> Vec<Vec<u64>> {
+        (0..reps).map(|_| {
+            let mut row = vec![0; self.elements.len()];
+            for _ in 0..n {
+                let (die, coin) = self.roll_die_and_flip_coin(rng);
+                row[self.select_index(die, coin)] += 1;
+            }
+            row
+        }).collect()
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resamples() {
+        let va = VoseAlias::new(vec![2, 4], vec![0.5, 0.5]);
+        let mut rng = rand::thread_rng();
+        let sizes = va.bootstrap(7, 5, &mut rng, |resample| resample.len());
+        assert_eq!(sizes, vec![7; 5]);
+        let sums = va.bootstrap(10, 50, &mut rng, |resample| resample.iter().sum::<i32>());
+        assert!(sums.iter().all(|s| (20..=40).contains(s) && s % 2 == 0));
+
+        let counts = va.bootstrap_counts(4, 2, &mut rng);
+        assert_eq!(counts.len(), 2);
+        assert!(counts.iter().all(|row| row.len() == 2 && row[0] + row[1] == 4));
+        assert!(va.bootstrap_counts(4, 0, &mut rng).is_empty());
+    }
+}
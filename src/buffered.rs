@@ -0,0 +1,111 @@
+//! A random number generator wrapper drawing its values in blocks, for high-throughput sampling.
+
+use rand::{Error, Rng, RngCore};
+
+
+/// Number of 64-bit values drawn at once by a `BufferedRng`: 4 KiB of randomness.
+pub(crate) const BLOCK_SIZE:usize = 512;
+
+
+/// A wrapper around a random number generator, which draws 4 KiB of random values at a time and serves them one by one.
+///
+/// Each sample of a `VoseAlias` consumes a single 64-bit value (see `VoseAlias::sample_with()`). Drawing them one at a time from a generator such as `rand::thread_rng()` has a fixed cost per call, which dominates the cost of a sample once the tables are in cache. Filling a block amortizes it over 512 samples, and lets the generator produce its output in bulk.
+/// The values served are the ones the inner generator would have produced as bytes, so a seeded inner generator gives a reproducible sequence, but not the same one as without the wrapper.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::{BufferedRng, VoseAlias};
+///
+/// let va = VoseAlias::new(vec!["a", "b", "c"], vec![0.5, 0.3, 0.2]);
+/// let mut rng = BufferedRng::new(rand::thread_rng());
+/// let a_count = (0..1_000_000).filter(|_| va.sample_with(&mut rng) == "a").count();
+/// assert!(a_count > 490_000 && a_count < 510_000);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BufferedRng<R> {
+    rng:R,
+    block:Box<[u64]>,
+    // the position of the next value to serve in `block`
+    position:usize,
+}
+
+
+impl<R: RngCore> BufferedRng<R> {
+
+    /// Returns a wrapper around `rng`. The first block is drawn when the first value is requested.
+    pub fn new(rng:R) -> BufferedRng<R> {
+        BufferedRng {
+            rng,
+            block: vec![0; BLOCK_SIZE].into_boxed_slice(),
+            position: BLOCK_SIZE,
+        }
+    }
+
+
+    /// Returns the inner generator. The values left in the current block are discarded.
+    pub fn into_inner(self) -> R {
+        self.rng
+    }
+
+
+    fn refill(&mut self) {
+        self.rng.fill(&mut self.block[..]);
+        self.position = 0;
+    }
+}
+
+
+impl<R: RngCore> RngCore for BufferedRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        if self.position == BLOCK_SIZE {
+            self.refill();
+        }
+        let value = self.block[self.position];
+        self.position += 1;
+        value
+    }
+
+    fn fill_bytes(&mut self, dest:&mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest:&mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn buffered_values() {
+        let mut rng = BufferedRng::new(StdRng::seed_from_u64(3));
+        let values:Vec<u64> = (0..BLOCK_SIZE + 10).map(|_| rng.next_u64()).collect();
+        let mut expected = vec![0_u64; 2 * BLOCK_SIZE];
+        StdRng::seed_from_u64(3).fill(&mut expected[..]);
+        assert_eq!(values[..], expected[..BLOCK_SIZE + 10]);
+
+        let mut bytes = [0_u8; 12];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(bytes[..8], expected[BLOCK_SIZE + 10].to_le_bytes());
+        assert_eq!(bytes[8..], expected[BLOCK_SIZE + 11].to_le_bytes()[..4]);
+    }
+}
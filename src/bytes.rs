@@ -0,0 +1,164 @@
+//! Compact binary encoding of `VoseAlias` objects, enabled by the `bytes` feature.
+//!
+//! The encoded tables are framed, so that truncated or foreign data is detected before being decoded:
+//! 1. the 4 bytes `VOSE`
+//! 2. the frame version, currently `3`, as a little-endian `u32`
+//! 3. the length of the payload in bytes, as a little-endian `u64`
+//! 4. the payload: the serialized form of the object (see the `serde` feature), encoded with `postcard`
+//!
+//! The frames of versions `1` and `2` hold the serialized forms of the same versions, and can still be decoded.
+
+use std::convert::TryInto;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use crate::serialization::{F32VoseAliasRepr, LegacyVoseAliasRepr};
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+
+
+/// The bytes starting every frame.
+const MAGIC:&[u8; 4] = b"VOSE";
+
+/// The version of the frame written by this version of the crate.
+const FRAME_VERSION:u32 = 3;
+
+/// The version of the frames holding the serialized form of version `2`, with `f32` probabilities.
+const F32_FRAME_VERSION:u32 = 2;
+
+/// The version of the frames holding the serialized form of version `1`.
+const LEGACY_FRAME_VERSION:u32 = 1;
+
+/// The size of the frame header, before the payload.
+const HEADER_SIZE:usize = 16;
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug + Serialize, I: SlotIndex {
+
+    /// Returns the framed binary encoding of the object, to be decoded with `VoseAlias::from_bytes()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["cache", "disk"], vec![0.9, 0.1]);
+    /// let bytes = va.to_bytes();
+    /// let va2:VoseAlias<&str> = VoseAlias::from_bytes(&bytes).unwrap();
+    /// assert_eq!(va, va2);
+    /// ```
+    ///
+    /// # Panics
+    /// This function panics if an element cannot be serialized.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload = match postcard::to_allocvec(self) {
+            Ok(payload) => payload,
+            Err(e) => panic!("The tables cannot be encoded: {}", e),
+        };
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FRAME_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+}
+
+
+impl<'a, T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug + Deserialize<'a>, I: SlotIndex {
+
+    /// Decodes an object encoded with `VoseAlias::to_bytes()`. Elements such as `&str` are borrowed from `bytes`.
+    ///
+    /// The tables are validated like when they are deserialized with `serde`: an error is returned if the frame is truncated or not a Vose-Alias frame, if it was written with an unsupported version, or if the tables are corrupt. This function never panics.
+    pub fn from_bytes(bytes:&'a [u8]) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC {
+            return Err(VoseAliasError::CorruptTable(String::from("the data does not start with a Vose-Alias frame header")));
+        }
+        let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap_or_default());
+        if ![FRAME_VERSION, F32_FRAME_VERSION, LEGACY_FRAME_VERSION].contains(&version) {
+            return Err(VoseAliasError::UnsupportedVersion(version));
+        }
+        let length = u64::from_le_bytes(bytes[8..16].try_into().unwrap_or_default());
+        let payload = &bytes[HEADER_SIZE..];
+        if payload.len() as u64 != length {
+            return Err(VoseAliasError::CorruptTable(format!("the frame announces {} bytes of payload, but contains {}", length, payload.len())));
+        }
+
+        if version == LEGACY_FRAME_VERSION {
+            return match postcard::from_bytes::<LegacyVoseAliasRepr<T>>(payload) {
+                Ok(repr) => repr.into_vose_alias(),
+                Err(e) => Err(VoseAliasError::CorruptTable(format!("invalid payload ({})", e))),
+            };
+        }
+        if version == F32_FRAME_VERSION {
+            return match postcard::from_bytes::<F32VoseAliasRepr<T>>(payload) {
+                Ok(repr) => repr.into_vose_alias(),
+                Err(e) => Err(VoseAliasError::CorruptTable(format!("invalid payload ({})", e))),
+            };
+        }
+        match postcard::from_bytes(payload) {
+            Ok(va) => Ok(va),
+            Err(e) => Err(VoseAliasError::CorruptTable(format!("invalid payload ({})", e))),
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let va:VoseAlias<u64, u16> = VoseAlias::new_with_index(vec![10, 20, 30], vec![0.2, 0.3, 0.5]);
+        let bytes = va.to_bytes();
+        assert_eq!(&bytes[0..4], b"VOSE");
+        let va2:VoseAlias<u64, u16> = VoseAlias::from_bytes(&bytes).unwrap();
+        assert_eq!(va, va2);
+        assert_eq!(va.prob, va2.prob);
+
+        // a frame of version 1, before the probabilities were stored
+        let payload = postcard::to_allocvec(&(1_u32, vec![1_u8, 2], vec![0.5_f32, 1.0], vec![Some(1_usize), None])).unwrap();
+        let mut legacy = b"VOSE".to_vec();
+        legacy.extend_from_slice(&1_u32.to_le_bytes());
+        legacy.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        legacy.extend_from_slice(&payload);
+        let va:VoseAlias<u8> = VoseAlias::from_bytes(&legacy).unwrap();
+        assert_eq!(va.elements, vec![1, 2]);
+        assert_eq!(va.probabilities(), &[0.25, 0.75]);
+
+        // a frame of version 2, with `f32` probabilities
+        let payload = postcard::to_allocvec(&(2_u32, vec![1_u8, 2], vec![0.2_f32, 1.0], vec![Some(1_usize), None], vec![0.1_f32, 0.9])).unwrap();
+        let mut legacy = b"VOSE".to_vec();
+        legacy.extend_from_slice(&2_u32.to_le_bytes());
+        legacy.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        legacy.extend_from_slice(&payload);
+        let va:VoseAlias<u8> = VoseAlias::from_bytes(&legacy).unwrap();
+        assert_eq!(va.probabilities(), &[0.1, 0.9]);
+        assert_eq!(va.probabilities, vec![f64::from(0.1_f32), f64::from(0.9_f32)]);
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let bytes = VoseAlias::new(vec![1u32, 2], vec![0.5, 0.5]).to_bytes();
+        assert!(matches!(VoseAlias::<u32>::from_bytes(&bytes[0..10]), Err(VoseAliasError::CorruptTable(_))));
+        assert!(matches!(VoseAlias::<u32>::from_bytes(&bytes[0..bytes.len() - 1]), Err(VoseAliasError::CorruptTable(_))));
+
+        let mut other = bytes.clone();
+        other[0] = b'X';
+        assert!(matches!(VoseAlias::<u32>::from_bytes(&other), Err(VoseAliasError::CorruptTable(_))));
+        let mut other = bytes.clone();
+        other[4] = 9;
+        assert_eq!(VoseAlias::<u32>::from_bytes(&other).err(), Some(VoseAliasError::UnsupportedVersion(9)));
+        let mut other = bytes;
+        let last = other.len() - 1;
+        other[last] = 0xff;
+        assert!(VoseAlias::<u32>::from_bytes(&other).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! An opt-in cache of Vose-Alias objects, to avoid building the same tables several times.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use crate::{VoseAlias, VoseAliasError};
+
+
+/// A cached table, along with the input it was built from.
+struct CacheEntry<T> where T: Copy + Hash + Eq + Debug {
+    elements: Vec<T>,
+    weights: Vec<u32>,
+    table: Arc<VoseAlias<T>>,
+}
+
+
+/// A cache memoizing the Vose-Alias objects built from a given element vector and probability vector.
+///
+/// The cache is owned by the user, and can be shared between threads (for instance in a `static` or behind an `Arc`). Entries are looked up by a fingerprint of the element and probability vectors, and the vectors themselves are then compared, so two different distributions never share the same table.
+/// Building a distribution already present in the cache returns a shared pointer to the existing table instead of running the initialization again.
+///
+/// The cache is never emptied automatically. Use `VoseAliasCache::clear()` to release the stored tables.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use vose_alias::VoseAliasCache;
+///
+/// let cache = VoseAliasCache::new();
+/// let va = cache.get_or_new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+/// let va2 = cache.get_or_new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+/// assert!(Arc::ptr_eq(&va, &va2));
+/// ```
+pub struct VoseAliasCache<T> where T: Copy + Hash + Eq + Debug {
+    entries: Mutex<HashMap<u64, Vec<CacheEntry<T>>>>,
+}
+
+
+impl<T> VoseAliasCache<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns an empty cache.
+    pub fn new() -> VoseAliasCache<T> {
+        VoseAliasCache {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+
+    /// Returns the Vose-Alias object built from `element_vector` and `probability_vector`, building and caching it if it is not already in the cache.
+    ///
+    /// # Panics
+    /// This function panics in the same cases as `VoseAlias::new()`. See `VoseAliasCache::get_or_try_new()` for a non-panicking version.
+    pub fn get_or_new(&self, element_vector:Vec<T>, probability_vector:Vec<f32>) -> Arc<VoseAlias<T>> {
+        match self.get_or_try_new(element_vector, probability_vector) {
+            Ok(va) => va,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+
+    /// Returns the Vose-Alias object built from `element_vector` and `probability_vector`, building and caching it if it is not already in the cache.
+    ///
+    /// Returns the same errors as `VoseAlias::try_new()`. Distributions that cannot be built are not cached.
+    pub fn get_or_try_new(&self, element_vector:Vec<T>, probability_vector:Vec<f32>) -> Result<Arc<VoseAlias<T>>, VoseAliasError> {
+        let weights:Vec<u32> = probability_vector.iter().map(|p| p.to_bits()).collect();
+        let fingerprint = fingerprint(&element_vector, &weights);
+
+        let mut entries = self.lock();
+        if let Some(bucket) = entries.get(&fingerprint) {
+            if let Some(entry) = bucket.iter().find(|entry| entry.elements == element_vector && entry.weights == weights) {
+                return Ok(Arc::clone(&entry.table));
+            }
+        }
+
+        let table = Arc::new(VoseAlias::try_new(element_vector.clone(), probability_vector)?);
+        entries.entry(fingerprint).or_default().push(CacheEntry {
+            elements: element_vector,
+            weights,
+            table: Arc::clone(&table),
+        });
+        Ok(table)
+    }
+
+
+    /// Returns the number of distributions stored in the cache.
+    pub fn len(&self) -> usize {
+        self.lock().values().map(|bucket| bucket.len()).sum()
+    }
+
+
+    /// Returns `true` if the cache does not contain any distribution.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+
+    /// Removes all the distributions from the cache. Tables still in use elsewhere are kept alive by their `Arc`.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, Vec<CacheEntry<T>>>> {
+        // a panic while holding the lock cannot leave the map in an inconsistent state
+        match self.entries.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+
+impl<T> Default for VoseAliasCache<T>
+where T: Copy + Hash + Eq + Debug {
+    fn default() -> Self {
+        VoseAliasCache::new()
+    }
+}
+
+
+/// Computes the fingerprint of a distribution from its elements and the bit representation of its probabilities.
+fn fingerprint<T: Hash>(elements:&[T], weights:&[u32]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    elements.hash(&mut hasher);
+    weights.hash(&mut hasher);
+    hasher.finish()
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_shares_tables() {
+        let cache = VoseAliasCache::new();
+        let va = cache.get_or_new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+        let va2 = cache.get_or_new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+        assert!(Arc::ptr_eq(&va, &va2));
+        assert_eq!(cache.len(), 1);
+
+        let va3 = cache.get_or_new(vec![1, 2, 3, 4], vec![0.4, 0.3, 0.2, 0.1]);
+        assert!(!Arc::ptr_eq(&va, &va3));
+        let va4 = cache.get_or_new(vec![4, 3, 2, 1], vec![0.5, 0.2, 0.2, 0.1]);
+        assert!(!Arc::ptr_eq(&va, &va4));
+        assert_eq!(cache.len(), 3);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn cache_errors_not_cached() {
+        let cache = VoseAliasCache::new();
+        assert!(cache.get_or_try_new(vec![1, 2, 3], vec![0.5, 0.2, 0.2, 0.1]).is_err());
+        assert!(cache.is_empty());
+    }
+}
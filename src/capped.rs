@@ -0,0 +1,166 @@
+//! A weighted sampler over elements in limited supply.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{DynamicSampler, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A weighted sampler where each element has a remaining stock, such as the prizes of a raffle or the rewards left in an inventory.
+///
+/// Each sample takes one unit of the stock of the sampled element. Once the stock of an element is exhausted, it cannot be sampled anymore, and the other elements are sampled proportionally to their weights among the ones left. When no element is left, sampling returns `VoseAliasError::Exhausted`.
+/// The weights are kept in a `DynamicSampler`, so that removing an exhausted element takes O(log n) instead of rebuilding a table.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::{CappedSampler, VoseAliasError};
+///
+/// let mut raffle = CappedSampler::new(vec!["car", "bike", "mug"], vec![1.0, 5.0, 50.0], vec![1, 2, 3]).unwrap();
+/// for _ in 0..6 {
+///     raffle.sample().unwrap();
+/// }
+/// assert_eq!(raffle.remaining(), 0);
+/// assert_eq!(raffle.sample(), Err(VoseAliasError::Exhausted));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CappedSampler<T> where T: Copy + Hash + Eq + Debug {
+    sampler:DynamicSampler<T>,
+    index:HashMap<T, usize>,
+    weights:Vec<f64>,
+    stock:Vec<u64>,
+}
+
+
+impl<T> CappedSampler<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns a sampler over the given elements, each sampled proportionally to its weight while its stock lasts.
+    ///
+    /// An error is returned if the vectors do not have the same size, if an element appears twice, or if a weight is negative or not finite.
+    pub fn new(element_vector:Vec<T>, weight_vector:Vec<f64>, stock_vector:Vec<u64>) -> Result<CappedSampler<T>, VoseAliasError> {
+        if weight_vector.len() != element_vector.len() {
+            return Err(VoseAliasError::SizeMismatch { elements: element_vector.len(), probabilities: weight_vector.len() });
+        }
+        if stock_vector.len() != element_vector.len() {
+            return Err(VoseAliasError::SizeMismatch { elements: element_vector.len(), probabilities: stock_vector.len() });
+        }
+        // the weights of the exhausted elements are masked, so they are checked before
+        if let Some(w) = weight_vector.iter().find(|w| !w.is_finite() || **w < 0.0) {
+            return Err(VoseAliasError::InvalidWeight(*w));
+        }
+        let available:Vec<f64> = weight_vector.iter().zip(stock_vector.iter()).map(|(w, s)| if *s > 0 { *w } else { 0.0 }).collect();
+        let sampler = DynamicSampler::from_weights(element_vector.clone(), available)?;
+        // the weights are valid, since they were accepted by the dynamic sampler
+        Ok(CappedSampler {
+            sampler,
+            index: element_vector.iter().enumerate().map(|(i, e)| (*e, i)).collect(),
+            weights: weight_vector,
+            stock: stock_vector,
+        })
+    }
+
+
+    /// Returns the elements of the sampler, including the exhausted ones.
+    pub fn elements(&self) -> &[T] {
+        self.sampler.elements()
+    }
+
+
+    /// Returns the remaining stock of an element, or `None` if it is not in the sampler.
+    pub fn stock(&self, element:&T) -> Option<u64> {
+        self.index.get(element).map(|i| self.stock[*i])
+    }
+
+
+    /// Returns the total remaining stock of the elements that can still be sampled, that is the number of samples left before the sampler is exhausted.
+    pub fn remaining(&self) -> u64 {
+        self.stock.iter().zip(self.weights.iter()).filter(|(_, w)| **w > 0.0).map(|(s, _)| *s).sum()
+    }
+
+
+    /// Returns `true` if no element can be sampled anymore.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == 0
+    }
+
+
+    /// Adds `amount` units to the stock of an element, which can be sampled again if it was exhausted. Returns `false`, without changing anything, if the element is not in the sampler.
+    pub fn restock(&mut self, element:&T, amount:u64) -> bool {
+        let i = match self.index.get(element) {
+            Some(i) => *i,
+            None => return false,
+        };
+        self.stock[i] = self.stock[i].saturating_add(amount);
+        if self.stock[i] > 0 {
+            self.set_available(i, self.weights[i]);
+        }
+        true
+    }
+
+
+    /// Returns a sampled element using the default generator of the crate, and takes it from its stock.
+    ///
+    /// `VoseAliasError::Exhausted` is returned if no element is left.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&mut self) -> Result<T, VoseAliasError> {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a sampled element using the given generator, and takes it from its stock, in O(log n).
+    ///
+    /// `VoseAliasError::Exhausted` is returned if no element is left.
+    pub fn sample_with<R: RngCore + ?Sized>(&mut self, rng:&mut R) -> Result<T, VoseAliasError> {
+        let i = self.sampler.sample_index_with(rng).ok_or(VoseAliasError::Exhausted)?;
+        self.stock[i] -= 1;
+        if self.stock[i] == 0 {
+            self.set_available(i, 0.0);
+        }
+        Ok(self.sampler.elements()[i])
+    }
+
+
+    /// Sets the weight of the element `i` in the dynamic sampler.
+    fn set_available(&mut self, i:usize, weight:f64) {
+        let element = self.sampler.elements()[i];
+        if let Err(e) = self.sampler.update_weight(&element, weight) {
+            panic!("Internal error. The weight of element {:?} was rejected: {}. If this happened, please fill in an issue report.", element, e);
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stock_runs_out() {
+        let mut sampler = CappedSampler::new(vec!['a', 'b', 'c'], vec![1.0, 1.0, 0.0], vec![2, 0, 5]).unwrap();
+        assert_eq!(sampler.remaining(), 2);
+        assert_eq!(sampler.sample(), Ok('a'));
+        assert_eq!(sampler.sample(), Ok('a'));
+        assert!(sampler.is_exhausted());
+        assert_eq!(sampler.sample(), Err(VoseAliasError::Exhausted));
+        assert_eq!(sampler.stock(&'c'), Some(5));
+
+        assert!(sampler.restock(&'b', 1));
+        assert!(!sampler.restock(&'z', 1));
+        assert_eq!(sampler.sample(), Ok('b'));
+        assert_eq!(sampler.sample(), Err(VoseAliasError::Exhausted));
+        assert!(CappedSampler::new(vec![1], vec![1.0], vec![]).is_err());
+        assert_eq!(CappedSampler::new(vec![1, 2], vec![1.0, -1.0], vec![1, 0]).err(), Some(VoseAliasError::InvalidWeight(-1.0)));
+        assert!(matches!(CappedSampler::new(vec![1, 2], vec![1.0, f64::NAN], vec![1, 0]), Err(VoseAliasError::InvalidWeight(_))));
+    }
+}
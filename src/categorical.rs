@@ -0,0 +1,104 @@
+//! Conversions from and to the categorical distribution of `statrs` (feature `statrs`), to compute the mass and cumulative functions of a distribution with `statrs` and sample it with the alias tables.
+//!
+//! The categories of a `statrs::distribution::Categorical` are the integers `0..n`: the category `i` is the element at the position `i` of `elements`.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use statrs::distribution::{Categorical, Discrete};
+use statrs::statistics::Max;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+
+
+impl VoseAlias<usize> {
+
+    /// Returns the Vose-Alias object sampling the categories `0..n` of `categorical` with their probabilities.
+    ///
+    /// # Examples
+    /// ```
+    /// use statrs::distribution::Categorical;
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let categorical = Categorical::new(&[1.0, 2.0, 1.0]).unwrap();
+    /// let va = VoseAlias::from_categorical(&categorical);
+    /// assert!((va.prob_of(&1) - 0.5).abs() < 1e-6);
+    /// ```
+    pub fn from_categorical(categorical:&Categorical) -> VoseAlias<usize> {
+        let n = categorical.max() as usize + 1;
+        match VoseAlias::from_categorical_labeled(categorical, (0..n).collect()) {
+            Ok(va) => va,
+            Err(e) => panic!("Internal error. The categorical distribution is not valid: {}. If this happened, please fill in an issue report.", e),
+        }
+    }
+}
+
+
+impl<T> VoseAlias<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns the Vose-Alias object sampling `labels`, the label at the position `i` having the probability of the category `i` of `categorical`.
+    ///
+    /// An error is returned if the number of labels is not the number of categories, or if the labels contain duplicates.
+    ///
+    /// # Examples
+    /// ```
+    /// use statrs::distribution::Categorical;
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let categorical = Categorical::new(&[0.2, 0.8]).unwrap();
+    /// let va = VoseAlias::from_categorical_labeled(&categorical, vec!["tails", "heads"]).unwrap();
+    /// assert!((va.prob_of(&"heads") - 0.8).abs() < 1e-6);
+    /// ```
+    pub fn from_categorical_labeled(categorical:&Categorical, labels:Vec<T>) -> Result<VoseAlias<T>, VoseAliasError> {
+        let n = categorical.max() + 1;
+        VoseAlias::from_weights(labels, (0..n).map(|i| categorical.pmf(i)).collect())
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the categorical distribution of `statrs` with the requested probabilities of the elements, the category `i` being the element at the position `i` of `elements`.
+    ///
+    /// # Examples
+    /// ```
+    /// use statrs::distribution::{Discrete, DiscreteCDF};
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["low", "mid", "high"], vec![0.5, 0.3, 0.2]);
+    /// let categorical = va.to_categorical();
+    /// assert!((categorical.pmf(1) - 0.3).abs() < 1e-6);
+    /// assert!((categorical.cdf(1) - 0.8).abs() < 1e-6);
+    /// ```
+    pub fn to_categorical(&self) -> Categorical {
+        match Categorical::new(&self.probabilities) {
+            Ok(categorical) => categorical,
+            Err(e) => panic!("Internal error. The probabilities are not a valid categorical distribution: {}. If this happened, please fill in an issue report.", e),
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categorical_round_trip() {
+        let va = VoseAlias::new(vec![3, 1, 2], vec![0.25, 0.0, 0.75]);
+        let categorical = va.to_categorical();
+        assert_eq!(categorical.pmf(1), 0.0);
+        let labeled = VoseAlias::from_categorical_labeled(&categorical, vec![3, 1, 2]).unwrap();
+        assert_eq!(labeled, va);
+        let indexes = VoseAlias::from_categorical(&categorical);
+        assert_eq!(indexes.elements, vec![0, 1, 2]);
+        assert!((indexes.probabilities[2] - 0.75).abs() < 1e-12);
+        assert!(matches!(VoseAlias::from_categorical_labeled(&categorical, vec![1, 2]), Err(VoseAliasError::SizeMismatch { .. })));
+    }
+}
@@ -0,0 +1,96 @@
+//! Methods mirroring the weighted selection of `rand::seq::SliceRandom`, so that code choosing from a slice with per-call weights can move to a prebuilt table by changing the receiver.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns a reference to an element chosen according to the distribution, like `SliceRandom::choose_weighted()` with the weights of the table.
+    ///
+    /// `SliceRandom::choose_weighted()` computes the cumulative weights at each call, and chooses in logarithmic time. The table is built once, and each choice takes constant time.
+    ///
+    /// # Errors
+    /// `VoseAliasError::EmptyDistribution` is returned if there is no element to choose from (see `VoseAlias::try_sample()`).
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use rand::seq::SliceRandom;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let items = [('a', 2_u32), ('b', 1), ('c', 1)];
+    /// let mut rng = rand::thread_rng();
+    /// // before: the weights are read at each call
+    /// let chosen = items.choose_weighted(&mut rng, |item| item.1).unwrap();
+    ///
+    /// // after: the weights are read once
+    /// let table = VoseAlias::try_from_weights(items.to_vec(), items.iter().map(|item| item.1).collect()).unwrap();
+    /// let chosen = table.choose_weighted(&mut rng).unwrap();
+    /// assert!(items.contains(chosen));
+    /// # }
+    /// ```
+    pub fn choose_weighted<R: RngCore + ?Sized>(&self, rng:&mut R) -> Result<&T, VoseAliasError> {
+        if !self.is_sampleable() {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        let (die, coin) = self.roll_die_and_flip_coin(rng);
+        Ok(&self.elements[self.select_index(die, coin)])
+    }
+
+
+    /// Returns an iterator over `amount` distinct elements chosen without replacement according to the distribution, like `SliceRandom::choose_multiple_weighted()` with the weights of the table.
+    ///
+    /// As with `rand`, fewer elements are returned if fewer than `amount` elements have a positive probability. The elements are returned in the order in which they would have been drawn one by one (see `VoseAlias::sample_k_distinct_exp()`).
+    ///
+    /// # Errors
+    /// `VoseAliasError::EmptyDistribution` is returned if there is no element to choose from (see `VoseAlias::try_sample()`).
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let table = VoseAlias::new(vec!["red", "green", "blue"], vec![0.5, 0.3, 0.2]);
+    /// let chosen:Vec<&&str> = table.choose_multiple_weighted(&mut rand::thread_rng(), 2).unwrap().collect();
+    /// assert_eq!(chosen.len(), 2);
+    /// assert!(chosen[0] != chosen[1]);
+    /// # }
+    /// ```
+    pub fn choose_multiple_weighted<R: RngCore + ?Sized>(&self, rng:&mut R, amount:usize) -> Result<impl Iterator<Item = &T>, VoseAliasError> {
+        if !self.is_sampleable() {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        let indexes = self.sample_k_distinct_indexes(amount, rng);
+        Ok(indexes.into_iter().map(move |i| &self.elements[i]))
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choose() {
+        let mut rng = rand::thread_rng();
+        let mut va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.5, 0.0]);
+        assert!((0..100).all(|_| *va.choose_weighted(&mut rng).unwrap() != 3));
+        let mut chosen:Vec<i32> = va.choose_multiple_weighted(&mut rng, 3).unwrap().copied().collect();
+        chosen.sort_unstable();
+        assert_eq!(chosen, vec![1, 2]);
+
+        va.elements.clear();
+        assert_eq!(va.choose_weighted(&mut rng).err(), Some(VoseAliasError::EmptyDistribution));
+        assert!(va.choose_multiple_weighted(&mut rng, 1).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Combinators building composite samplers out of Vose-Alias objects.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns a two-stage sampler, which samples a first element from this distribution, then a second element from the distribution returned by `second` for the first element.
+    ///
+    /// This covers conditional models, where the distribution of an item depends on a previously sampled category.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use std::collections::HashMap;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let categories = VoseAlias::new(vec!["fruit", "vegetable"], vec![0.7, 0.3]);
+    /// let mut items = HashMap::new();
+    /// items.insert("fruit", VoseAlias::new(vec!["apple", "banana"], vec![0.5, 0.5]));
+    /// items.insert("vegetable", VoseAlias::new(vec!["carrot", "leek"], vec![0.9, 0.1]));
+    ///
+    /// let sampler = categories.then(|category| &items[category]);
+    /// let (category, item) = sampler.sample();
+    /// assert!(items[&category].elements.contains(&item));
+    /// # }
+    /// ```
+    pub fn then<'a, U, J, F>(&'a self, second:F) -> Then<'a, T, I, U, J, F>
+    where U: Copy + Hash + Eq + Debug, J: SlotIndex + 'a, F: Fn(&T) -> &'a VoseAlias<U, J> {
+        Then {
+            first: self,
+            second,
+            _marker: PhantomData,
+        }
+    }
+}
+
+
+/// A two-stage sampler, returned by `VoseAlias::then()`.
+pub struct Then<'a, T, I, U, J, F>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex, U: Copy + Hash + Eq + Debug, J: SlotIndex + 'a, F: Fn(&T) -> &'a VoseAlias<U, J> {
+    first:&'a VoseAlias<T, I>,
+    second:F,
+    _marker:PhantomData<&'a VoseAlias<U, J>>,
+}
+
+
+impl<'a, T, I, U, J, F> Then<'a, T, I, U, J, F>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex, U: Copy + Hash + Eq + Debug, J: SlotIndex + 'a, F: Fn(&T) -> &'a VoseAlias<U, J> {
+
+    /// Returns a pair of elements sampled in two stages, using the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> (T, U) {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a pair of elements sampled in two stages, using the given generator.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> (T, U) {
+        let first = self.first.sample_with(rng);
+        let second = (self.second)(&first).sample_with(rng);
+        (first, second)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditional_sampling() {
+        let first = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        let odd = VoseAlias::new(vec![11, 13], vec![0.5, 0.5]);
+        let even = VoseAlias::new(vec![12, 14], vec![0.5, 0.5]);
+        let sampler = first.then(|e| if *e == 1 { &odd } else { &even });
+        for _ in 0..100 {
+            let (a, b) = sampler.sample();
+            assert_eq!((a + b) % 2, 0);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Removal of elements (one at a time, or all the negligible ones) and release of the excess capacity, for long-lived distributions.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{build_slots, ObserverSlot, SlotIndex, VoseAlias, VoseAliasError};
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Removes `element`, renormalizes the probabilities of the remaining elements and rebuilds the tables. Returns the current probability of the element before its removal (see `VoseAlias::probabilities()`), which accounts for the renormalizations of the previous removals.
+    ///
+    /// The last element takes the position of the removed one in `elements`, like `Vec::swap_remove()`, so that only one entry of the index is updated and the elements are not rehashed. The tables are rebuilt from the remaining probabilities, which costs O(n) per removal: to remove elements often, use a `DynamicSampler` and set their weight to 0 with `DynamicSampler::update_weight()`, in O(log n) each. The observer is kept.
+    ///
+    /// `None` is returned, and the distribution is left unchanged, if `element` is not part of the distribution, if the remaining elements all have a probability of 0 (for instance, if `element` is the only one), or if the tables do not match `elements`.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let mut va = VoseAlias::new(vec!["a", "b", "c", "d"], vec![0.5, 0.25, 0.125, 0.125]);
+    /// assert_eq!(va.remove(&"a"), Some(0.5));
+    /// assert_eq!(va.elements, vec!["d", "b", "c"]);
+    /// assert!((va.prob_of(&"b") - 0.5).abs() < 1e-9);
+    /// assert_eq!(va.remove(&"a"), None);
+    /// // "b" now has a probability of 0.5
+    /// assert_eq!(va.remove(&"b"), Some(0.5));
+    /// ```
+    pub fn remove(&mut self, element:&T) -> Option<f32> {
+        if !self.is_sampleable() {
+            return None;
+        }
+        let i = *self.index.get(element)?;
+        let rest:f64 = self.probabilities.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, p)| p).sum();
+        if !(rest > 0.0 && rest.is_finite()) {
+            return None;
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("vose_alias.rebuild", sampler = "vose_alias", size = self.elements.len() - 1).entered();
+
+        let removed = self.probabilities.swap_remove(i) as f32;
+        self.elements.swap_remove(i);
+        self.index.remove(element);
+        if let Some(moved) = self.elements.get(i) {
+            self.index.insert(*moved, i);
+        }
+        let size = self.elements.len();
+        for p in self.probabilities.iter_mut() {
+            *p /= rest;
+        }
+        self.input_probabilities = self.probabilities.iter().map(|p| *p as f32).collect();
+
+        let mut scaled:Vec<f32> = self.probabilities.iter().map(|p| (p * size as f64) as f32).collect();
+        let mut slot_alias:Vec<usize> = vec![0; size];
+        self.slot_prob.truncate(size);
+        build_slots(&mut scaled, &mut self.slot_prob, &mut slot_alias, &mut Vec::new(), &mut Vec::new());
+        self.slot_alias = slot_alias.iter().map(|a| I::from_usize(*a)).collect();
+
+        // `IndexMap::swap_remove()` moves the last entry like `Vec::swap_remove()`, keeping the order of `elements`
+        self.prob.swap_remove(element);
+        for (p, slot_p) in self.prob.values_mut().zip(self.slot_prob.iter()) {
+            *p = *slot_p;
+        }
+        self.alias.clear();
+        for (j, a) in slot_alias.into_iter().enumerate() {
+            if a != j {
+                self.alias.insert(self.elements[j], self.elements[a]);
+            }
+        }
+        Some(removed)
+    }
+
+
+    /// Removes the elements whose probability is below `epsilon`, renormalizes the probabilities of the remaining ones and rebuilds the tables. Returns the number of removed elements.
+    ///
+    /// The remaining elements keep their order, and the observer is kept. A positive `epsilon` also removes the elements of probability 0. The tables are rebuilt with exactly the needed capacity.
+    ///
+    /// # Errors
+    /// An error is returned, and the distribution is left unchanged, if `epsilon` is negative or not finite (`VoseAliasError::InvalidParameter`), or if every element would be removed (`VoseAliasError::EmptyDistribution`).
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let mut va = VoseAlias::new(vec!["a", "b", "c", "d"], vec![0.6, 0.3999, 0.0001, 0.0]);
+    /// assert_eq!(va.prune_below(0.001), Ok(2));
+    /// assert_eq!(va.elements, vec!["a", "b"]);
+    /// assert!((va.prob_of(&"a") - 0.6 / 0.9999).abs() < 1e-6);
+    /// ```
+    pub fn prune_below(&mut self, epsilon:f64) -> Result<usize, VoseAliasError> {
+        if !(epsilon.is_finite() && epsilon >= 0.0) {
+            return Err(VoseAliasError::InvalidParameter { name: "epsilon", value: epsilon });
+        }
+        let removed = self.probabilities.iter().filter(|p| **p < epsilon).count();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        let (elements, weights):(Vec<T>, Vec<f64>) = self.elements.iter().copied().zip(self.probabilities.iter().copied())
+            .filter(|(_, p)| *p >= epsilon)
+            .unzip();
+        let mut pruned:VoseAlias<T, I> = VoseAlias::from_weights(elements, weights)?;
+        pruned.observer = std::mem::replace(&mut self.observer, ObserverSlot::empty());
+        *self = pruned;
+        Ok(removed)
+    }
+
+
+    /// Releases the excess capacity of the element vector, the tables and the internal index, such as the capacity left by the construction from an iterator or by a manual edition of `elements`.
+    pub fn shrink_to_fit(&mut self) {
+        self.elements.shrink_to_fit();
+        self.alias.shrink_to_fit();
+        self.prob.shrink_to_fit();
+        self.slot_prob.shrink_to_fit();
+        self.slot_alias.shrink_to_fit();
+        self.index.shrink_to_fit();
+        self.probabilities.shrink_to_fit();
+        self.input_probabilities.shrink_to_fit();
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+    use crate::WeightedSampler;
+
+    #[test]
+    fn prune_and_shrink() {
+        let mut va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.25, 0.25, 0.0]);
+        assert_eq!(va.prune_below(0.0), Ok(0));
+        assert_eq!(va.prune_below(0.1), Ok(1));
+        assert_eq!((va.elements.clone(), va.slot_prob.len()), (vec![1, 2, 3], 3));
+        assert!(!va.prob.contains_key(&4));
+
+        assert_eq!(va.prune_below(0.3), Ok(2));
+        assert_eq!(va.sample(), 1);
+        assert!((va.prob_of(&1) - 1.0).abs() < 1e-9);
+        assert_eq!(va.prune_below(2.0), Err(VoseAliasError::EmptyDistribution));
+        assert!(matches!(va.prune_below(f64::NAN), Err(VoseAliasError::InvalidParameter { name: "epsilon", .. })));
+        assert_eq!(va.elements, vec![1]);
+
+        va.elements.reserve(100);
+        va.slot_prob.reserve(100);
+        va.shrink_to_fit();
+        assert_eq!((va.elements.capacity(), va.slot_prob.capacity()), (1, 1));
+    }
+
+    #[test]
+    fn remove_rebuilds_tables() {
+        let mut va = VoseAlias::new(vec![1, 2, 3, 4, 5], vec![0.125, 0.25, 0.25, 0.125, 0.25]);
+        assert_eq!(va.remove(&2), Some(0.25));
+        let rebuilt = VoseAlias::from_weights(vec![1, 5, 3, 4], vec![0.125, 0.25, 0.25, 0.125]).unwrap();
+        assert_eq!(va, rebuilt);
+        assert_eq!((va.slot_prob.clone(), va.slot_alias.clone()), (rebuilt.slot_prob.clone(), rebuilt.slot_alias.clone()));
+        assert_eq!((va.prob.clone(), va.alias.clone(), va.index.clone()), (rebuilt.prob.clone(), rebuilt.alias.clone(), rebuilt.index.clone()));
+
+        assert_eq!(va.remove(&4), Some((1.0_f64 / 6.0) as f32));
+        assert_eq!(va.remove(&4), None);
+        assert_eq!(va.elements, vec![1, 5, 3]);
+        let mut single = VoseAlias::new(vec![1, 2], vec![1.0, 0.0]);
+        assert_eq!(single.remove(&1), None);
+        assert_eq!(single.remove(&2), Some(0.0));
+        assert_eq!(single.remove(&1), None);
+        assert_eq!(single.sample(), 1);
+    }
+}
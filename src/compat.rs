@@ -0,0 +1,132 @@
+//! Adapters for the generators of the other major versions of `rand_core` (features `rand-core-06` and `rand-core-09`).
+//!
+//! The functions taking a generator accept a `rand_core::RngCore` of `rand_core` 0.5, the version used by `rand` 0.7. A generator of `rand` 0.8 (`rand_core` 0.6) or `rand` 0.9 (`rand_core` 0.9) is wrapped in `RandCore06` or `RandCore09`, which forward the draws to it: the samples are the ones the generator would give to a crate depending on its own version of `rand_core`.
+
+#[cfg(feature = "rand-core-06")]
+use rand_core_06 as core_06;
+#[cfg(feature = "rand-core-09")]
+use rand_core_09 as core_09;
+
+
+/// A generator of `rand_core` 0.6 (`rand` 0.8), usable by the functions taking a generator.
+///
+/// Errors of `try_fill_bytes()` are not forwarded: the generators of `rand` 0.8 only fail when the operating system cannot provide entropy, and the sampling functions only draw through the infallible methods.
+///
+/// # Examples
+/// ```
+/// # use rand_08 as rand;
+/// use rand::SeedableRng;
+/// use vose_alias::{RandCore06, VoseAlias};
+///
+/// let va = VoseAlias::new(vec!["a", "b"], vec![0.75, 0.25]);
+/// let mut rng = RandCore06(rand::rngs::StdRng::seed_from_u64(7));
+/// let mut same_seed = RandCore06(rand::rngs::StdRng::seed_from_u64(7));
+/// assert_eq!(va.sample_with(&mut rng), va.sample_with(&mut same_seed));
+/// ```
+#[cfg(feature = "rand-core-06")]
+#[derive(Debug, Clone)]
+pub struct RandCore06<R>(pub R);
+
+
+#[cfg(feature = "rand-core-06")]
+impl<R: core_06::RngCore> rand_core::RngCore for RandCore06<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest:&mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest:&mut [u8]) -> Result<(), rand_core::Error> {
+        self.0.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+
+#[cfg(feature = "rand-core-06")]
+impl<R: core_06::CryptoRng> rand_core::CryptoRng for RandCore06<R> {}
+
+
+/// A generator of `rand_core` 0.9 (`rand` 0.9), usable by the functions taking a generator.
+///
+/// # Examples
+/// ```
+/// # use rand_09 as rand;
+/// use vose_alias::{RandCore09, VoseAlias};
+///
+/// let va = VoseAlias::new(vec!["a", "b"], vec![1.0, 0.0]);
+/// assert_eq!(va.sample_with(&mut RandCore09(rand::rng())), "a");
+/// ```
+#[cfg(feature = "rand-core-09")]
+#[derive(Debug, Clone)]
+pub struct RandCore09<R>(pub R);
+
+
+#[cfg(feature = "rand-core-09")]
+impl<R: core_09::RngCore> rand_core::RngCore for RandCore09<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest:&mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest:&mut [u8]) -> Result<(), rand_core::Error> {
+        self.0.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+
+#[cfg(feature = "rand-core-09")]
+impl<R: core_09::CryptoRng> rand_core::CryptoRng for RandCore09<R> {}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VoseAlias;
+
+    #[cfg(feature = "rand-core-06")]
+    #[test]
+    fn rand_core_06_draws() {
+        use rand_08::SeedableRng;
+        let mut rng = RandCore06(rand_08::rngs::StdRng::seed_from_u64(1));
+        let mut reference = rand_08::rngs::StdRng::seed_from_u64(1);
+        assert_eq!(rand_core::RngCore::next_u64(&mut rng), core_06::RngCore::next_u64(&mut reference));
+
+        let va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.5, 0.0]);
+        for _ in 0..100 {
+            assert_ne!(va.sample_with(&mut rng), 3);
+        }
+    }
+
+    #[cfg(feature = "rand-core-09")]
+    #[test]
+    fn rand_core_09_draws() {
+        use rand_09::SeedableRng;
+        let mut rng = RandCore09(rand_09::rngs::StdRng::seed_from_u64(1));
+        let mut reference = rand_09::rngs::StdRng::seed_from_u64(1);
+        assert_eq!(rand_core::RngCore::next_u64(&mut rng), core_09::RngCore::next_u64(&mut reference));
+
+        let va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.5, 0.0]);
+        for _ in 0..100 {
+            assert_ne!(va.sample_with(&mut rng), 3);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Confidence intervals of the frequencies observed in a batch of samples, to check that a sampler draws its elements with the expected probabilities.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+
+
+/// The Wilson confidence interval of the frequency of an element, returned by `VoseAlias::confidence_intervals()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyInterval<T> {
+    /// The element.
+    pub element:T,
+    /// The number of samples of the element.
+    pub count:u64,
+    /// The observed frequency of the element (0 if there is no sample).
+    pub observed:f64,
+    /// The probability of the element in the distribution.
+    pub expected:f64,
+    /// The lower bound of the confidence interval of the frequency.
+    pub lower:f64,
+    /// The upper bound of the confidence interval of the frequency.
+    pub upper:f64,
+    /// Whether the probability of the element lies in the confidence interval. With a confidence level of 99%, about 1 element in 100 is expected to be flagged as inconsistent even if the sampler is correct.
+    pub consistent:bool,
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the Wilson score interval of the frequency of each element, in the order of `elements`, given the number of samples of each element among `n` samples, and flags the elements whose probability lies outside of their interval.
+    ///
+    /// The elements missing from `counts` have not been sampled. The Wilson interval stays meaningful for small counts and for frequencies close to 0 or 1, unlike the normal approximation. An element of probability 0 is flagged as soon as it is sampled.
+    ///
+    /// # Errors
+    /// An error is returned if `confidence` is not strictly between 0 and 1, if `counts` contains an element which is not part of the distribution, or if the counts sum to more than `n`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::collections::HashMap;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["heads", "tails"], vec![0.5, 0.5]);
+    /// let mut counts = HashMap::new();
+    /// counts.insert("heads", 620);
+    /// counts.insert("tails", 380);
+    ///
+    /// let intervals = va.confidence_intervals(&counts, 1000, 0.99).unwrap();
+    /// assert!((intervals[0].observed - 0.62).abs() < 1e-9);
+    /// assert!(intervals[0].lower > 0.5);
+    /// assert!(!intervals[0].consistent);
+    /// ```
+    pub fn confidence_intervals(&self, counts:&HashMap<T, u64>, n:u64, confidence:f64) -> Result<Vec<FrequencyInterval<T>>, VoseAliasError> {
+        if !(confidence > 0.0 && confidence < 1.0) {
+            return Err(VoseAliasError::InvalidParameter { name: "confidence", value: confidence });
+        }
+        if let Some(e) = counts.keys().find(|e| !self.index.contains_key(*e)) {
+            return Err(VoseAliasError::UnknownElement(format!("{:?}", e)));
+        }
+        let total = counts.values().fold(0_u64, |acc, c| acc.saturating_add(*c));
+        if total > n {
+            return Err(VoseAliasError::InvalidParameter { name: "n", value: n as f64 });
+        }
+
+        // two-sided interval: the quantile of the standard normal distribution at (1 + confidence) / 2
+        let z = normal_quantile((1.0 + confidence) / 2.0);
+        let samples = n as f64;
+        let intervals = self.elements.iter().zip(self.probabilities.iter()).map(|(e, expected)| {
+            let count = counts.get(e).copied().unwrap_or(0);
+            let (observed, lower, upper) = if n == 0 {
+                (0.0, 0.0, 1.0)
+            }
+            else {
+                let observed = count as f64 / samples;
+                let center = (observed + z * z / (2.0 * samples)) / (1.0 + z * z / samples);
+                let half_width = z / (1.0 + z * z / samples) * (observed * (1.0 - observed) / samples + z * z / (4.0 * samples * samples)).sqrt();
+                (observed, (center - half_width).max(0.0), (center + half_width).min(1.0))
+            };
+            FrequencyInterval {
+                element: *e,
+                count,
+                observed,
+                expected: *expected,
+                lower,
+                upper,
+                consistent: lower <= *expected && *expected <= upper,
+            }
+        }).collect();
+        Ok(intervals)
+    }
+}
+
+
+/// Returns the quantile of the standard normal distribution at `p`, in ]0, 1[, with a relative error below 1.2e-9 (Acklam's rational approximation).
+fn normal_quantile(p:f64) -> f64 {
+    const A:[f64; 6] = [-3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02, 1.38357751867269e+02, -3.066479806614716e+01, 2.506628277459239e+00];
+    const B:[f64; 5] = [-5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02, 6.680131188771972e+01, -1.328068155288572e+01];
+    const C:[f64; 6] = [-7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00, -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00];
+    const D:[f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00, 3.754408661907416e+00];
+    const P_LOW:f64 = 0.02425;
+
+    let tail = |q:f64| {
+        let q = (-2.0 * q.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail(p)
+    }
+    else if p > 1.0 - P_LOW {
+        -tail(1.0 - p)
+    }
+    else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn wilson_intervals() {
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+        assert!((normal_quantile(0.005) + 2.575829).abs() < 1e-6);
+
+        let va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.5, 0.0]);
+        let counts:HashMap<i32, u64> = vec![(1, 5), (2, 5)].into_iter().collect();
+        let intervals = va.confidence_intervals(&counts, 10, 0.95).unwrap();
+        // Wilson interval of 5 successes out of 10 at 95%
+        assert!((intervals[0].lower - 0.2366).abs() < 1e-4);
+        assert!((intervals[0].upper - 0.7634).abs() < 1e-4);
+        assert!(intervals[0].consistent && intervals[1].consistent);
+        assert_eq!((intervals[2].count, intervals[2].lower), (0, 0.0));
+        assert!(intervals[2].consistent);
+
+        let mut rng = rand::thread_rng();
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.4, 0.3, 0.2, 0.1]);
+        let mut counts:HashMap<i32, u64> = HashMap::new();
+        for _ in 0..10000 {
+            *counts.entry(va.sample_with(&mut rng)).or_insert(0) += 1;
+        }
+        let intervals = va.confidence_intervals(&counts, 10000, 0.9999).unwrap();
+        assert!(intervals.iter().all(|i| i.consistent));
+    }
+
+    #[test]
+    fn invalid_intervals() {
+        let va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        let counts:HashMap<i32, u64> = vec![(1, 5), (2, 5)].into_iter().collect();
+        assert!(matches!(va.confidence_intervals(&counts, 10, 1.0), Err(VoseAliasError::InvalidParameter { .. })));
+        assert!(matches!(va.confidence_intervals(&counts, 9, 0.95), Err(VoseAliasError::InvalidParameter { .. })));
+        let counts:HashMap<i32, u64> = vec![(3, 5)].into_iter().collect();
+        assert!(matches!(va.confidence_intervals(&counts, 10, 0.95), Err(VoseAliasError::UnknownElement(_))));
+    }
+}
@@ -0,0 +1,197 @@
+//! Numerical diagnostics of the tables: how faithfully they represent the requested probabilities.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError, COIN_SCALE};
+
+
+/// Number of elements listed in `Diagnostics::deviations`.
+const DIAGNOSTICS_TOP_ELEMENTS:usize = 5;
+
+
+/// A report on how faithfully the tables of a distribution represent the probabilities they were built from, returned by `VoseAlias::try_new_with_diagnostics()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics<T> {
+    /// The number of elements with a probability of 0.
+    pub zero_weights:usize,
+    /// The ratio between the largest and the smallest positive probabilities. A large ratio means the smallest probabilities are close to the precision of the tables.
+    pub weight_ratio:f64,
+    /// The largest error on a probability caused by storing the probabilities multiplied by the number of elements as `f32`, before building the tables.
+    pub scaling_error:f64,
+    /// The total variation distance between the requested probabilities and the ones represented by the tables: half the sum of the absolute differences.
+    pub total_variation:f64,
+    /// The elements whose probability in the tables deviates the most from the requested one, the largest deviation first (at most 5 elements).
+    pub deviations:Vec<Deviation<T>>,
+}
+
+
+/// The requested and represented probabilities of an element, listed in `Diagnostics::deviations`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation<T> {
+    /// The element.
+    pub element:T,
+    /// The probability given at construction.
+    pub requested:f64,
+    /// The probability represented by the tables, before the resolution of the coin is taken into account (see `VoseAlias::effective_probability()`).
+    pub represented:f64,
+}
+
+
+impl<T> VoseAlias<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Builds the Vose-Alias object like `VoseAlias::try_new()`, and returns it with a report on the numerical quality of its tables.
+    ///
+    /// The same errors as `VoseAlias::try_new()` are returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let (va, diagnostics) = VoseAlias::try_new_with_diagnostics(vec!["a", "b", "c"], vec![0.9999, 0.0001, 0.0]).unwrap();
+    /// assert_eq!(diagnostics.zero_weights, 1);
+    /// assert!(diagnostics.weight_ratio > 9000.0);
+    /// assert!(diagnostics.total_variation < 1e-4);
+    /// assert_eq!(va.elements.len(), 3);
+    /// ```
+    pub fn try_new_with_diagnostics(element_vector:Vec<T>, probability_vector:Vec<f32>) -> Result<(VoseAlias<T>, Diagnostics<T>), VoseAliasError> {
+        let requested:Vec<f64> = probability_vector.iter().map(|p| f64::from(*p)).collect();
+        let va = VoseAlias::try_new(element_vector, probability_vector)?;
+        let diagnostics = va.diagnostics(&requested);
+        Ok((va, diagnostics))
+    }
+
+
+    /// Compares the probabilities of the tables with the requested ones, given in the order of `elements`.
+    fn diagnostics(&self, requested:&[f64]) -> Diagnostics<T> {
+        let size = requested.len() as f64;
+        let positive = requested.iter().filter(|p| **p > 0.0);
+        let largest = positive.clone().fold(0.0, |acc:f64, p| acc.max(*p));
+        let smallest = positive.fold(f64::INFINITY, |acc:f64, p| acc.min(*p));
+        let scaling_error = requested.iter().fold(0.0, |acc:f64, p| {
+            let scaled = p * size;
+            acc.max((f64::from(scaled as f32) - scaled).abs() / size)
+        });
+
+        let represented = self.table_probabilities();
+        let mut deviations:Vec<Deviation<T>> = self.elements.iter().zip(requested.iter().zip(represented.iter()))
+            .map(|(e, (r, p))| Deviation { element: *e, requested: *r, represented: *p })
+            .collect();
+        let total_variation = deviations.iter().fold(0.0, |acc, d| acc + (d.represented - d.requested).abs()) / 2.0;
+        let gap = |d:&Deviation<T>| (d.represented - d.requested).abs();
+        deviations.sort_by(|a, b| gap(b).partial_cmp(&gap(a)).unwrap_or(Ordering::Equal));
+        deviations.truncate(DIAGNOSTICS_TOP_ELEMENTS);
+
+        Diagnostics {
+            zero_weights: requested.iter().filter(|p| **p == 0.0).count(),
+            weight_ratio: largest / smallest,
+            scaling_error,
+            total_variation,
+            deviations,
+        }
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the probability that `VoseAlias::sample()` actually returns `element`, or `None` if it is not part of the distribution.
+    ///
+    /// The probability is computed from the tables as they are used when sampling: the coin is an integer drawn in [0, 2^24), and the element of a column is kept if the coin is strictly lower than its probability × 2^24. So the probability of keeping the element of a column is rounded up to a multiple of 2^-24 (an element of probability 0 is never kept). Comparing this with the requested probability measures the fidelity of the sampler, which the probabilities stored in the tables alone do not show.
+    /// Only the columns of `element` and the ones aliased to it are counted, in a single pass over the alias table without allocating: use `VoseAlias::effective_probabilities()` to get the probabilities of all the elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["a", "b", "c"], vec![0.7, 0.3, 0.0]);
+    /// assert!((va.effective_probability(&"b").unwrap() - 0.3).abs() < 1e-6);
+    /// assert_eq!(va.effective_probability(&"c"), Some(0.0));
+    /// ```
+    pub fn effective_probability(&self, element:&T) -> Option<f64> {
+        let i = *self.index.get(element)?;
+        let size = self.slot_prob.len();
+        // the element gets the kept share of its own column and the rest of the columns aliased to it
+        let mass = (0..size).fold(0.0, |acc, j| {
+            let a = self.slot_alias[j].to_usize();
+            match (j == i, a == j) {
+                (true, true) => acc + 1.0,
+                (true, false) => acc + self.kept_share(j),
+                (false, false) if a == i => acc + 1.0 - self.kept_share(j),
+                _ => acc,
+            }
+        });
+        Some(mass / size as f64)
+    }
+
+
+    /// Returns the probability that `VoseAlias::sample()` actually returns each element, in the order of `elements`. See `VoseAlias::effective_probability()`.
+    pub fn effective_probabilities(&self) -> Vec<f64> {
+        let size = self.elements.len();
+        let mut mass:Vec<f64> = vec![0.0; size];
+        for i in 0..size {
+            let a = self.slot_alias[i].to_usize();
+            if a == i {
+                mass[i] += 1.0;
+            }
+            else {
+                let kept = self.kept_share(i);
+                mass[i] += kept;
+                mass[a] += 1.0 - kept;
+            }
+        }
+        mass.iter().map(|m| m / size as f64).collect()
+    }
+
+
+    /// Returns the probability of keeping the element of column `i` rather than its alias.
+    fn kept_share(&self, i:usize) -> f64 {
+        // the number of coins c in [0, 2^24) such that c < p × 2^24, as compared by the sampling
+        let threshold = f64::from(self.slot_prob[i] * COIN_SCALE);
+        threshold.ceil().clamp(0.0, f64::from(COIN_SCALE)) / f64::from(COIN_SCALE)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn construction_report() {
+        let (_, diagnostics) = VoseAlias::try_new_with_diagnostics(vec![1, 2, 3, 4, 5, 6], vec![0.5, 0.25, 0.125, 0.125, 0.0, 0.0]).unwrap();
+        assert_eq!(diagnostics.zero_weights, 2);
+        assert_eq!(diagnostics.weight_ratio, 4.0);
+        assert_eq!(diagnostics.scaling_error, 0.0);
+        assert!(diagnostics.total_variation < 1e-6);
+        assert_eq!(diagnostics.deviations.len(), 5);
+        assert!(diagnostics.deviations.windows(2).all(|w| (w[0].represented - w[0].requested).abs() >= (w[1].represented - w[1].requested).abs()));
+
+        assert!(VoseAlias::try_new_with_diagnostics(vec![1], vec![0.5]).is_err());
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn effective_probabilities() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+        let effective = va.effective_probabilities();
+        assert!((effective.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(effective.iter().zip(va.probabilities.iter()).all(|(e, p)| (e - p).abs() < 1e-6));
+        for (e, p) in va.elements.iter().zip(effective.iter()) {
+            assert_eq!(va.effective_probability(e), Some(*p));
+        }
+        assert_eq!(va.effective_probability(&5), None);
+
+        let mut rng = rand::thread_rng();
+        let n = 200000;
+        let fours = (0..n).filter(|_| va.sample_with(&mut rng) == 4).count() as f64 / n as f64;
+        assert!((fours - effective[3]).abs() < 0.005);
+    }
+}
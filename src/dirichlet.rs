@@ -0,0 +1,239 @@
+//! A categorical distribution learned from observations, with a Dirichlet prior.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{check_weights, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A sampler learning a categorical distribution from observed elements, by Bayesian updating of a Dirichlet prior.
+///
+/// The sampler stores one concentration parameter per element, initialized with the prior and increased by 1 every time the element is observed. It can sample:
+/// - from the posterior mean, where each element has a probability proportional to its concentration (`DirichletSampler::sample()`)
+/// - a random categorical distribution drawn from the posterior, for Thompson sampling (`DirichletSampler::sample_categorical()`)
+///
+/// The tables of the posterior mean are rebuilt lazily, on the first sample after an observation.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::DirichletSampler;
+///
+/// let mut sampler = DirichletSampler::with_uniform_prior(vec!["rock", "paper", "scissors"], 1.0).unwrap();
+/// for _ in 0..7 {
+///     sampler.observe(&"rock");
+/// }
+/// assert_eq!(sampler.concentration(&"rock"), Some(8.0));
+/// assert!((sampler.posterior_mean()[0] - 0.8).abs() < 1e-9);
+///
+/// // a plausible distribution of the opponent's moves
+/// let categorical = sampler.sample_categorical();
+/// let next_move = categorical.sample();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DirichletSampler<T> where T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    index:HashMap<T, usize>,
+    concentrations:Vec<f64>,
+    mean:Option<VoseAlias<T>>,
+}
+
+
+impl<T> DirichletSampler<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns a sampler over the given elements, with the given prior concentration parameters.
+    ///
+    /// An error is returned if both vectors do not have the same size, if the element vector is empty or contains duplicates, or if a concentration is not positive and finite.
+    pub fn new(element_vector:Vec<T>, prior:Vec<f64>) -> Result<DirichletSampler<T>, VoseAliasError> {
+        match check_weights(element_vector.len(), &prior) {
+            Ok(_) | Err(VoseAliasError::ZeroTotalWeight) => (),
+            Err(e) => return Err(e),
+        }
+        // unlike a weight, a concentration of 0 does not describe a distribution
+        if let Some(a) = prior.iter().find(|a| **a == 0.0) {
+            return Err(VoseAliasError::InvalidWeight(*a));
+        }
+        let mut index:HashMap<T, usize> = HashMap::with_capacity(element_vector.len());
+        for (i, e) in element_vector.iter().enumerate() {
+            if index.insert(*e, i).is_some() {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+
+        Ok(DirichletSampler {
+            elements: element_vector,
+            index,
+            concentrations: prior,
+            mean: None,
+        })
+    }
+
+
+    /// Returns a sampler over the given elements, with the same prior concentration `alpha` for all of them (1 is the uniform prior).
+    ///
+    /// An error is returned if the element vector is empty or contains duplicates, or if `alpha` is not positive and finite.
+    pub fn with_uniform_prior(element_vector:Vec<T>, alpha:f64) -> Result<DirichletSampler<T>, VoseAliasError> {
+        let prior = vec![alpha; element_vector.len()];
+        DirichletSampler::new(element_vector, prior)
+    }
+
+
+    /// Returns the elements of the sampler.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the concentration parameter of an element, or `None` if it is not in the sampler.
+    pub fn concentration(&self, element:&T) -> Option<f64> {
+        self.index.get(element).map(|i| self.concentrations[*i])
+    }
+
+
+    /// Records an observation of `element`, increasing its concentration by 1. Returns `false` if the element is not in the sampler.
+    pub fn observe(&mut self, element:&T) -> bool {
+        match self.index.get(element) {
+            Some(i) => {
+                self.concentrations[*i] += 1.0;
+                self.mean = None;
+                true
+            },
+            None => false,
+        }
+    }
+
+
+    /// Returns the probabilities of the posterior mean distribution, in the order of `DirichletSampler::elements()`.
+    pub fn posterior_mean(&self) -> Vec<f64> {
+        let total:f64 = self.concentrations.iter().sum();
+        self.concentrations.iter().map(|a| a / total).collect()
+    }
+
+
+    /// Returns an element sampled from the posterior mean distribution, using the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&mut self) -> T {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns an element sampled from the posterior mean distribution, using the given generator.
+    pub fn sample_with<R: RngCore + ?Sized>(&mut self, rng:&mut R) -> T {
+        let (elements, concentrations) = (&self.elements, &self.concentrations);
+        self.mean.get_or_insert_with(|| mean_table(elements, concentrations)).sample_with(rng)
+    }
+
+
+    /// Returns a categorical distribution drawn from the posterior Dirichlet distribution, using the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_categorical(&self) -> VoseAlias<T> {
+        rng::with_default_rng(|rng| self.sample_categorical_with(rng))
+    }
+
+
+    /// Returns a categorical distribution drawn from the posterior Dirichlet distribution, using the given generator.
+    ///
+    /// The probabilities are obtained by drawing one Gamma(αᵢ, 1) variable per element and normalizing them.
+    pub fn sample_categorical_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> VoseAlias<T> {
+        let gammas:Vec<f64> = self.concentrations.iter().map(|a| sample_gamma(*a, rng)).collect();
+        // with tiny concentrations, all the draws can underflow to 0
+        match VoseAlias::from_positive_weights(&self.elements, &gammas) {
+            Some(table) => table,
+            None => mean_table(&self.elements, &self.concentrations),
+        }
+    }
+}
+
+
+/// Builds the tables of the posterior mean distribution.
+fn mean_table<T>(elements:&[T], concentrations:&[f64]) -> VoseAlias<T>
+where T: Copy + Hash + Eq + Debug {
+    match VoseAlias::from_weights(elements.to_vec(), concentrations.to_vec()) {
+        Ok(table) => table,
+        Err(e) => panic!("Internal error. The concentration parameters are not valid: {}. If this happened, please fill in an issue report.", e),
+    }
+}
+
+
+/// Draws a Gamma(alpha, 1) variable with the method of Marsaglia and Tsang, boosted by U^(1/alpha) for alpha < 1.
+fn sample_gamma<R: RngCore + ?Sized>(alpha:f64, rng:&mut R) -> f64 {
+    if alpha < 1.0 {
+        let u:f64 = rng.gen();
+        return sample_gamma(alpha + 1.0, rng) * u.powf(1.0 / alpha);
+    }
+
+    let d = alpha - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = sample_standard_normal(rng);
+        let v = 1.0 + c * x;
+        if v <= 0.0 {
+            continue;
+        }
+        let v = v * v * v;
+        let u:f64 = rng.gen();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+
+/// Draws a standard normal variable with the Box-Muller transform.
+fn sample_standard_normal<R: RngCore + ?Sized>(rng:&mut R) -> f64 {
+    // 1 - gen() is in (0, 1], so its logarithm is finite
+    let u1:f64 = 1.0 - rng.gen::<f64>();
+    let u2:f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+    use crate::WeightedSampler;
+
+    #[test]
+    fn gamma_mean() {
+        let mut rng = rand::thread_rng();
+        for alpha in [0.5, 1.0, 4.0].iter() {
+            let mean = (0..20000).map(|_| sample_gamma(*alpha, &mut rng)).sum::<f64>() / 20000.0;
+            assert!((mean - alpha).abs() < 0.1 * alpha.max(1.0), "mean {} for alpha {}", mean, alpha);
+        }
+    }
+
+    #[test]
+    fn posterior_updates() {
+        let mut sampler = DirichletSampler::new(vec![1, 2], vec![1.0, 3.0]).unwrap();
+        assert_eq!(sampler.posterior_mean(), vec![0.25, 0.75]);
+        assert!(sampler.observe(&1));
+        assert!(!sampler.observe(&3));
+        assert_eq!(sampler.posterior_mean(), vec![0.4, 0.6]);
+        let mut rng = rand::thread_rng();
+        let ones = (0..10000).filter(|_| sampler.sample_with(&mut rng) == 1).count();
+        assert!(ones > 3700 && ones < 4300);
+        assert!(DirichletSampler::new(vec![1, 2], vec![1.0, 0.0]).is_err());
+    }
+
+    #[test]
+    fn thompson_sampling() {
+        let mut sampler = DirichletSampler::with_uniform_prior(vec!['a', 'b'], 1.0).unwrap();
+        for _ in 0..1000 {
+            sampler.observe(&'a');
+        }
+        let categorical = sampler.sample_categorical();
+        assert!(categorical.prob_of(&'a') > 0.98);
+    }
+}
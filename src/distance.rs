@@ -0,0 +1,112 @@
+//! Distances between two distributions, computed over the union of their supports, to measure how much a distribution drifted from another.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{SlotIndex, VoseAlias};
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the total variation distance between this distribution and `other`: half the sum of the absolute differences of the probabilities, between 0 (same distributions) and 1 (disjoint supports).
+    ///
+    /// The elements of one distribution that are not part of the other have a probability of 0 in the other one.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let release1 = VoseAlias::new(vec!["cat", "dog"], vec![0.5, 0.5]);
+    /// let release2 = VoseAlias::new(vec!["dog", "bird"], vec![0.75, 0.25]);
+    /// assert!((release1.total_variation(&release2) - 0.5).abs() < 1e-9);
+    /// assert_eq!(release1.total_variation(&release1), 0.0);
+    /// ```
+    pub fn total_variation<J: SlotIndex>(&self, other:&VoseAlias<T, J>) -> f64 {
+        self.paired_probabilities(other).iter().fold(0.0, |acc, (p, q)| acc + (p - q).abs()) / 2.0
+    }
+
+
+    /// Returns the Hellinger distance between this distribution and `other`: √(1 - Σ √(p q)), between 0 (same distributions) and 1 (disjoint supports).
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let release1 = VoseAlias::new(vec!["cat", "dog"], vec![0.5, 0.5]);
+    /// let release2 = VoseAlias::new(vec!["bird"], vec![1.0]);
+    /// assert_eq!(release1.hellinger_distance(&release2), 1.0);
+    /// ```
+    pub fn hellinger_distance<J: SlotIndex>(&self, other:&VoseAlias<T, J>) -> f64 {
+        let coefficient = self.paired_probabilities(other).iter().fold(0.0, |acc, (p, q)| acc + (p * q).sqrt());
+        // rounding errors can push the coefficient slightly above 1
+        (1.0 - coefficient).max(0.0).sqrt()
+    }
+
+
+    /// Returns the Jensen-Shannon divergence between this distribution and `other`, in bits: the mean of the Kullback-Leibler divergences of both distributions to their mixture M = (P + Q) / 2.
+    ///
+    /// Unlike the Kullback-Leibler divergence, it is symmetric and always finite, between 0 (same distributions) and 1 (disjoint supports).
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let release1 = VoseAlias::new(vec!["cat", "dog"], vec![0.5, 0.5]);
+    /// let release2 = VoseAlias::new(vec!["cat", "dog"], vec![0.9, 0.1]);
+    /// let drift = release1.js_divergence(&release2);
+    /// assert!(drift > 0.1 && drift < 0.2);
+    /// assert!((drift - release2.js_divergence(&release1)).abs() < 1e-12);
+    /// ```
+    pub fn js_divergence<J: SlotIndex>(&self, other:&VoseAlias<T, J>) -> f64 {
+        let divergence = self.paired_probabilities(other).iter().fold(0.0, |acc, (p, q)| {
+            let m = (p + q) / 2.0;
+            let term = |x:f64| if x > 0.0 { x * (x / m).log2() } else { 0.0 };
+            acc + term(*p) + term(*q)
+        }) / 2.0;
+        divergence.max(0.0)
+    }
+
+
+    /// Returns the probabilities of each element of the union of the supports, in this distribution and in `other`.
+    fn paired_probabilities<J: SlotIndex>(&self, other:&VoseAlias<T, J>) -> Vec<(f64, f64)> {
+        let probability = |va:&VoseAlias<T, J>, e:&T| va.index.get(e).map_or(0.0, |i| va.probabilities[*i]);
+        let mut pairs:Vec<(f64, f64)> = self.elements.iter().zip(self.probabilities.iter())
+            .map(|(e, p)| (*p, probability(other, e)))
+            .collect();
+        pairs.extend(other.elements.iter().zip(other.probabilities.iter())
+            .filter(|(e, _)| !self.index.contains_key(*e))
+            .map(|(_, q)| (0.0, *q)));
+        pairs
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let p = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.25, 0.25]);
+        let q = VoseAlias::new(vec![3, 2, 1], vec![0.25, 0.25, 0.5]);
+        assert!(p.total_variation(&q) < 1e-9);
+        assert!(p.hellinger_distance(&q) < 1e-6);
+        assert!(p.js_divergence(&q) < 1e-9);
+
+        let r:VoseAlias<i32, u16> = VoseAlias::new_with_index(vec![4, 5], vec![0.5, 0.5]);
+        assert!((p.total_variation(&r) - 1.0).abs() < 1e-9);
+        assert!((p.hellinger_distance(&r) - 1.0).abs() < 1e-9);
+        assert!((p.js_divergence(&r) - 1.0).abs() < 1e-9);
+
+        let s = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        assert!((p.total_variation(&s) - 0.25).abs() < 1e-9);
+        assert!((p.total_variation(&s) - s.total_variation(&p)).abs() < 1e-12);
+        let hellinger = p.hellinger_distance(&s);
+        assert!(hellinger > 0.0 && hellinger < 1.0);
+    }
+}
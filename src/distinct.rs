@@ -0,0 +1,169 @@
+//! Weighted sampling of several distinct elements (sampling without replacement).
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+
+
+/// Orders the exponential keys increasingly.
+fn by_key(a:&(f64, usize), b:&(f64, usize)) -> Ordering {
+    a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal)
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns `k` distinct elements, sampled without replacement according to the distribution, using the exponential keys method of Efraimidis and Spirakis.
+    ///
+    /// Each element `e` with probability `p` receives the random key `-ln(u) / p`, with `u` uniform in (0, 1], and the `k` elements with the smallest keys are returned, in the order in which they would have been drawn one by one. The method makes a single pass over the elements, and the keys can be computed independently of each other.
+    ///
+    /// Elements with a probability of 0 are never returned: if fewer than `k` elements have a positive probability, all of them are returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.3, 0.0]);
+    /// let elements = va.sample_k_distinct_exp(2, &mut rand::thread_rng());
+    /// assert_eq!(elements.len(), 2);
+    /// assert!(elements[0] != elements[1]);
+    /// assert!(!elements.contains(&4));
+    /// # }
+    /// ```
+    pub fn sample_k_distinct_exp<R: RngCore + ?Sized>(&self, k:usize, rng:&mut R) -> Vec<T> {
+        self.sample_k_distinct_indexes(k, rng).iter().map(|i| self.elements[*i]).collect()
+    }
+
+
+    /// Returns the indexes in `elements` of `k` distinct elements sampled without replacement, like `VoseAlias::sample_k_distinct_exp()`.
+    pub(crate) fn sample_k_distinct_indexes<R: RngCore + ?Sized>(&self, k:usize, rng:&mut R) -> Vec<usize> {
+        let mut keys = self.exponential_keys(rng);
+        if k < keys.len() {
+            keys.select_nth_unstable_by(k, by_key);
+            keys.truncate(k);
+        }
+        keys.sort_unstable_by(by_key);
+        keys.into_iter().map(|(_, i)| i).collect()
+    }
+
+
+    /// Returns distinct elements sampled without replacement according to the distribution, until the total cost of the sampled elements would exceed `budget`.
+    ///
+    /// The elements are drawn one by one among the ones not sampled yet, the probabilities being renormalized after each draw, and the first element whose cost does not fit in the remaining budget stops the sampling: it is not returned, and no cheaper element is drawn after it. The elements are returned in the order in which they were drawn.
+    /// `cost` is called on each element with a positive probability, and elements with a probability of 0 are never returned.
+    ///
+    /// An error is returned if `budget` or one of the costs is negative or not finite.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// // the monsters of an encounter, with their difficulty
+    /// let difficulty = |monster:&&str| match *monster { "goblin" => 1.0, "orc" => 3.0, _ => 8.0 };
+    /// let monsters = VoseAlias::new(vec!["goblin", "orc", "troll"], vec![0.6, 0.3, 0.1]);
+    /// let encounter = monsters.sample_within_budget(10.0, difficulty, &mut rand::thread_rng()).unwrap();
+    /// assert!(encounter.iter().map(difficulty).sum::<f64>() <= 10.0);
+    /// # }
+    /// ```
+    pub fn sample_within_budget<R, F>(&self, budget:f64, mut cost:F, rng:&mut R) -> Result<Vec<T>, VoseAliasError>
+    where R: RngCore + ?Sized, F: FnMut(&T) -> f64 {
+        if !budget.is_finite() || budget < 0.0 {
+            return Err(VoseAliasError::InvalidParameter { name: "budget", value: budget });
+        }
+        // sorting by exponential keys gives the order of successive draws without replacement
+        let mut keys = self.exponential_keys(rng);
+        keys.sort_unstable_by(by_key);
+
+        let mut costs:Vec<f64> = Vec::with_capacity(keys.len());
+        for (_, i) in keys.iter() {
+            let c = cost(&self.elements[*i]);
+            if !c.is_finite() || c < 0.0 {
+                return Err(VoseAliasError::InvalidParameter { name: "cost", value: c });
+            }
+            costs.push(c);
+        }
+
+        let mut remaining = budget;
+        let mut sampled:Vec<T> = Vec::new();
+        for ((_, i), c) in keys.iter().zip(costs) {
+            if c > remaining {
+                break;
+            }
+            remaining -= c;
+            sampled.push(self.elements[*i]);
+        }
+        Ok(sampled)
+    }
+
+
+    /// Returns the exponential key of each element with a positive probability, along with its index.
+    fn exponential_keys<R: RngCore + ?Sized>(&self, rng:&mut R) -> Vec<(f64, usize)> {
+        self.probabilities.iter().enumerate()
+            .filter(|(_, p)| **p > 0.0)
+            .map(|(i, p)| {
+                let u:f64 = 1.0 - rng.gen::<f64>();
+                (-u.ln() / p, i)
+            })
+            .collect()
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distinct_elements() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4, 5], vec![0.4, 0.3, 0.1, 0.2, 0.0]);
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let mut elements = va.sample_k_distinct_exp(3, &mut rng);
+            assert_eq!(elements.len(), 3);
+            elements.sort_unstable();
+            elements.dedup();
+            assert_eq!(elements.len(), 3);
+            assert!(!elements.contains(&5));
+        }
+        // only 4 elements can be sampled
+        assert_eq!(va.sample_k_distinct_exp(10, &mut rng).len(), 4);
+        assert!(va.sample_k_distinct_exp(0, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn first_element_follows_distribution() {
+        let va = VoseAlias::new(vec![1, 2], vec![0.9, 0.1]);
+        let mut rng = rand::thread_rng();
+        let ones = (0..10000).filter(|_| va.sample_k_distinct_exp(1, &mut rng)[0] == 1).count();
+        assert!(ones > 8500 && ones < 9500);
+    }
+
+    #[test]
+    fn budget() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.4, 0.3, 0.3, 0.0]);
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let elements = va.sample_within_budget(5.0, |e| *e as f64, &mut rng).unwrap();
+            assert!(elements.iter().sum::<i32>() <= 5);
+            assert!(!elements.is_empty());
+            assert!(!elements.contains(&4));
+        }
+        // everything fits
+        assert_eq!(va.sample_within_budget(6.0, |e| *e as f64, &mut rng).unwrap().len(), 3);
+        assert!(va.sample_within_budget(0.5, |e| *e as f64, &mut rng).unwrap().is_empty());
+        assert!(va.sample_within_budget(-1.0, |e| *e as f64, &mut rng).is_err());
+        assert!(va.sample_within_budget(1.0, |_| f64::NAN, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Constructors of index samplers for common discrete distributions.
+
+use crate::{VoseAlias, VoseAliasError};
+
+
+impl VoseAlias<usize> {
+
+    /// Returns the Vose-Alias object sampling the ranks `0..n` with Zipf's law of exponent `s`: the element `i` has a probability proportional to `1 / (i + 1)^s`, so that rank 0 is the most frequent.
+    ///
+    /// An error is returned if `n` is 0, or if `s` is negative or not finite.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// // key popularity of a synthetic cache workload
+    /// let keys = VoseAlias::zipf(1000, 1.1).unwrap();
+    /// assert!(keys.prob_of(&0) > keys.prob_of(&1));
+    ///
+    /// let uniform = VoseAlias::zipf(4, 0.0).unwrap();
+    /// assert!((uniform.prob_of(&3) - 0.25).abs() < 1e-6);
+    /// ```
+    pub fn zipf(n:usize, s:f64) -> Result<VoseAlias<usize>, VoseAliasError> {
+        if !s.is_finite() || s < 0.0 {
+            return Err(VoseAliasError::InvalidParameter { name: "s", value: s });
+        }
+        VoseAlias::power_law(n, s)
+    }
+
+
+    /// Returns the Vose-Alias object sampling `0..n` with a power law of exponent `alpha`: the element `i` has a probability proportional to `(i + 1)^(-alpha)`.
+    ///
+    /// Unlike `VoseAlias::zipf()`, the exponent can be negative, giving increasing probabilities. The weights are computed relatively to the largest one, so that large exponents neither overflow nor underflow all the weights.
+    ///
+    /// An error is returned if `n` is 0 or if `alpha` is not finite.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let va = VoseAlias::power_law(3, -1.0).unwrap();
+    /// assert!((va.prob_of(&2) - 0.5).abs() < 1e-6);
+    /// ```
+    pub fn power_law(n:usize, alpha:f64) -> Result<VoseAlias<usize>, VoseAliasError> {
+        if !alpha.is_finite() {
+            return Err(VoseAliasError::InvalidParameter { name: "alpha", value: alpha });
+        }
+        // log of the largest weight, reached at 0 for positive exponents and at n - 1 otherwise
+        let max_log = if alpha >= 0.0 { 0.0 } else { -alpha * (n as f64).ln() };
+        VoseAlias::from_pmf(n, |i| (-alpha * ((i + 1) as f64).ln() - max_log).exp())
+    }
+
+
+    /// Returns the Vose-Alias object sampling `0..n`, the element `i` having a probability proportional to `pmf(i)`. The values of `pmf` do not need to sum to 1 over `0..n`: they are normalized, which truncates the distribution to `0..n`.
+    ///
+    /// `pmf` is called once per element, in increasing order.
+    /// An error is returned if `n` is 0, if a value is negative or not finite, or if all the values are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// // a triangular distribution over 0..4
+    /// let va = VoseAlias::from_pmf(4, |i| (i + 1) as f64).unwrap();
+    /// assert!((va.prob_of(&3) - 0.4).abs() < 1e-6);
+    /// ```
+    pub fn from_pmf<F: Fn(usize) -> f64>(n:usize, pmf:F) -> Result<VoseAlias<usize>, VoseAliasError> {
+        VoseAlias::from_weights((0..n).collect(), (0..n).map(pmf).collect())
+    }
+
+
+    /// Returns the Vose-Alias object sampling the Poisson distribution of mean `lambda`, truncated to `0..=max`.
+    ///
+    /// The probabilities are computed in log space and normalized over `0..=max`, so that large means and cutoffs do not overflow.
+    /// An error is returned if `lambda` is negative or not finite.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// // arrivals per tick of a queue simulation
+    /// let arrivals = VoseAlias::poisson(3.5, 30).unwrap();
+    /// assert!((arrivals.mean_by(|k| *k as f64) - 3.5).abs() < 1e-3);
+    /// ```
+    pub fn poisson(lambda:f64, max:usize) -> Result<VoseAlias<usize>, VoseAliasError> {
+        if !lambda.is_finite() || lambda < 0.0 {
+            return Err(VoseAliasError::InvalidParameter { name: "lambda", value: lambda });
+        }
+        // log(lambda^k / k!), up to a constant
+        let mut log_pmf:Vec<f64> = Vec::with_capacity(max + 1);
+        log_pmf.push(0.0);
+        for k in 1..=max {
+            log_pmf.push(log_pmf[k - 1] + lambda.ln() - (k as f64).ln());
+        }
+        VoseAlias::from_log_pmf(log_pmf)
+    }
+
+
+    /// Returns the Vose-Alias object sampling the number of successes among `trials` independent trials of success probability `p`, truncated to `0..=max`. If `max` is at least `trials`, the binomial distribution is not truncated.
+    ///
+    /// An error is returned if `p` is not between 0 and 1, or if no value of `0..=max` has a positive probability (when `p` is 1 and `max` is lower than `trials`).
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let heads = VoseAlias::binomial(4, 0.5, 4).unwrap();
+    /// assert!((heads.prob_of(&2) - 0.375).abs() < 1e-6);
+    /// ```
+    pub fn binomial(trials:usize, p:f64, max:usize) -> Result<VoseAlias<usize>, VoseAliasError> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(VoseAliasError::InvalidParameter { name: "p", value: p });
+        }
+        let top = max.min(trials);
+        // log(C(trials, k) p^k (1-p)^(trials-k)), up to a constant
+        let log_odds = p.ln() - (1.0 - p).ln();
+        let mut log_pmf:Vec<f64> = Vec::with_capacity(top + 1);
+        log_pmf.push(if p < 1.0 { 0.0 } else { f64::NEG_INFINITY });
+        for k in 1..=top {
+            let log_p = if p < 1.0 {
+                log_pmf[k - 1] + ((trials - k + 1) as f64).ln() - (k as f64).ln() + log_odds
+            }
+            else if k == trials { 0.0 } else { f64::NEG_INFINITY };
+            log_pmf.push(log_p);
+        }
+        VoseAlias::from_log_pmf(log_pmf)
+    }
+
+
+    /// Returns the Vose-Alias object sampling the number of failures before the first success of independent trials of success probability `p`, truncated to `0..=max`.
+    ///
+    /// An error is returned if `p` is not in (0, 1].
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let retries = VoseAlias::geometric(0.5, 2).unwrap();
+    /// assert!((retries.prob_of(&0) - 4.0 / 7.0).abs() < 1e-6);
+    /// ```
+    pub fn geometric(p:f64, max:usize) -> Result<VoseAlias<usize>, VoseAliasError> {
+        if !(p > 0.0 && p <= 1.0) {
+            return Err(VoseAliasError::InvalidParameter { name: "p", value: p });
+        }
+        VoseAlias::from_pmf(max + 1, |k| (1.0 - p).powf(k as f64))
+    }
+
+
+    /// Builds the Vose-Alias object sampling `0..n` from the logarithms of an unnormalized pmf, after shifting them so that the largest weight is 1.
+    fn from_log_pmf(log_pmf:Vec<f64>) -> Result<VoseAlias<usize>, VoseAliasError> {
+        let max_log = log_pmf.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if max_log == f64::NEG_INFINITY {
+            return Err(VoseAliasError::ZeroTotalWeight);
+        }
+        VoseAlias::from_pmf(log_pmf.len(), |k| (log_pmf[k] - max_log).exp())
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zipf_normalization() {
+        let va = VoseAlias::zipf(3, 1.0).unwrap();
+        // 1 + 1/2 + 1/3 = 11/6
+        assert!((va.probabilities[0] - 6.0 / 11.0).abs() < 1e-6);
+        assert!((va.probabilities[2] - 2.0 / 11.0).abs() < 1e-6);
+        let va = VoseAlias::power_law(5, -2000.0).unwrap();
+        assert!((va.probabilities[4] - 1.0).abs() < 1e-6);
+
+        assert_eq!(VoseAlias::zipf(0, 1.0).err(), Some(VoseAliasError::EmptyDistribution));
+        assert_eq!(VoseAlias::zipf(3, -1.0).err(), Some(VoseAliasError::InvalidParameter { name: "s", value: -1.0 }));
+        assert!(VoseAlias::power_law(3, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn pmf_closure() {
+        let va = VoseAlias::from_pmf(3, |i| if i == 1 { 0.0 } else { 0.2 }).unwrap();
+        assert_eq!(va.elements, vec![0, 1, 2]);
+        assert!((va.probabilities[2] - 0.5).abs() < 1e-6);
+
+        assert_eq!(VoseAlias::from_pmf(0, |_| 1.0).err(), Some(VoseAliasError::EmptyDistribution));
+        assert_eq!(VoseAlias::from_pmf(2, |i| i as f64 - 1.0).err(), Some(VoseAliasError::InvalidWeight(-1.0)));
+        assert_eq!(VoseAlias::from_pmf(2, |_| 0.0).err(), Some(VoseAliasError::ZeroTotalWeight));
+    }
+
+    #[test]
+    fn truncated_distributions() {
+        let va = VoseAlias::poisson(2.0, 2).unwrap();
+        // 1, 2, 2 before normalization
+        assert!((va.probabilities[0] - 0.2).abs() < 1e-6);
+        let va = VoseAlias::poisson(500.0, 2000).unwrap();
+        assert!((va.mean_by(|k| *k as f64) - 500.0).abs() < 1e-2);
+        assert_eq!(VoseAlias::poisson(0.0, 3).unwrap().probabilities[0], 1.0);
+
+        let va = VoseAlias::binomial(3, 0.5, 1).unwrap();
+        assert!((va.probabilities[1] - 0.75).abs() < 1e-6);
+        assert_eq!(VoseAlias::binomial(3, 1.0, 5).unwrap().elements, vec![0, 1, 2, 3]);
+        assert_eq!(VoseAlias::binomial(3, 1.0, 5).unwrap().probabilities[3], 1.0);
+        assert_eq!(VoseAlias::binomial(3, 0.0, 5).unwrap().probabilities[0], 1.0);
+        assert_eq!(VoseAlias::binomial(3, 1.0, 2).err(), Some(VoseAliasError::ZeroTotalWeight));
+
+        assert_eq!(VoseAlias::geometric(1.0, 3).unwrap().probabilities[0], 1.0);
+        assert!(VoseAlias::geometric(0.0, 3).is_err());
+        assert!(VoseAlias::poisson(-1.0, 3).is_err());
+        assert!(VoseAlias::binomial(3, 1.5, 3).is_err());
+    }
+}
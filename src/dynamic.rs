@@ -0,0 +1,412 @@
+//! A weighted sampler supporting fast weight updates, backed by a Fenwick tree (binary indexed tree) of prefix sums.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{check_weight, SlotIndex, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// Minimum number of weight updates between two rebuilds of the tree, which remove the rounding errors the updates accumulate. The tree is rebuilt after as many updates as there are elements, which keeps the amortized cost of an update in O(log n).
+const MIN_UPDATES_BEFORE_REBUILD:usize = 1024;
+
+
+/// A weighted sampler whose weights can be changed at any time.
+///
+/// Where a `VoseAlias` samples in constant time but must be rebuilt entirely when a probability changes, a `DynamicSampler` stores the prefix sums of the weights in a Fenwick tree: updating a weight and sampling both take O(log n).
+/// The weights do not need to sum to 1, each element is sampled with a probability proportional to its weight.
+///
+/// Workloads alternating between frequent updates and intensive sampling can convert between the two types: `DynamicSampler::to_vose_alias()` freezes the current weights into alias tables, and `DynamicSampler::from_vose_alias()` goes the other way.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::DynamicSampler;
+///
+/// let mut sampler = DynamicSampler::new();
+/// sampler.insert("common", 70.0).unwrap();
+/// sampler.insert("rare", 30.0).unwrap();
+/// sampler.update_weight(&"rare", 0.0).unwrap();
+/// assert_eq!(sampler.sample(), Some("common"));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DynamicSampler<T> where T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    index:HashMap<T, usize>,
+    weights:Vec<f64>,
+    // tree[i] holds the sum of the weights of the elements in (i - lowbit(i), i], 1-indexed
+    tree:Vec<f64>,
+    // the number of positive weights, so that the rounding errors of the tree never make them unreachable
+    positive:usize,
+    // the number of updates since the tree was last rebuilt
+    updates:usize,
+}
+
+
+impl<T> DynamicSampler<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns an empty sampler.
+    pub fn new() -> DynamicSampler<T> {
+        DynamicSampler {
+            elements: Vec::new(),
+            index: HashMap::new(),
+            weights: Vec::new(),
+            tree: vec![0.0],
+            positive: 0,
+            updates: 0,
+        }
+    }
+
+
+    /// Returns a sampler over the given elements and weights, built in O(n).
+    ///
+    /// An error is returned if both vectors do not have the same size, if an element appears twice, or if a weight is negative or not finite.
+    pub fn from_weights(element_vector:Vec<T>, weight_vector:Vec<f64>) -> Result<DynamicSampler<T>, VoseAliasError> {
+        if element_vector.len() != weight_vector.len() {
+            return Err(VoseAliasError::SizeMismatch { elements: element_vector.len(), probabilities: weight_vector.len() });
+        }
+        let mut index:HashMap<T, usize> = HashMap::with_capacity(element_vector.len());
+        for (i, e) in element_vector.iter().enumerate() {
+            if index.insert(*e, i).is_some() {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+        for w in &weight_vector {
+            check_weight(*w)?;
+        }
+
+        let mut sampler = DynamicSampler {
+            elements: element_vector,
+            index,
+            positive: weight_vector.iter().filter(|w| **w > 0.0).count(),
+            weights: weight_vector,
+            tree: Vec::new(),
+            updates: 0,
+        };
+        sampler.rebuild_tree();
+        Ok(sampler)
+    }
+
+
+    /// Returns a sampler with the same distribution as the given Vose-Alias object.
+    ///
+    /// An error (`VoseAliasError::CorruptTable`) is returned if the public fields of the object were modified so that they no longer match its tables, for instance if elements were added to `elements`.
+    pub fn from_vose_alias<I: SlotIndex>(va:&VoseAlias<T, I>) -> Result<DynamicSampler<T>, VoseAliasError> {
+        if !va.is_sampleable() {
+            return Err(VoseAliasError::CorruptTable(format!("{} elements for {} probabilities", va.elements.len(), va.probabilities.len())));
+        }
+        DynamicSampler::from_weights(va.elements.clone(), va.probabilities.clone())
+    }
+
+
+    /// Returns a Vose-Alias object sampling from the current weights, normalized into a distribution.
+    ///
+    /// An error is returned if the sampler is empty or if all the weights are 0.
+    pub fn to_vose_alias(&self) -> Result<VoseAlias<T>, VoseAliasError> {
+        VoseAlias::from_weights(self.elements.clone(), self.weights.clone())
+    }
+
+
+    /// Adds an element with the given weight, in O(log n).
+    ///
+    /// An error is returned if the element is already in the sampler, or if the weight is negative or not finite.
+    pub fn insert(&mut self, element:T, weight:f64) -> Result<(), VoseAliasError> {
+        check_weight(weight)?;
+        if self.index.contains_key(&element) {
+            return Err(VoseAliasError::DuplicateElement(format!("{:?}", element)));
+        }
+
+        let i = self.elements.len();
+        self.index.insert(element, i);
+        self.elements.push(element);
+        self.weights.push(weight);
+        if weight > 0.0 {
+            self.positive += 1;
+        }
+
+        // the new node covers the elements in (i + 1 - lowbit(i + 1), i + 1]
+        let node = i + 1;
+        let mut sum = weight;
+        let mut child = node - 1;
+        let start = node - lowbit(node);
+        while child > start {
+            sum += self.tree[child];
+            child -= lowbit(child);
+        }
+        self.tree.push(sum);
+        Ok(())
+    }
+
+
+    /// Changes the weight of an element, in O(log n), and returns its previous weight. The tree is rebuilt from the weights periodically, in O(n) every n updates, so that the rounding errors do not accumulate.
+    ///
+    /// An error is returned if the weight is negative or not finite. If the element is not in the sampler, `Ok(None)` is returned and nothing is changed.
+    pub fn update_weight(&mut self, element:&T, weight:f64) -> Result<Option<f64>, VoseAliasError> {
+        check_weight(weight)?;
+        let i = match self.index.get(element) {
+            Some(i) => *i,
+            None => return Ok(None),
+        };
+
+        let previous = self.weights[i];
+        self.weights[i] = weight;
+        if previous > 0.0 {
+            self.positive -= 1;
+        }
+        if weight > 0.0 {
+            self.positive += 1;
+        }
+        self.updates += 1;
+        if self.updates >= self.elements.len().max(MIN_UPDATES_BEFORE_REBUILD) {
+            self.rebuild_tree();
+            return Ok(Some(previous));
+        }
+        let delta = weight - previous;
+        let mut node = i + 1;
+        while node < self.tree.len() {
+            self.tree[node] += delta;
+            node += lowbit(node);
+        }
+        Ok(Some(previous))
+    }
+
+
+    /// Returns the weight of an element, or `None` if it is not in the sampler.
+    pub fn weight(&self, element:&T) -> Option<f64> {
+        self.index.get(element).map(|i| self.weights[*i])
+    }
+
+
+    /// Returns the sum of all the weights.
+    pub fn total_weight(&self) -> f64 {
+        self.tree_total()
+    }
+
+
+    /// Returns the elements of the sampler, in insertion order.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the number of elements in the sampler.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+
+    /// Returns `true` if the sampler does not contain any element.
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+
+    /// Releases the excess capacity left by the insertions, for a sampler which stopped growing.
+    pub fn shrink_to_fit(&mut self) {
+        self.elements.shrink_to_fit();
+        self.index.shrink_to_fit();
+        self.weights.shrink_to_fit();
+        self.tree.shrink_to_fit();
+    }
+
+
+    /// Returns a sampled element using the default generator of the crate, or `None` if the sampler is empty or all the weights are 0.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> Option<T> {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a sampled element using the given random number generator, in O(log n), or `None` if the sampler is empty or all the weights are 0.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<T> {
+        self.sample_index_with(rng).map(|i| self.elements[i])
+    }
+
+
+    /// Returns the index in `elements()` of a sampled element, or `None` if the sampler is empty or all the weights are 0.
+    pub(crate) fn sample_index_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<usize> {
+        // the total can be slightly positive because of rounding errors even if all the weights are 0
+        if self.positive == 0 {
+            return None;
+        }
+        let target = rng.gen::<f64>() * self.tree_total().max(0.0);
+        self.find(target)
+    }
+
+
+    /// Returns the index of the element whose cumulative weight interval contains `target`, or `None` if all the weights are 0.
+    fn find(&self, target:f64) -> Option<usize> {
+        if self.positive == 0 {
+            return None;
+        }
+        let size = self.elements.len();
+        let mut position = 0;
+        let mut remaining = target;
+        let mut step = if size == 0 { 0 } else { 1 << (usize::BITS - 1 - size.leading_zeros()) };
+        while step > 0 {
+            let next = position + step;
+            if next <= size && self.tree[next] <= remaining {
+                position = next;
+                remaining -= self.tree[next];
+            }
+            step >>= 1;
+        }
+        // rounding errors in the tree can lead past the last element, or on an element of weight 0: the closest element of positive weight is taken, the previous one first
+        let found = position.min(size - 1);
+        if self.weights[found] > 0.0 {
+            return Some(found);
+        }
+        (0..found).rev().chain(found + 1..size).find(|i| self.weights[*i] > 0.0)
+    }
+
+
+    /// Returns the sum of the weights, as stored in the tree.
+    fn tree_total(&self) -> f64 {
+        let mut total = 0.0;
+        let mut node = self.elements.len();
+        while node > 0 {
+            total += self.tree[node];
+            node -= lowbit(node);
+        }
+        total
+    }
+
+
+    /// Rebuilds the tree from the weights in O(n), which also removes the rounding errors accumulated by the updates.
+    fn rebuild_tree(&mut self) {
+        self.updates = 0;
+        let size = self.weights.len();
+        self.tree = vec![0.0; size + 1];
+        for i in 1..=size {
+            self.tree[i] += self.weights[i - 1];
+            let parent = i + lowbit(i);
+            if parent <= size {
+                self.tree[parent] += self.tree[i];
+            }
+        }
+    }
+}
+
+
+impl<T> Default for DynamicSampler<T>
+where T: Copy + Hash + Eq + Debug {
+    fn default() -> Self {
+        DynamicSampler::new()
+    }
+}
+
+
+impl<T, I> TryFrom<&VoseAlias<T, I>> for DynamicSampler<T>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+    type Error = VoseAliasError;
+
+    /// See `DynamicSampler::from_vose_alias()`.
+    fn try_from(va:&VoseAlias<T, I>) -> Result<Self, Self::Error> {
+        DynamicSampler::from_vose_alias(va)
+    }
+}
+
+
+/// Returns the lowest set bit of `i`.
+fn lowbit(i:usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree_prefix_sums() {
+        let mut sampler = DynamicSampler::new();
+        for i in 0..13 {
+            sampler.insert(i, (i + 1) as f64).unwrap();
+        }
+        let built = DynamicSampler::from_weights((0..13).collect(), (1..=13).map(|w| w as f64).collect()).unwrap();
+        assert_eq!(sampler.tree, built.tree);
+        assert_eq!(sampler.tree_total(), 91.0);
+
+        // element i covers [i(i+1)/2, (i+1)(i+2)/2)
+        assert_eq!(sampler.find(0.0), Some(0));
+        assert_eq!(sampler.find(0.5), Some(0));
+        assert_eq!(sampler.find(1.0), Some(1));
+        assert_eq!(sampler.find(45.0), Some(9));
+        assert_eq!(sampler.find(90.9), Some(12));
+
+        sampler.update_weight(&12, 0.0).unwrap();
+        assert_eq!(sampler.tree_total(), 78.0);
+        assert_eq!(sampler.find(77.9), Some(11));
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn rounding_drift() {
+        let mut sampler = DynamicSampler::from_weights((0..100).collect(), vec![0.0; 100]).unwrap();
+        sampler.update_weight(&99, 1.0).unwrap();
+        // updates of very different magnitudes leave rounding errors in the tree
+        for i in 0..1000 {
+            sampler.update_weight(&(i % 99), 1e17).unwrap();
+            sampler.update_weight(&(i % 99), 0.0).unwrap();
+        }
+        assert_eq!(sampler.updates, 2001 - MIN_UPDATES_BEFORE_REBUILD);
+        for target in &[0.0, 0.5, sampler.tree_total(), 1e9] {
+            assert_eq!(sampler.find(*target), Some(99));
+        }
+        for _ in 0..100 {
+            assert_eq!(sampler.sample(), Some(99));
+        }
+
+        sampler.rebuild_tree();
+        assert_eq!(sampler.tree_total(), 1.0);
+        sampler.update_weight(&99, 0.0).unwrap();
+        assert_eq!(sampler.find(0.0), None);
+        assert_eq!(sampler.sample(), None);
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn errors() {
+        let mut sampler = DynamicSampler::new();
+        assert_eq!(sampler.sample(), None);
+        sampler.insert(1, 1.0).unwrap();
+        assert!(sampler.insert(1, 1.0).is_err());
+        assert!(sampler.insert(2, -1.0).is_err());
+        assert_eq!(sampler.update_weight(&3, 1.0), Ok(None));
+        assert_eq!(sampler.update_weight(&1, 0.0), Ok(Some(1.0)));
+        assert_eq!(sampler.sample(), None);
+        assert!(sampler.to_vose_alias().is_err());
+        assert!(DynamicSampler::from_weights(vec![1, 1], vec![1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn conversions() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+        let mut sampler = DynamicSampler::try_from(&va).unwrap();
+        assert!((sampler.weight(&1).unwrap() - 0.5).abs() < 0.000001);
+        sampler.update_weight(&4, 0.0).unwrap();
+        sampler.update_weight(&1, 0.2).unwrap();
+        let va2 = sampler.to_vose_alias().unwrap();
+        let probabilities = va2.table_probabilities();
+        for p in &probabilities[0..3] {
+            assert!((p - 1.0 / 3.0).abs() < 0.000001);
+        }
+        assert_eq!(probabilities[3], 0.0);
+
+        let mut va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        va.elements.push(3);
+        assert!(matches!(DynamicSampler::from_vose_alias(&va), Err(VoseAliasError::CorruptTable(_))));
+    }
+}
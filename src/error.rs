@@ -0,0 +1,74 @@
+//! Error type returned by the non-panicking functions of this crate.
+
+use std::error::Error;
+use std::fmt;
+
+
+/// The errors that can happen when building, loading or using a `VoseAlias` object.
+///
+/// The panicking functions of this crate (such as `VoseAlias::new()`) panic with the `Display` message of the corresponding error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VoseAliasError {
+    /// The element vector and the probability vector do not contain the same number of elements.
+    SizeMismatch { elements: usize, probabilities: usize },
+    /// The distribution does not contain any element.
+    EmptyDistribution,
+    /// The distribution contains more elements than the index type of the tables can address.
+    TooManyElements { elements: usize, capacity: usize },
+    /// The same element appears more than once in the element vector. The element is given in its `Debug` form.
+    DuplicateElement(String),
+    /// A probability is negative or not a number.
+    InvalidProbability(f32),
+    /// A weight is negative, infinite or not a number.
+    InvalidWeight(f64),
+    /// All the weights are 0, so they cannot be normalized into a distribution.
+    ZeroTotalWeight,
+    /// The probabilities do not sum to 1. The actual sum is given.
+    NotNormalized(f32),
+    /// The data was written with a format version this version of the crate cannot read.
+    UnsupportedVersion(u32),
+    /// The alias or probability tables do not respect the invariants of the Vose-Alias method.
+    CorruptTable(String),
+    /// A recorded draw of a sampling trace cannot be replayed on the tables. The position of the draw in the trace is given.
+    InvalidTrace { position: usize },
+    /// A value of an input column is null. The row of the value is given.
+    NullValue { row: usize },
+    /// An input column does not exist or does not have the expected data type. The reason is given.
+    InvalidColumn(String),
+    /// An input file cannot be read or does not have the expected content. The reason is given.
+    InvalidFile(String),
+    /// A parameter of a distribution is out of its domain. The name and the value of the parameter are given.
+    InvalidParameter { name: &'static str, value: f64 },
+    /// All the elements of a sampler with limited supply have been sampled.
+    Exhausted,
+    /// An element given as a parameter is not part of the distribution. The element is given in its `Debug` form.
+    UnknownElement(String),
+}
+
+
+impl fmt::Display for VoseAliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoseAliasError::SizeMismatch { .. } => write!(f, "Both vectors should contain the same number of elements"),
+            VoseAliasError::EmptyDistribution => write!(f, "The distribution should contain at least one element"),
+            VoseAliasError::TooManyElements { elements, capacity } => write!(f, "The distribution contains {} elements, but its index type can only address {}", elements, capacity),
+            VoseAliasError::DuplicateElement(e) => write!(f, "Element {} appears more than once in the element vector", e),
+            VoseAliasError::InvalidProbability(p) => write!(f, "Invalid probability {}: probabilities should be positive numbers", p),
+            VoseAliasError::InvalidWeight(w) => write!(f, "Invalid weight {}: weights should be finite positive numbers", w),
+            VoseAliasError::ZeroTotalWeight => write!(f, "The weights sum to 0"),
+            VoseAliasError::NotNormalized(_) => write!(f, "Probability vector does not sum to 1"),
+            VoseAliasError::UnsupportedVersion(v) => write!(f, "Unsupported format version {}", v),
+            VoseAliasError::CorruptTable(reason) => write!(f, "Corrupt Vose-Alias table: {}", reason),
+            VoseAliasError::InvalidTrace { position } => write!(f, "Draw {} of the trace cannot be replayed on these tables", position),
+            VoseAliasError::NullValue { row } => write!(f, "The value of row {} is null", row),
+            VoseAliasError::InvalidColumn(reason) => write!(f, "Invalid column: {}", reason),
+            VoseAliasError::InvalidFile(reason) => write!(f, "Invalid file: {}", reason),
+            VoseAliasError::InvalidParameter { name, value } => write!(f, "Invalid value {} for parameter {}", value, name),
+            VoseAliasError::Exhausted => write!(f, "All the elements are out of stock"),
+            VoseAliasError::UnknownElement(e) => write!(f, "Element {} is not part of the distribution", e),
+        }
+    }
+}
+
+
+impl Error for VoseAliasError {}
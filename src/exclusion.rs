@@ -0,0 +1,187 @@
+//! Sampling while avoiding some elements: excluded sets and rejection sampling.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias};
+
+
+/// Above this excluded probability mass, `sample_excluding` renormalizes the remaining elements instead of rejecting the excluded ones.
+const REJECTION_MAX_EXCLUDED_MASS:f64 = 0.5;
+
+/// Maximum number of draws of the rejection strategy, after which the renormalized strategy is used.
+const REJECTION_MAX_TRIES:usize = 64;
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns an element sampled from the distribution restricted to the elements that are not in `excluded`, or `None` if all the elements with a positive probability are excluded.
+    ///
+    /// When the excluded elements have a small total probability, elements are sampled from the table until one is not excluded (each draw succeeds with probability at least 1/2). Otherwise, the remaining elements are renormalized and sampled from directly, in O(n).
+    /// Computing the excluded mass costs O(k), with k the size of `excluded`.
+    ///
+    /// The observer, if any, is only notified of the returned element, not of the rejected draws. An element drawn from the renormalized elements is reported with its own slot, as if the coin had kept it.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use std::collections::HashSet;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["sword", "shield", "potion"], vec![0.2, 0.3, 0.5]);
+    /// let owned:HashSet<&str> = vec!["sword", "potion"].into_iter().collect();
+    /// assert_eq!(va.sample_excluding(&owned, &mut rand::thread_rng()), Some("shield"));
+    ///
+    /// let owned:HashSet<&str> = va.elements.iter().copied().collect();
+    /// assert_eq!(va.sample_excluding(&owned, &mut rand::thread_rng()), None);
+    /// # }
+    /// ```
+    pub fn sample_excluding<R: RngCore + ?Sized>(&self, excluded:&HashSet<T>, rng:&mut R) -> Option<T> {
+        let excluded_mass:f64 = excluded.iter().filter_map(|e| self.index.get(e)).map(|i| self.probabilities[*i]).sum();
+
+        if excluded_mass < REJECTION_MAX_EXCLUDED_MASS {
+            for _ in 0..REJECTION_MAX_TRIES {
+                let (die, coin) = self.roll_die_and_flip_coin(rng);
+                let (index, alias_used) = self.select_slot(die, coin);
+                if !excluded.contains(&self.elements[index]) {
+                    self.observer.notify(index, die, alias_used);
+                    return Some(self.elements[index]);
+                }
+            }
+        }
+
+        // renormalizing the remaining elements
+        let remaining:Vec<(usize, f64)> = self.probabilities.iter().copied().enumerate()
+            .filter(|(i, p)| *p > 0.0 && !excluded.contains(&self.elements[*i]))
+            .collect();
+        let total:f64 = remaining.iter().map(|(_, p)| p).sum();
+        let last = remaining.last()?;
+        let mut target = rng.gen::<f64>() * total;
+        for (i, p) in &remaining {
+            if target < *p {
+                self.observer.notify(*i, *i, false);
+                return Some(self.elements[*i]);
+            }
+            target -= p;
+        }
+        // rounding errors can leave a tiny part of the mass after the last element
+        self.observer.notify(last.0, last.0, false);
+        Some(self.elements[last.0])
+    }
+
+
+    /// Samples elements until one is accepted by `predicate`, within at most `max_tries` draws.
+    ///
+    /// Returns the accepted element (or `None` if no element was accepted within `max_tries` draws), along with the number of draws performed. The observer, if any, is only notified of the accepted element, not of the rejected draws.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+    /// let (element, tries) = va.sample_until(|e| e % 2 == 0, 100, &mut rand::thread_rng());
+    /// assert!(tries >= 1 && tries <= 100);
+    /// if let Some(e) = element {
+    ///     assert!(e == 2 || e == 4);
+    /// }
+    ///
+    /// let (element, tries) = va.sample_until(|e| *e > 4, 10, &mut rand::thread_rng());
+    /// assert_eq!((element, tries), (None, 10));
+    /// # }
+    /// ```
+    pub fn sample_until<R, P>(&self, mut predicate:P, max_tries:usize, rng:&mut R) -> (Option<T>, usize)
+    where R: RngCore + ?Sized, P: FnMut(&T) -> bool {
+        for tries in 1..=max_tries {
+            let (die, coin) = self.roll_die_and_flip_coin(rng);
+            let (index, alias_used) = self.select_slot(die, coin);
+            if predicate(&self.elements[index]) {
+                self.observer.notify(index, die, alias_used);
+                return (Some(self.elements[index]), tries);
+            }
+        }
+        (None, max_tries)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_excluded_mass() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.1, 0.3, 0.3, 0.3]);
+        let excluded:HashSet<i32> = vec![1, 42].into_iter().collect();
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let e = va.sample_excluding(&excluded, &mut rng).unwrap();
+            assert!(e != 1);
+        }
+    }
+
+    #[test]
+    fn large_excluded_mass() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.7, 0.2, 0.05, 0.05]);
+        let excluded:HashSet<i32> = vec![1, 2].into_iter().collect();
+        let mut rng = rand::thread_rng();
+        let threes = (0..10000).filter(|_| va.sample_excluding(&excluded, &mut rng) == Some(3)).count();
+        assert!(threes > 4500 && threes < 5500);
+    }
+
+    #[test]
+    fn sample_until_bounded() {
+        let va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        let mut rng = rand::thread_rng();
+        let (element, tries) = va.sample_until(|_| true, 5, &mut rng);
+        assert!(element.is_some());
+        assert_eq!(tries, 1);
+        assert_eq!(va.sample_until(|_| false, 0, &mut rng), (None, 0));
+        let mut calls = 0;
+        let (element, tries) = va.sample_until(|e| { calls += 1; *e == 2 }, 1000, &mut rng);
+        assert_eq!(element, Some(2));
+        assert_eq!(tries, calls);
+    }
+
+    #[test]
+    fn zero_probability_remaining() {
+        let va = VoseAlias::new(vec![1, 2], vec![1.0, 0.0]);
+        let excluded:HashSet<i32> = vec![1].into_iter().collect();
+        assert_eq!(va.sample_excluding(&excluded, &mut rand::thread_rng()), None);
+        let excluded:HashSet<i32> = vec![2].into_iter().collect();
+        assert_eq!(va.sample_excluding(&excluded, &mut rand::thread_rng()), Some(1));
+    }
+
+    #[test]
+    fn observer_notified_of_returned_draws() {
+        use std::sync::{Arc, Mutex};
+        use crate::SampleEvent;
+
+        let events:Arc<Mutex<Vec<SampleEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        let mut va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.1, 0.3, 0.3, 0.3]);
+        va.set_observer(Arc::new(move |event:&SampleEvent| log.lock().unwrap().push(*event)));
+        let mut rng = rand::thread_rng();
+
+        let small:HashSet<i32> = vec![2].into_iter().collect();
+        let large:HashSet<i32> = vec![2, 3, 4].into_iter().collect();
+        let mut returned = Vec::new();
+        for _ in 0..100 {
+            returned.extend(va.sample_excluding(&small, &mut rng));
+            returned.extend(va.sample_excluding(&large, &mut rng));
+            returned.extend(va.sample_until(|e| *e == 1, 1000, &mut rng).0);
+        }
+        assert_eq!(va.sample_until(|_| false, 10, &mut rng), (None, 10));
+        let indexes:Vec<i32> = events.lock().unwrap().iter().map(|e| va.elements[e.index]).collect();
+        assert_eq!(indexes, returned);
+    }
+}
@@ -0,0 +1,174 @@
+//! Export of the distribution to formats read by external tools.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Write};
+use std::hash::Hash;
+use std::io;
+
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Display + Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the distribution as an indented JSON object, mapping each element (in its `Display` form) to its probability, in the order of `elements`.
+    ///
+    /// The probabilities are the ones of the distribution, not the values of the alias tables, written in their shortest form that parses back to the same value: a probability given as an `f32` (for instance by `VoseAlias::new()`) is written as that `f32`, so 0.1 is not written as 0.10000000149011612. This function does not require the `serde` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["heads", "tails"], vec![0.5, 0.5]);
+    /// assert_eq!(va.to_json_pretty(), "{\n  \"heads\": 0.5,\n  \"tails\": 0.5\n}");
+    /// ```
+    pub fn to_json_pretty(&self) -> String {
+        let mut json = String::from("{");
+        for (i, e) in self.elements.iter().enumerate() {
+            json.push_str(if i == 0 { "\n  " } else { ",\n  " });
+            push_json_string(&mut json, &e.to_string());
+            json.push_str(": ");
+            self.push_probability(&mut json, i);
+        }
+        json.push_str("\n}");
+        json
+    }
+
+
+    /// Draws `n_samples` samples with the default generator of the crate, and writes their histogram to `writer` as CSV. See `VoseAlias::write_histogram_csv_with()`.
+    #[cfg(feature = "thread-rng")]
+    pub fn write_histogram_csv<W: io::Write>(&self, n_samples:usize, writer:W) -> io::Result<()> {
+        rng::with_default_rng(|rng| self.write_histogram_csv_with(n_samples, writer, rng))
+    }
+
+
+    /// Draws `n_samples` samples with the given generator, and writes their histogram to `writer` as CSV, to audit the distribution in a spreadsheet or a plotting tool.
+    ///
+    /// The CSV has a header line and one line per element, in the order of `elements`, with three columns: the element (in its `Display` form, quoted if needed), its probability in the distribution, and its observed frequency among the samples (0 if `n_samples` is 0).
+    ///
+    /// # Errors
+    /// The errors of `writer` are returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["heads", "tails"], vec![1.0, 0.0]);
+    /// let mut csv = Vec::new();
+    /// va.write_histogram_csv_with(100, &mut csv, &mut rand::thread_rng()).unwrap();
+    /// assert_eq!(String::from_utf8(csv).unwrap(), "element,expected,observed\nheads,1,1\ntails,0,0\n");
+    /// # }
+    /// ```
+    pub fn write_histogram_csv_with<W: io::Write, R: RngCore + ?Sized>(&self, n_samples:usize, mut writer:W, rng:&mut R) -> io::Result<()> {
+        let mut counts:HashMap<T, u64> = HashMap::with_capacity(self.elements.len());
+        for _ in 0..n_samples {
+            *counts.entry(self.sample_with(rng)).or_insert(0) += 1;
+        }
+
+        writeln!(writer, "element,expected,observed")?;
+        let mut line = String::new();
+        for (i, e) in self.elements.iter().enumerate() {
+            let count = counts.get(e).copied().unwrap_or(0);
+            let observed = if n_samples == 0 { 0.0 } else { count as f64 / n_samples as f64 };
+            line.clear();
+            push_csv_field(&mut line, &e.to_string());
+            line.push(',');
+            self.push_probability(&mut line, i);
+            writeln!(writer, "{},{}", line, observed)?;
+        }
+        writer.flush()
+    }
+
+
+    /// Appends the probability of the element at position `i`, as the `f32` it was given as if it was one, and as an `f64` otherwise.
+    fn push_probability(&self, s:&mut String, i:usize) {
+        let p = self.probabilities[i];
+        // writing to a String cannot fail
+        match self.input_probabilities.get(i) {
+            Some(input) if f64::from(*input) == p => { let _ = write!(s, "{}", input); },
+            _ => { let _ = write!(s, "{}", p); },
+        }
+    }
+}
+
+
+/// Appends `s` to `json` as a quoted JSON string.
+fn push_json_string(json:&mut String, s:&str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            },
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+
+
+/// Appends `s` to `csv` as a CSV field, quoted if it contains a comma, a quote or a line break (RFC 4180).
+fn push_csv_field(csv:&mut String, s:&str) {
+    if s.contains([',', '"', '\n', '\r']) {
+        csv.push('"');
+        csv.push_str(&s.replace('"', "\"\""));
+        csv.push('"');
+    }
+    else {
+        csv.push_str(s);
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_json() {
+        let va = VoseAlias::new(vec!["say \"hi\"", "a\\b\n"], vec![0.75, 0.25]);
+        let json = va.to_json_pretty();
+        assert_eq!(json, "{\n  \"say \\\"hi\\\"\": 0.75,\n  \"a\\\\b\\n\": 0.25\n}");
+        let parsed:serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["say \"hi\""], 0.75);
+    }
+
+    #[test]
+    fn shortest_probabilities() {
+        let va = VoseAlias::new(vec![1, 2], vec![0.1, 0.9]);
+        assert_eq!(va.to_json_pretty(), "{\n  \"1\": 0.1,\n  \"2\": 0.9\n}");
+        let va:VoseAlias<i32> = VoseAlias::from_weights(vec![1, 2], vec![1.0, 2.0]).unwrap();
+        let parsed:serde_json::Value = serde_json::from_str(&va.to_json_pretty()).unwrap();
+        assert_eq!(parsed["1"], 1.0 / 3.0);
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn histogram_csv() {
+        let va = VoseAlias::new(vec!["a,b", "say \"hi\"", "c"], vec![0.5, 0.5, 0.0]);
+        let mut csv = Vec::new();
+        va.write_histogram_csv(0, &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "element,expected,observed\n\"a,b\",0.5,0\n\"say \"\"hi\"\"\",0.5,0\nc,0,0\n");
+
+        let mut csv = Vec::new();
+        va.write_histogram_csv(1000, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let observed:f64 = csv.lines().map(|l| l.rsplit(',').next().unwrap()).skip(1).map(|o| o.parse::<f64>().unwrap()).sum();
+        assert!((observed - 1.0).abs() < 1e-9);
+    }
+}
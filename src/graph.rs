@@ -0,0 +1,194 @@
+//! Weighted neighbor sampling and random walks over a `petgraph` graph (feature `petgraph`), with one Vose-Alias table per node.
+
+use indexmap::IndexMap;
+use petgraph::graph::{DefaultIx, Graph, IndexType, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::EdgeType;
+use rand_core::RngCore;
+
+use crate::{VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A sampler of the neighbors of the nodes of a `petgraph::graph::Graph`, each neighbor being drawn proportionally to the weight of the edge leading to it, in constant time.
+///
+/// The tables are built once from the graph, which is not borrowed afterwards: the sampler has to be rebuilt if the graph changes. In a directed graph, the neighbors of a node are the targets of its outgoing edges. In an undirected graph, they are the nodes at the other end of all its edges.
+/// The weights of parallel edges between the same nodes are summed. A node without edges, or whose edges all have a weight of 0, is a dead end.
+///
+/// This is the sampling step of the random walks used by graph embedding methods such as DeepWalk (see `GraphSampler::walks_with()`).
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use petgraph::graph::Graph;
+/// use vose_alias::GraphSampler;
+///
+/// let mut graph = Graph::new_undirected();
+/// let paris = graph.add_node("Paris");
+/// let lyon = graph.add_node("Lyon");
+/// let lille = graph.add_node("Lille");
+/// graph.add_edge(paris, lyon, 9.0);
+/// graph.add_edge(paris, lille, 1.0);
+///
+/// let sampler = GraphSampler::new(&graph, |weight| *weight).unwrap();
+/// let mut rng = rand::thread_rng();
+/// let trips = (0..1000).filter(|_| sampler.sample_neighbor_with(paris, &mut rng) == Some(lyon)).count();
+/// assert!(trips > 800);
+/// assert_eq!(sampler.walk_with(lille, 3, &mut rng)[1], paris);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GraphSampler<Ix = DefaultIx> where Ix: IndexType {
+    // the table of the neighbors of each node, by node index
+    tables:Vec<Option<VoseAlias<NodeIndex<Ix>>>>,
+}
+
+
+impl<Ix> GraphSampler<Ix>
+where Ix: IndexType {
+
+    /// Builds the tables of the neighbors of each node of `graph`, the weight of each edge being given by `weight`.
+    ///
+    /// An error is returned if the weight of an edge is negative or not finite.
+    pub fn new<N, E, Ty, F>(graph:&Graph<N, E, Ty, Ix>, mut weight:F) -> Result<GraphSampler<Ix>, VoseAliasError>
+    where Ty: EdgeType, F: FnMut(&E) -> f64 {
+        let mut tables:Vec<Option<VoseAlias<NodeIndex<Ix>>>> = Vec::with_capacity(graph.node_count());
+        for node in graph.node_indices() {
+            let mut neighbors:IndexMap<NodeIndex<Ix>, f64> = IndexMap::new();
+            for edge in graph.edges(node) {
+                let w = weight(edge.weight());
+                if !w.is_finite() || w < 0.0 {
+                    return Err(VoseAliasError::InvalidWeight(w));
+                }
+                *neighbors.entry(edge.target()).or_insert(0.0) += w;
+            }
+            let (elements, weights):(Vec<NodeIndex<Ix>>, Vec<f64>) = neighbors.into_iter().unzip();
+            let table = match VoseAlias::from_weights(elements, weights) {
+                Ok(table) => Some(table),
+                Err(VoseAliasError::EmptyDistribution) | Err(VoseAliasError::ZeroTotalWeight) => None,
+                Err(e) => return Err(e),
+            };
+            tables.push(table);
+        }
+        Ok(GraphSampler { tables })
+    }
+
+
+    /// Returns the number of nodes of the graph the sampler was built from.
+    pub fn node_count(&self) -> usize {
+        self.tables.len()
+    }
+
+
+    /// Returns the table used to sample the neighbors of `node`, or `None` if `node` is a dead end or is not part of the graph.
+    pub fn neighbors(&self, node:NodeIndex<Ix>) -> Option<&VoseAlias<NodeIndex<Ix>>> {
+        self.tables.get(node.index()).and_then(|table| table.as_ref())
+    }
+
+
+    /// Returns a neighbor of `node` sampled with the default generator of the crate, or `None` if `node` is a dead end or is not part of the graph.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_neighbor(&self, node:NodeIndex<Ix>) -> Option<NodeIndex<Ix>> {
+        rng::with_default_rng(|rng| self.sample_neighbor_with(node, rng))
+    }
+
+
+    /// Returns a neighbor of `node` sampled with the given generator, or `None` if `node` is a dead end or is not part of the graph.
+    pub fn sample_neighbor_with<R: RngCore + ?Sized>(&self, node:NodeIndex<Ix>, rng:&mut R) -> Option<NodeIndex<Ix>> {
+        self.neighbors(node).map(|table| table.sample_with(rng))
+    }
+
+
+    /// Returns a random walk of `len` nodes starting with `start` (included), using the default generator of the crate. The walk is shorter if it reaches a dead end.
+    #[cfg(feature = "thread-rng")]
+    pub fn walk(&self, start:NodeIndex<Ix>, len:usize) -> Vec<NodeIndex<Ix>> {
+        rng::with_default_rng(|rng| self.walk_with(start, len, rng))
+    }
+
+
+    /// Returns a random walk of `len` nodes starting with `start` (included), using the given generator. The walk is shorter if it reaches a dead end.
+    pub fn walk_with<R: RngCore + ?Sized>(&self, start:NodeIndex<Ix>, len:usize, rng:&mut R) -> Vec<NodeIndex<Ix>> {
+        let mut walk:Vec<NodeIndex<Ix>> = Vec::with_capacity(len);
+        if len == 0 {
+            return walk;
+        }
+        walk.push(start);
+        let mut current = start;
+        while walk.len() < len {
+            match self.sample_neighbor_with(current, rng) {
+                Some(next) => {
+                    walk.push(next);
+                    current = next;
+                },
+                None => break,
+            }
+        }
+        walk
+    }
+
+
+    /// Returns `walks_per_node` random walks of `len` nodes starting from each node of the graph, using the default generator of the crate. See `GraphSampler::walks_with()`.
+    #[cfg(feature = "thread-rng")]
+    pub fn walks(&self, walks_per_node:usize, len:usize) -> Vec<Vec<NodeIndex<Ix>>> {
+        rng::with_default_rng(|rng| self.walks_with(walks_per_node, len, rng))
+    }
+
+
+    /// Returns `walks_per_node` random walks of `len` nodes starting from each node of the graph, using the given generator, as the corpus of a DeepWalk embedding.
+    ///
+    /// The walks are returned round by round: the first walk of every node, in the order of the node indexes, then the second one of every node, and so on.
+    pub fn walks_with<R: RngCore + ?Sized>(&self, walks_per_node:usize, len:usize, rng:&mut R) -> Vec<Vec<NodeIndex<Ix>>> {
+        let mut walks:Vec<Vec<NodeIndex<Ix>>> = Vec::with_capacity(walks_per_node * self.tables.len());
+        for _ in 0..walks_per_node {
+            for node in 0..self.tables.len() {
+                walks.push(self.walk_with(NodeIndex::new(node), len, rng));
+            }
+        }
+        walks
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directed_neighbors() {
+        let mut graph:Graph<(), u32> = Graph::new();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let c = graph.add_node(());
+        graph.add_edge(a, b, 1);
+        graph.add_edge(a, b, 2);
+        graph.add_edge(a, c, 3);
+        graph.add_edge(b, c, 0);
+        let sampler = GraphSampler::new(&graph, |w| f64::from(*w)).unwrap();
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0x9e37_79b9_7f4a_7c15);
+
+        let table = sampler.neighbors(a).unwrap();
+        assert_eq!((table.probabilities[0], table.probabilities[1]), (0.5, 0.5));
+        assert!(sampler.neighbors(b).is_none());
+        assert_eq!(sampler.sample_neighbor_with(c, &mut rng), None);
+        assert_eq!(sampler.sample_neighbor_with(NodeIndex::new(10), &mut rng), None);
+        assert_eq!(sampler.walk_with(b, 4, &mut rng), vec![b]);
+
+        let walks = sampler.walks_with(2, 3, &mut rng);
+        assert_eq!(walks.len(), 6);
+        assert!(walks[0] == vec![a, b] || walks[0] == vec![a, c]);
+        assert_eq!(walks[4], vec![b]);
+    }
+
+    #[test]
+    fn invalid_weights() {
+        let mut graph:Graph<(), f64> = Graph::new();
+        let a = graph.add_node(());
+        graph.add_edge(a, a, -1.0);
+        assert_eq!(GraphSampler::new(&graph, |w| *w).err(), Some(VoseAliasError::InvalidWeight(-1.0)));
+    }
+}
@@ -0,0 +1,309 @@
+//! A two-level alias structure for very large supports: an alias table over groups of elements, then an alias table within the sampled group.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{build_slots, check_weights, die_and_coin, VoseAliasError, COIN_SCALE};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A weighted sampler over a very large support (hundreds of millions of elements), split into groups of consecutive elements.
+///
+/// A sample first selects a group from an alias table over the total weights of the groups, then an element of that group from the alias table of the group. Both steps take constant time and draw one 64-bit value each. The table of a group is contiguous and, with the default group size of 4096 elements, fits in 32 KiB (a typical L1 cache), so that the second step stays within a few pages.
+/// Updating weights only rebuilds the tables of the groups containing them, and the table over the groups: an update costs O(group size + number of groups) instead of O(n).
+///
+/// The elements are addressed by their position, to avoid storing an index of hundreds of millions of elements. The weights are kept (8 bytes per element) to rebuild the groups.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::GroupedAlias;
+///
+/// let mut grouped = GroupedAlias::with_group_size((0..1000_u32).collect(), vec![1.0; 1000], 100).unwrap();
+/// assert_eq!(grouped.group_count(), 10);
+///
+/// // only the group of the elements 0..100 is rebuilt
+/// grouped.update_weights((1..100).map(|i| (i, 0.0))).unwrap();
+/// assert!((grouped.probability(0) - 1.0 / 901.0).abs() < 1e-12);
+/// let sample = grouped.sample();
+/// assert!(sample == 0 || sample >= 100);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GroupedAlias<T> where T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    weights:Vec<f64>,
+    group_size:usize,
+    total:f64,
+    group_totals:Vec<f64>,
+    // the table over the groups
+    group_prob:Vec<f32>,
+    group_alias:Vec<u32>,
+    // the tables within the groups, group g uses the slots [g * group_size, (g + 1) * group_size), with aliases relative to the group
+    prob:Vec<f32>,
+    alias:Vec<u32>,
+}
+
+
+impl<T> GroupedAlias<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// The default number of elements of a group.
+    pub const DEFAULT_GROUP_SIZE:usize = 4096;
+
+
+    /// Returns the sampler of the elements with a probability proportional to their weights, in groups of `GroupedAlias::DEFAULT_GROUP_SIZE` elements.
+    ///
+    /// # Errors
+    /// See `GroupedAlias::with_group_size()`.
+    pub fn new(element_vector:Vec<T>, weight_vector:Vec<f64>) -> Result<GroupedAlias<T>, VoseAliasError> {
+        GroupedAlias::with_group_size(element_vector, weight_vector, GroupedAlias::<T>::DEFAULT_GROUP_SIZE)
+    }
+
+
+    /// Returns the sampler of the elements with a probability proportional to their weights, in groups of `group_size` consecutive elements (the last group can be smaller).
+    ///
+    /// The duplicates are not checked, as it would require hashing the whole support: a duplicated element is sampled with the sum of its weights.
+    ///
+    /// # Errors
+    /// An error is returned if both vectors do not have the same size, if the element vector is empty, if a weight is negative or not finite, if all the weights are 0, if `group_size` is 0 or greater than 2^32, or if there are more than 2^32 groups.
+    pub fn with_group_size(element_vector:Vec<T>, weight_vector:Vec<f64>, group_size:usize) -> Result<GroupedAlias<T>, VoseAliasError> {
+        check_weights(element_vector.len(), &weight_vector)?;
+        if group_size == 0 || group_size > u32::MAX as usize {
+            return Err(VoseAliasError::InvalidParameter { name: "group_size", value: group_size as f64 });
+        }
+        let groups = element_vector.len().div_ceil(group_size);
+        if groups > u32::MAX as usize {
+            return Err(VoseAliasError::TooManyElements { elements: element_vector.len(), capacity: u32::MAX as usize * group_size });
+        }
+
+        let n = element_vector.len();
+        let mut grouped = GroupedAlias {
+            elements: element_vector,
+            weights: weight_vector,
+            group_size,
+            total: 0.0,
+            group_totals: vec![0.0; groups],
+            group_prob: vec![0.0; groups],
+            group_alias: vec![0; groups],
+            prob: vec![0.0; n],
+            alias: vec![0; n],
+        };
+        grouped.rebuild((0..groups).collect())?;
+        Ok(grouped)
+    }
+
+
+    /// Returns the elements of the sampler.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the number of elements of the sampler.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+
+    /// Returns `true` if the sampler has no element, which cannot happen once built.
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+
+    /// Returns the number of elements of a group (except the last one, which can be smaller).
+    pub fn group_size(&self) -> usize {
+        self.group_size
+    }
+
+
+    /// Returns the number of groups.
+    pub fn group_count(&self) -> usize {
+        self.group_totals.len()
+    }
+
+
+    /// Returns the weight of the element at the position `index`, or `None` if it is out of bounds.
+    pub fn weight(&self, index:usize) -> Option<f64> {
+        self.weights.get(index).copied()
+    }
+
+
+    /// Returns the probability of the element at the position `index`, or 0 if it is out of bounds.
+    pub fn probability(&self, index:usize) -> f64 {
+        self.weights.get(index).map_or(0.0, |w| w / self.total)
+    }
+
+
+    /// Changes the weight of the element at the position `index`, and rebuilds its group. See `GroupedAlias::update_weights()`.
+    pub fn update_weight(&mut self, index:usize, weight:f64) -> Result<f64, VoseAliasError> {
+        let previous = self.weight(index);
+        self.update_weights(std::iter::once((index, weight)))?;
+        // the update succeeded, so `index` is in bounds
+        Ok(previous.unwrap_or(0.0))
+    }
+
+
+    /// Changes the weights of the elements at the given positions, applied in order, then rebuilds the tables of the groups containing them and the table over the groups, once.
+    ///
+    /// # Errors
+    /// An error is returned, and no weight is changed, if a position is out of bounds (`VoseAliasError::InvalidParameter`), if a weight is negative or not finite, or if all the weights would be 0.
+    pub fn update_weights<U: IntoIterator<Item = (usize, f64)>>(&mut self, updates:U) -> Result<(), VoseAliasError> {
+        let updates:Vec<(usize, f64)> = updates.into_iter().collect();
+        for (i, w) in &updates {
+            if *i >= self.elements.len() {
+                return Err(VoseAliasError::InvalidParameter { name: "index", value: *i as f64 });
+            }
+            if !w.is_finite() || *w < 0.0 {
+                return Err(VoseAliasError::InvalidWeight(*w));
+            }
+        }
+
+        let mut previous:Vec<(usize, f64)> = Vec::with_capacity(updates.len());
+        let mut groups:Vec<usize> = Vec::with_capacity(updates.len());
+        for (i, w) in updates {
+            previous.push((i, self.weights[i]));
+            self.weights[i] = w;
+            groups.push(i / self.group_size);
+        }
+        groups.sort_unstable();
+        groups.dedup();
+        if let Err(e) = self.rebuild(groups.clone()) {
+            for (i, w) in previous.into_iter().rev() {
+                self.weights[i] = w;
+            }
+            // the previous weights had a positive total
+            let _ = self.rebuild(groups);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+
+    /// Returns an element sampled with the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> T {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns an element sampled with the given generator: a group is selected by a first 64-bit value, then an element of the group by a second one.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> T {
+        self.elements[self.sample_index_with(rng)]
+    }
+
+
+    /// Returns the position of an element sampled with the given generator.
+    pub fn sample_index_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> usize {
+        let (die, coin) = die_and_coin(rng, self.group_totals.len());
+        let group = select(&self.group_prob, &self.group_alias, die, coin);
+        let start = group * self.group_size;
+        let end = (start + self.group_size).min(self.elements.len());
+        let (die, coin) = die_and_coin(rng, end - start);
+        start + select(&self.prob[start..end], &self.alias[start..end], die, coin)
+    }
+
+
+    /// Rebuilds the tables of the given groups (sorted, without duplicates), then the table over the groups. An error is returned if all the weights are 0, the table over the groups being left unchanged.
+    fn rebuild(&mut self, groups:Vec<usize>) -> Result<(), VoseAliasError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("vose_alias.rebuild", sampler = "grouped", size = self.elements.len(), groups = groups.len()).entered();
+        let mut scaled:Vec<f32> = Vec::with_capacity(self.group_size.min(self.elements.len()));
+        let mut slot_alias:Vec<usize> = Vec::with_capacity(scaled.capacity());
+        let (mut small, mut large) = (Vec::new(), Vec::new());
+
+        for g in groups {
+            let start = g * self.group_size;
+            let end = (start + self.group_size).min(self.elements.len());
+            let weights = &self.weights[start..end];
+            let total:f64 = weights.iter().sum();
+            self.group_totals[g] = total;
+            let len = end - start;
+            scaled.clear();
+            if total > 0.0 {
+                scaled.extend(weights.iter().map(|w| (w / total * len as f64) as f32));
+            }
+            else {
+                // never selected by the table over the groups
+                scaled.resize(len, 1.0);
+            }
+            slot_alias.resize(len, 0);
+            build_slots(&mut scaled, &mut self.prob[start..end], &mut slot_alias, &mut small, &mut large);
+            for (a, s) in self.alias[start..end].iter_mut().zip(slot_alias.iter()) {
+                *a = *s as u32;
+            }
+        }
+
+        let total:f64 = self.group_totals.iter().sum();
+        if total <= 0.0 || !total.is_finite() {
+            return Err(VoseAliasError::ZeroTotalWeight);
+        }
+        self.total = total;
+        let groups = self.group_totals.len();
+        let mut scaled:Vec<f32> = self.group_totals.iter().map(|t| (t / total * groups as f64) as f32).collect();
+        let mut slot_alias:Vec<usize> = vec![0; groups];
+        build_slots(&mut scaled, &mut self.group_prob, &mut slot_alias, &mut small, &mut large);
+        for (a, s) in self.group_alias.iter_mut().zip(slot_alias) {
+            *a = s as u32;
+        }
+        Ok(())
+    }
+}
+
+
+/// Returns the column selected by the die, or its alias, in the tables `prob` and `alias`.
+fn select(prob:&[f32], alias:&[u32], die:usize, coin:u32) -> usize {
+    if (coin as f32) < prob[die] * COIN_SCALE {
+        die
+    }
+    else {
+        alias[die] as usize
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn partial_rebuilds() {
+        let mut grouped = GroupedAlias::with_group_size((0..10).collect(), vec![1.0; 10], 4).unwrap();
+        assert_eq!((grouped.group_count(), grouped.group_totals.clone()), (3, vec![4.0, 4.0, 2.0]));
+
+        let prob = grouped.prob.clone();
+        assert_eq!(grouped.update_weight(9, 0.0), Ok(1.0));
+        assert_eq!(&grouped.prob[..8], &prob[..8]);
+        assert_eq!(grouped.group_totals, vec![4.0, 4.0, 1.0]);
+        assert!((grouped.probability(8) - 1.0 / 9.0).abs() < 1e-12);
+        for _ in 0..1000 {
+            assert_ne!(grouped.sample(), 9);
+        }
+
+        // zeroing every group but the last one
+        grouped.update_weights((0..8).map(|i| (i, 0.0))).unwrap();
+        for _ in 0..100 {
+            assert_eq!(grouped.sample(), 8);
+        }
+        assert_eq!(grouped.update_weight(8, 0.0), Err(VoseAliasError::ZeroTotalWeight));
+        assert_eq!(grouped.weight(8), Some(1.0));
+        assert_eq!(grouped.sample(), 8);
+        assert!(matches!(grouped.update_weight(10, 1.0), Err(VoseAliasError::InvalidParameter { name: "index", .. })));
+    }
+
+    #[test]
+    fn invalid_construction() {
+        assert!(matches!(GroupedAlias::with_group_size(vec![1, 2], vec![1.0, 1.0], 0), Err(VoseAliasError::InvalidParameter { name: "group_size", .. })));
+        assert_eq!(GroupedAlias::new(vec![1, 2], vec![0.0, 0.0]).err(), Some(VoseAliasError::ZeroTotalWeight));
+        assert_eq!(GroupedAlias::<u8>::new(vec![], vec![]).err(), Some(VoseAliasError::EmptyDistribution));
+    }
+}
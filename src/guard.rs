@@ -0,0 +1,141 @@
+//! A guard editing the weights of a distribution by element, and rebuilding its tables once the edits are done.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{ObserverSlot, SlotIndex, VoseAlias, VoseAliasError};
+
+
+/// A mutable view of the weights of a `VoseAlias` object, returned by `VoseAlias::weights_mut()`.
+///
+/// The weights start as the probabilities of the distribution, and are edited by element. Each edit is validated, and the tables are rebuilt once, with the renormalized weights, when the guard is dropped or committed. The tables are left untouched if no weight changed.
+///
+/// # Examples
+/// ```
+/// use vose_alias::{VoseAlias, WeightedSampler};
+///
+/// let mut va = VoseAlias::new(vec!["a", "b", "c"], vec![0.5, 0.25, 0.25]);
+/// {
+///     let mut weights = va.weights_mut();
+///     weights.set(&"a", 0.0).unwrap();
+///     assert!(weights.set(&"d", 1.0).is_err());
+///     assert!(weights.set(&"b", -1.0).is_err());
+/// }
+/// assert_eq!(va.prob_of(&"a"), 0.0);
+/// assert!((va.prob_of(&"b") - 0.5).abs() < 1e-9);
+/// ```
+pub struct WeightsMut<'a, T, I = u32> where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+    va:&'a mut VoseAlias<T, I>,
+    weights:Vec<f64>,
+    changed:bool,
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns a guard editing the weights of the elements, which rebuilds the tables when it is dropped. See `WeightsMut`.
+    pub fn weights_mut(&mut self) -> WeightsMut<'_, T, I> {
+        let weights = self.probabilities.clone();
+        WeightsMut {
+            va: self,
+            weights,
+            changed: false,
+        }
+    }
+}
+
+
+impl<'a, T, I> WeightsMut<'a, T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the current weight of `element`, or `None` if it is not part of the distribution.
+    pub fn get(&self, element:&T) -> Option<f64> {
+        self.va.index.get(element).and_then(|i| self.weights.get(*i)).copied()
+    }
+
+
+    /// Changes the weight of `element`, and returns its previous weight.
+    ///
+    /// # Errors
+    /// An error is returned, and the weight is left unchanged, if `element` is not part of the distribution (`VoseAliasError::UnknownElement`), or if the weight is negative or not finite.
+    pub fn set(&mut self, element:&T, weight:f64) -> Result<f64, VoseAliasError> {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(VoseAliasError::InvalidWeight(weight));
+        }
+        let weights = &mut self.weights;
+        let w = match self.va.index.get(element).and_then(|i| weights.get_mut(*i)) {
+            Some(w) => w,
+            None => return Err(VoseAliasError::UnknownElement(format!("{:?}", element))),
+        };
+        let previous = *w;
+        if weight != previous {
+            *w = weight;
+            self.changed = true;
+        }
+        Ok(previous)
+    }
+
+
+    /// Rebuilds the tables with the edited weights, and returns the error if they cannot be, instead of ignoring it like the drop of the guard.
+    ///
+    /// # Errors
+    /// An error (`VoseAliasError::ZeroTotalWeight`) is returned, and the tables are left unchanged, if all the weights are 0.
+    pub fn commit(mut self) -> Result<(), VoseAliasError> {
+        self.apply()
+    }
+
+
+    fn apply(&mut self) -> Result<(), VoseAliasError> {
+        if !self.changed {
+            return Ok(());
+        }
+        self.changed = false;
+        let mut rebuilt:VoseAlias<T, I> = VoseAlias::from_weights(self.va.elements.clone(), std::mem::take(&mut self.weights))?;
+        rebuilt.observer = std::mem::replace(&mut self.va.observer, ObserverSlot::empty());
+        *self.va = rebuilt;
+        Ok(())
+    }
+}
+
+
+impl<'a, T, I> Drop for WeightsMut<'a, T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+    /// Rebuilds the tables if a weight changed. If all the weights are 0, the tables are left unchanged: use `WeightsMut::commit()` to get the error.
+    fn drop(&mut self) {
+        let _ = self.apply();
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebuild_on_drop() {
+        let mut va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.25, 0.25]);
+        let tables = va.slot_prob.clone();
+        assert_eq!(va.weights_mut().get(&2), Some(0.25));
+        assert_eq!(va.slot_prob, tables);
+
+        let mut weights = va.weights_mut();
+        assert_eq!(weights.set(&1, 2.0), Ok(0.5));
+        assert_eq!(weights.set(&3, 0.0), Ok(0.25));
+        assert_eq!(weights.get(&1), Some(2.0));
+        drop(weights);
+        assert_eq!(va, VoseAlias::from_weights(vec![1, 2, 3], vec![2.0, 0.25, 0.0]).unwrap());
+
+        let probabilities = va.probabilities.clone();
+        let tables = va.slot_prob.clone();
+        let mut weights = va.weights_mut();
+        weights.set(&1, 0.0).unwrap();
+        weights.set(&2, 0.0).unwrap();
+        assert_eq!(weights.commit(), Err(VoseAliasError::ZeroTotalWeight));
+        assert_eq!((va.probabilities.clone(), va.slot_prob.clone()), (probabilities, tables));
+    }
+}
@@ -0,0 +1,56 @@
+//! Integer types that can be used to store the indexes of the alias table.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+
+/// An unsigned integer type used to store the slot indexes of the alias table.
+///
+/// A `VoseAlias<T, I>` stores one index of type `I` per element. Smaller types pack the table into less memory (and cache), but limit the number of elements that can be sampled from:
+/// - `u16`: up to 65 536 elements
+/// - `u32` (the default): up to 4 294 967 296 elements
+/// - `u64` and `usize`: as many elements as the memory allows
+///
+/// This trait is sealed: it cannot be implemented outside of this crate.
+pub trait SlotIndex: Copy + Eq + Hash + Debug + private::Sealed {
+    /// The number of elements that can be indexed with this type.
+    const CAPACITY: usize;
+
+    /// Converts an index to this type. The index must be lower than `CAPACITY`.
+    fn from_usize(index:usize) -> Self;
+
+    /// Converts this index to a `usize`.
+    fn to_usize(self) -> usize;
+}
+
+
+macro_rules! impl_slot_index {
+    ($($t:ty),*) => {
+        $(
+            impl private::Sealed for $t {}
+
+            impl SlotIndex for $t {
+                const CAPACITY: usize = if (<$t>::MAX as u128) < (usize::MAX as u128) {
+                    <$t>::MAX as usize + 1
+                } else {
+                    usize::MAX
+                };
+
+                fn from_usize(index:usize) -> Self {
+                    index as $t
+                }
+
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_slot_index!(u16, u32, u64, usize);
+
+
+mod private {
+    pub trait Sealed {}
+}
@@ -0,0 +1,151 @@
+//! Deterministic assignment of keys to elements, driven by a hash of the key instead of a random number generator.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+
+use crate::{SlotIndex, VoseAlias, COIN_BITS};
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the element selected by hashing `key` into the die and the coin, instead of drawing them from a random number generator.
+    ///
+    /// The same key always gives the same element for a given table, and keys are spread over the elements according to the distribution, so this can assign users to weighted variants or route requests to weighted backends without storing any assignment.
+    /// Changing a probability rebuilds the tables, which can reassign any key: use `VoseAlias::assign_consistent()` if only a small fraction of the keys should move.
+    ///
+    /// The key is hashed with `std::collections::hash_map::DefaultHasher`, which is deterministic within a program but whose algorithm may change between Rust releases, so assignments are not meant to be persisted across builds.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let variants = VoseAlias::new(vec!["control", "treatment"], vec![0.9, 0.1]);
+    /// let variant = variants.sample_keyed(&"user-42");
+    /// assert_eq!(variants.sample_keyed(&"user-42"), variant);
+    /// ```
+    pub fn sample_keyed<K: Hash + ?Sized>(&self, key:&K) -> T {
+        let (die, coin) = self.keyed_die_and_coin(key);
+        self.elements[self.select_index(die, coin)]
+    }
+
+
+    /// Returns the element assigned to `key` by weighted rendezvous hashing (highest random weight), so that changing the distribution moves as few keys as possible.
+    ///
+    /// Each element e of probability p gets the score p / -ln(h(key, e)), where h hashes the key and the element into (0, 1), and the element with the highest score is returned: a key is assigned to each element with its probability.
+    /// Since the score of an element does not depend on the other elements, adding an element only moves the keys it takes, removing an element only moves the keys it had, and changing a probability only moves keys from or to the changed element, proportionally to the change. This makes it suitable for weighted shard placement, at the cost of O(n) per key instead of the O(1) of `VoseAlias::sample_keyed()`.
+    ///
+    /// The key and the elements are hashed with `std::collections::hash_map::DefaultHasher`, with the same caveat as `VoseAlias::sample_keyed()`. Elements with a probability of 0 are never returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let shards = VoseAlias::new(vec!["s1", "s2", "s3"], vec![0.5, 0.25, 0.25]);
+    /// // a fourth shard is added, taking its share from the others
+    /// let grown = VoseAlias::new(vec!["s1", "s2", "s3", "s4"], vec![0.4, 0.2, 0.2, 0.2]);
+    /// for key in 0..1000 {
+    ///     let shard = grown.assign_consistent(&key);
+    ///     assert!(shard == "s4" || shard == shards.assign_consistent(&key));
+    /// }
+    /// ```
+    pub fn assign_consistent<K: Hash + ?Sized>(&self, key:&K) -> T {
+        let mut best:Option<(f64, usize)> = None;
+        for (i, p) in self.probabilities.iter().enumerate() {
+            if *p <= 0.0 {
+                continue;
+            }
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            self.elements[i].hash(&mut hasher);
+            let mut state = hasher.finish();
+            // 53 random bits give a uniform value in (0, 1)
+            let u = ((splitmix64(&mut state) >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+            let score = p / -u.ln();
+            if best.is_none_or(|(s, _)| score > s) {
+                best = Some((score, i));
+            }
+        }
+        match best {
+            Some((_, i)) => self.elements[i],
+            None => panic!("Internal error. No element has a positive probability. If this happened, please fill in an issue report."),
+        }
+    }
+
+
+    /// Derives a die in [0, n) and a coin in [0, 2^24) from the hash of `key`.
+    fn keyed_die_and_coin<K: Hash + ?Sized>(&self, key:&K) -> (usize, u32) {
+        if self.elements.is_empty() {
+            panic!("Internal error. The element vector is empty. If this happened, please fill in an issue report.");
+        }
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let mut state = hasher.finish();
+        let die_bits = splitmix64(&mut state);
+        let coin_bits = splitmix64(&mut state);
+        // multiplying by the range and keeping the high bits maps a uniform u64 to a uniform value in the range
+        let die = ((u128::from(die_bits) * self.elements.len() as u128) >> 64) as usize;
+        let coin = (coin_bits >> (64 - COIN_BITS)) as u32;
+        (die, coin)
+    }
+}
+
+
+/// Advances a SplitMix64 state and returns its next output, which separates the die and the coin drawn from a single hash.
+pub(crate) fn splitmix64(state:&mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyed_sampling() {
+        let va = VoseAlias::new(vec!['a', 'b', 'c'], vec![0.6, 0.3, 0.1]);
+        let mut counts = [0; 3];
+        for user in 0..10000 {
+            let element = va.sample_keyed(&user);
+            assert_eq!(va.sample_keyed(&user), element);
+            counts[va.index[&element]] += 1;
+        }
+        assert!(counts[0] > 5500 && counts[0] < 6500);
+        assert!(counts[2] > 700 && counts[2] < 1300);
+        // string keys can be given unsized
+        assert_eq!(va.sample_keyed("user"), va.sample_keyed(&String::from("user")));
+    }
+
+    #[test]
+    fn consistent_assignment() {
+        let before = VoseAlias::new(vec![1, 2, 3, 4], vec![0.25, 0.25, 0.25, 0.25]);
+        let after = VoseAlias::new(vec![1, 2, 3, 4], vec![0.4, 0.2, 0.2, 0.2]);
+        let mut moved = 0;
+        let mut ones = 0;
+        for key in 0..10000 {
+            let (b, a) = (before.assign_consistent(&key), after.assign_consistent(&key));
+            // only the element whose probability grew takes keys
+            if b != a {
+                assert_eq!(a, 1);
+                moved += 1;
+            }
+            if a == 1 {
+                ones += 1;
+            }
+        }
+        assert!(moved > 1000 && moved < 2000);
+        assert!(ones > 3600 && ones < 4400);
+
+        let va = VoseAlias::new(vec!['a', 'b'], vec![1.0, 0.0]);
+        assert!((0..100).all(|key| va.assign_consistent(&key) == 'a'));
+    }
+}
@@ -0,0 +1,262 @@
+//! A weighted sampler deferring the rebuild of its tables until the next sample, for weights updated in bursts.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{check_weights, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A weighted sampler whose weights can be updated, the alias tables being rebuilt lazily.
+///
+/// Updating a weight only stores it and marks the tables as outdated: the tables are rebuilt, in O(n), on the next sample. A burst of updates between two samples is therefore coalesced into a single rebuild, and consecutive samples are drawn in constant time.
+/// Compared to a `DynamicSampler`, which updates and samples in O(log n), this is faster when samples are much more frequent than the bursts of updates.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::LazySampler;
+///
+/// let mut sampler = LazySampler::new(vec!["a", "b", "c"], vec![1.0, 1.0, 1.0]).unwrap();
+/// // a burst of updates, rebuilt once by the next sample
+/// sampler.update_weight(&"a", 0.0).unwrap();
+/// sampler.update_weight(&"b", 0.0).unwrap();
+/// assert!(sampler.is_outdated());
+/// assert_eq!(sampler.sample(), Some("c"));
+/// assert!(!sampler.is_outdated());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LazySampler<T> where T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    index:HashMap<T, usize>,
+    weights:Vec<f64>,
+    // built over the elements of positive weight only, `None` when all the weights are 0
+    table:Option<VoseAlias<T>>,
+    outdated:bool,
+}
+
+
+impl<T> LazySampler<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns a sampler over the given elements and weights. The tables are built on the first sample.
+    ///
+    /// An error is returned if both vectors do not have the same size, if the element vector is empty or contains duplicates, or if a weight is negative or not finite.
+    pub fn new(element_vector:Vec<T>, weight_vector:Vec<f64>) -> Result<LazySampler<T>, VoseAliasError> {
+        // all the weights can be 0
+        match check_weights(element_vector.len(), &weight_vector) {
+            Ok(_) | Err(VoseAliasError::ZeroTotalWeight) => (),
+            Err(e) => return Err(e),
+        }
+        let mut index:HashMap<T, usize> = HashMap::with_capacity(element_vector.len());
+        for (i, e) in element_vector.iter().enumerate() {
+            if index.insert(*e, i).is_some() {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+
+        Ok(LazySampler {
+            elements: element_vector,
+            index,
+            weights: weight_vector,
+            table: None,
+            outdated: true,
+        })
+    }
+
+
+    /// Returns the elements of the sampler.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the current weight of an element, or `None` if it is not in the sampler.
+    pub fn weight(&self, element:&T) -> Option<f64> {
+        self.index.get(element).map(|i| self.weights[*i])
+    }
+
+
+    /// Returns `true` if a weight changed since the last rebuild of the tables.
+    pub fn is_outdated(&self) -> bool {
+        self.outdated
+    }
+
+
+    /// Changes the weight of an element, and returns its previous weight. The tables are rebuilt on the next sample, if the weight changed.
+    ///
+    /// An error is returned if the weight is negative or not finite. If the element is not in the sampler, `Ok(None)` is returned and nothing is changed.
+    pub fn update_weight(&mut self, element:&T, weight:f64) -> Result<Option<f64>, VoseAliasError> {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(VoseAliasError::InvalidWeight(weight));
+        }
+        let i = match self.index.get(element) {
+            Some(i) => *i,
+            None => return Ok(None),
+        };
+
+        let previous = self.weights[i];
+        if weight != previous {
+            self.weights[i] = weight;
+            self.outdated = true;
+        }
+        Ok(Some(previous))
+    }
+
+
+    /// Changes the weights of several elements, applied in order, and returns the number of updates of elements of the sampler. The updates of elements which are not in the sampler are ignored.
+    ///
+    /// An error is returned, and no weight is changed, if one of the weights is negative or not finite.
+    pub fn update_weights<U: IntoIterator<Item = (T, f64)>>(&mut self, updates:U) -> Result<usize, VoseAliasError> {
+        let updates:Vec<(T, f64)> = updates.into_iter().collect();
+        if let Some((_, w)) = updates.iter().find(|(_, w)| !w.is_finite() || *w < 0.0) {
+            return Err(VoseAliasError::InvalidWeight(*w));
+        }
+        let mut updated = 0;
+        for (e, w) in updates {
+            if self.update_weight(&e, w)?.is_some() {
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+
+
+    /// Adds an element with the given weight, or changes its weight if it is already in the sampler, and returns its previous weight. The tables are rebuilt on the next sample.
+    ///
+    /// An error is returned if the weight is negative or not finite.
+    pub fn insert(&mut self, element:T, weight:f64) -> Result<Option<f64>, VoseAliasError> {
+        if self.index.contains_key(&element) {
+            return self.update_weight(&element, weight);
+        }
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(VoseAliasError::InvalidWeight(weight));
+        }
+        self.index.insert(element, self.elements.len());
+        self.elements.push(element);
+        self.weights.push(weight);
+        self.outdated = true;
+        Ok(None)
+    }
+
+
+    /// Inserts the weighted elements in order, like `LazySampler::insert()`: the new elements are appended, and the weights of the existing ones are replaced. The renormalization and the rebuild of the tables happen once, on the next sample.
+    ///
+    /// An error is returned, and the sampler is left unchanged, if one of the weights is negative or not finite. `Extend` is the panicking version of this function.
+    pub fn try_extend<U: IntoIterator<Item = (T, f64)>>(&mut self, elements:U) -> Result<(), VoseAliasError> {
+        let elements:Vec<(T, f64)> = elements.into_iter().collect();
+        if let Some((_, w)) = elements.iter().find(|(_, w)| !w.is_finite() || *w < 0.0) {
+            return Err(VoseAliasError::InvalidWeight(*w));
+        }
+        self.elements.reserve(elements.len());
+        self.weights.reserve(elements.len());
+        for (e, w) in elements {
+            self.insert(e, w)?;
+        }
+        Ok(())
+    }
+
+
+    /// Returns an element sampled according to the current weights, or `None` if they are all 0. The tables are rebuilt first if they are outdated.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&mut self) -> Option<T> {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns an element sampled according to the current weights using the given generator, or `None` if they are all 0. The tables are rebuilt first if they are outdated.
+    pub fn sample_with<R: RngCore + ?Sized>(&mut self, rng:&mut R) -> Option<T> {
+        self.table().map(|table| table.sample_with(rng))
+    }
+
+
+    /// Returns the tables of the current weights, rebuilding them if they are outdated, or `None` if all the weights are 0.
+    pub fn table(&mut self) -> Option<&VoseAlias<T>> {
+        if self.outdated {
+            self.rebuild();
+        }
+        self.table.as_ref()
+    }
+
+
+    fn rebuild(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("vose_alias.rebuild", sampler = "lazy", size = self.elements.len()).entered();
+        self.table = VoseAlias::from_positive_weights(&self.elements, &self.weights);
+        self.outdated = false;
+    }
+}
+
+impl<T> Extend<(T, f64)> for LazySampler<T>
+where T: Copy + Hash + Eq + Debug {
+    /// Inserts the weighted elements in bulk, the tables being rebuilt once on the next sample. See `LazySampler::try_extend()`.
+    ///
+    /// # Panics
+    /// This function panics, leaving the sampler unchanged, if one of the weights is negative or not finite.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::LazySampler;
+    ///
+    /// let mut sampler = LazySampler::new(vec!["a"], vec![0.0]).unwrap();
+    /// sampler.extend(vec![("b", 2.0), ("c", 0.0)]);
+    /// assert_eq!(sampler.elements(), &["a", "b", "c"]);
+    /// assert_eq!(sampler.sample(), Some("b"));
+    /// # }
+    /// ```
+    fn extend<U: IntoIterator<Item = (T, f64)>>(&mut self, elements:U) {
+        if let Err(e) = self.try_extend(elements) {
+            panic!("{}", e);
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesced_updates() {
+        let mut sampler = LazySampler::new(vec![1, 2, 3], vec![1.0, 0.0, 0.0]).unwrap();
+        assert_eq!(sampler.sample(), Some(1));
+        assert!(!sampler.is_outdated());
+        assert_eq!(sampler.update_weight(&1, 1.0), Ok(Some(1.0)));
+        assert!(!sampler.is_outdated());
+
+        assert_eq!(sampler.update_weights(vec![(1, 0.0), (2, 5.0), (2, 0.0), (3, 2.0), (4, 1.0)]), Ok(4));
+        assert_eq!(sampler.weight(&2), Some(0.0));
+        assert!(sampler.is_outdated());
+        assert_eq!(sampler.table().unwrap().elements, vec![3]);
+        assert_eq!(sampler.sample(), Some(3));
+
+        assert_eq!(sampler.update_weights(vec![(1, 1.0), (2, -1.0)]), Err(VoseAliasError::InvalidWeight(-1.0)));
+        assert_eq!(sampler.weight(&1), Some(0.0));
+        sampler.update_weight(&3, 0.0).unwrap();
+        assert_eq!(sampler.sample(), None);
+    }
+
+    #[test]
+    fn bulk_extend() {
+        let mut sampler = LazySampler::new(vec![1], vec![1.0]).unwrap();
+        assert_eq!(sampler.sample(), Some(1));
+        sampler.extend((2..100).map(|i| (i, 0.0)).chain(std::iter::once((1, 0.0))));
+        sampler.extend(vec![(100, 3.0)]);
+        assert_eq!((sampler.elements().len(), sampler.weight(&1)), (100, Some(0.0)));
+        assert!(sampler.is_outdated());
+        assert_eq!(sampler.table().unwrap().elements, vec![100]);
+
+        assert_eq!(sampler.try_extend(vec![(101, 1.0), (102, f64::INFINITY)]), Err(VoseAliasError::InvalidWeight(f64::INFINITY)));
+        assert_eq!((sampler.elements().len(), sampler.is_outdated()), (100, false));
+    }
+}
@@ -4,17 +4,183 @@
 //!
 //! The algorithm implemented follows the explanation given on [this page](https://www.keithschwarz.com/darts-dice-coins/)
 //!
+//! # Panics
+//!
+//! The constructors of `VoseAlias` that panic on invalid input (`VoseAlias::new()`, `VoseAlias::new_with_index()`) and the functions sampling from degenerate tables (such as `VoseAlias::sample()` after emptying `elements`) are convenient for scripts and tests. For the programs that cannot afford a panic, the following functions never panic on library misuse, and return an error or `None` instead:
+//! - `VoseAlias::try_new()`, `VoseAlias::try_new_with_index()` and the constructors returning a `Result` return an error for invalid input;
+//! - `VoseAlias::try_sample()`, `VoseAlias::try_sample_with()`, `VoseAlias::try_sample_n()` and `VoseAlias::try_sample_constant_time()` return `None` when the tables cannot be sampled, like `WeightedSampler::sample()`;
+//! - `VoseAlias::try_prob_of()` returns an error for the unknown elements, where indexing the `prob` and `alias` tables panics;
+//! - `VoseAliasMatrix::try_sample_with()` and `VoseAliasMatrix::try_sample_label_with()` return `None` for a row out of the matrix;
+//! - `VoseAlias::try_quantile()`, `VoseAlias::try_median()` and `VoseAlias::try_mc_standard_error()` return an error for an invalid parameter, and `resampling::try_counts()` for an index out of the particles;
+//! - formatting a `VoseAlias` with `Display` or `Debug` never panics, even on empty or inconsistent tables.
+//!
+//! The other functions that can panic document it in a `# Panics` section, which names their non-panicking variant when there is one (such as `VoseAliasCache::get_or_try_new()`).
+//!
 
 
 use std::fmt;
 use std::fmt::Display;
 use std::hash::Hash;
 use std::fmt::Debug;
+use std::hint::black_box;
 use float_cmp::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 
-use rand::seq::SliceRandom;
 use rand::Rng;
+use rand_core::RngCore;
+
+use observer::ObserverSlot;
+
+mod adaptive;
+mod allocation;
+#[cfg(feature = "allocator-api2")]
+mod allocator;
+#[cfg(feature = "rkyv")]
+mod archive;
+#[cfg(feature = "bumpalo")]
+mod arena;
+#[cfg(feature = "ndarray")]
+mod array;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod bank;
+mod bootstrap;
+mod buffered;
+#[cfg(feature = "bytes")]
+mod bytes;
+mod cache;
+mod capped;
+#[cfg(feature = "statrs")]
+mod categorical;
+mod choose;
+mod combinator;
+mod compaction;
+#[cfg(any(feature = "rand-core-06", feature = "rand-core-09"))]
+mod compat;
+mod confidence;
+mod diagnostics;
+mod dirichlet;
+mod distance;
+mod distinct;
+mod distributions;
+mod dynamic;
+mod error;
+mod exclusion;
+mod export;
+#[cfg(feature = "petgraph")]
+mod graph;
+mod grouped;
+mod guard;
+mod index;
+mod keyed;
+mod lazy;
+mod macros;
+mod markov;
+mod matrix;
+mod ngram;
+#[cfg(feature = "petgraph")]
+mod node2vec;
+#[cfg(feature = "npy")]
+mod npy;
+mod observer;
+mod padded;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pity;
+#[cfg(feature = "polars")]
+mod polars;
+mod propensity;
+mod quantized;
+mod replay;
+pub mod resampling;
+#[cfg(feature = "thread-rng")]
+mod rng;
+mod round_robin;
+mod sampler;
+mod scheduled;
+#[cfg(feature = "serde")]
+mod serialization;
+mod statistics;
+mod static_alias;
+mod stats;
+#[cfg(feature = "tokio")]
+mod stream;
+mod sum_tree;
+#[cfg(feature = "metrics")]
+mod telemetry;
+#[cfg(feature = "test-support")]
+mod testing;
+#[cfg(feature = "tokenizer")]
+mod tokenizer;
+mod trace;
+mod transform;
+mod weight;
+
+pub use adaptive::{AdaptiveSampler, UpdateRule};
+#[cfg(feature = "allocator-api2")]
+pub use allocator::VoseAliasIn;
+#[cfg(feature = "rkyv")]
+pub use archive::ArchivedVoseAlias;
+pub use bank::SamplerBank;
+pub use buffered::BufferedRng;
+pub use cache::VoseAliasCache;
+pub use capped::CappedSampler;
+pub use combinator::Then;
+#[cfg(feature = "rand-core-06")]
+pub use compat::RandCore06;
+#[cfg(feature = "rand-core-09")]
+pub use compat::RandCore09;
+pub use confidence::FrequencyInterval;
+pub use diagnostics::{Deviation, Diagnostics};
+pub use dirichlet::DirichletSampler;
+pub use dynamic::DynamicSampler;
+pub use error::VoseAliasError;
+#[cfg(feature = "petgraph")]
+pub use graph::GraphSampler;
+pub use grouped::GroupedAlias;
+pub use guard::WeightsMut;
+pub use indexmap::IndexMap;
+pub use index::SlotIndex;
+pub use lazy::LazySampler;
+pub use markov::MarkovChain;
+pub use matrix::VoseAliasMatrix;
+pub use ngram::{NGramBuilder, NGramModel};
+#[cfg(feature = "petgraph")]
+pub use node2vec::Node2VecSampler;
+pub use observer::{SampleEvent, SampleObserver};
+pub use padded::PaddedVoseAlias;
+pub use pity::PitySampler;
+pub use propensity::PropensitySampler;
+pub use quantized::{FixedPoint, QuantizedVoseAlias};
+pub use replay::{ReplayBuffer, ReplaySample};
+pub use round_robin::RoundRobin;
+pub use sampler::WeightedSampler;
+pub use scheduled::{Schedule, ScheduledSampler};
+pub use static_alias::StaticVoseAlias;
+pub use stats::{SamplingStats, StatsEntry, StatsReport};
+#[cfg(feature = "tokio")]
+pub use stream::SampleStream;
+#[cfg(feature = "metrics")]
+pub use telemetry::MetricsObserver;
+pub use trace::SamplingTrace;
+pub use transform::Partition;
+pub use weight::Weight;
+
+/// Maximum number of elements shown by the `Display` implementation of `VoseAlias`.
+const DISPLAY_MAX_ELEMENTS:usize = 20;
+
+/// Number of most probable elements shown by the `Debug` implementation of `VoseAlias`.
+const DEBUG_TOP_ELEMENTS:usize = 5;
+
+/// Tolerance used when checking the probabilities stored in precomputed tables, to account for floating point rounding errors during the construction.
+const TABLE_TOLERANCE:f32 = 0.0001;
+
+/// Number of bits of the coin, as many as the mantissa of the `f32` probabilities: the coin is an integer in [0, 2^24), compared to the probability of the column × 2^24.
+const COIN_BITS:u32 = 24;
+
+/// Number of values of the coin, 2^24, as a float to scale the probabilities of the columns.
+const COIN_SCALE:f32 = (1_u32 << COIN_BITS) as f32;
 
 
 /////////////////////////////////////////////
@@ -34,18 +200,31 @@ use rand::Rng;
 /// - Hash
 /// - Eq
 /// - Debug
-#[derive(Debug, Clone)]
-pub struct VoseAlias <T> where T: Display + Copy + Hash + Eq + Debug{
+///
+/// `T` only needs to implement `Display` to display the object itself, and for the few functions writing the elements as text (such as `VoseAlias::to_json_pretty()`).
+///
+/// The alias and probability tables are `IndexMap`s (re-exported by this crate), which iterate in the order of `elements`: iterating over them, displaying the object and serializing it are deterministic across runs and platforms.
+///
+/// The second type parameter, `I`, is the integer type used to store the indexes of the internal slot tables (see `SlotIndex`). It defaults to `u32`; `u16` packs the tables of small distributions into less memory, and `usize` allows giant distributions. Use `VoseAlias::new_with_index()` to choose it.
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct VoseAlias <T, I = u32> where T: Copy + Hash + Eq + Debug, I: SlotIndex{
     pub elements:Vec<T>,
-    pub alias:HashMap<T, T>,
-    pub prob:HashMap<T, f32>,
-    _private:()
-    
+    pub alias:IndexMap<T, T>,
+    pub prob:IndexMap<T, f32>,
+    // the same tables, indexed like `elements`, used by the sampling functions
+    slot_prob:Vec<f32>,
+    slot_alias:Vec<I>,
+    // the position of each element in `elements`, and its requested probability, in full and in the precision given to `probabilities()`
+    index:HashMap<T, usize>,
+    probabilities:Vec<f64>,
+    input_probabilities:Vec<f32>,
+    observer:ObserverSlot,
 }
 
 
 impl<T> VoseAlias<T>
-where T: Display + Copy + Hash + Eq + Debug {
+where T: Copy + Hash + Eq + Debug {
 
     /// Returns the Vose-Alias object containing the element vector as well as the alias and probability tables.
     ///
@@ -59,6 +238,8 @@ where T: Display + Copy + Hash + Eq + Debug {
     /// 1. the `element_vector` and the `probability_vector` do not contain the same number of elements
     /// 2. the sum of the elements in `probability_vector` is not equal to 1 (with a floating number precision of 0.0001), meaning that `probability_vector` does not describe a well formed probability distribution
     ///
+    /// See `VoseAlias::try_new()` for a version of this function that returns an error instead of panicking.
+    ///
     /// # Examples
     /// ```
     /// use vose_alias::VoseAlias;
@@ -66,105 +247,574 @@ where T: Display + Copy + Hash + Eq + Debug {
     /// // Creates a Vose-Alias object from a list of Integer elements
     /// let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
     /// ```
-    
     pub fn new(element_vector:Vec<T>, probability_vector:Vec<f32>) -> VoseAlias<T> {
+        match VoseAlias::try_new(element_vector, probability_vector) {
+            Ok(va) => va,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+
+    /// Returns the Vose-Alias object containing the element vector as well as the alias and probability tables, or an error if the input does not describe a valid distribution.
+    ///
+    /// This function performs the same initialization as `VoseAlias::new()`, but never panics. An error is returned if:
+    /// 1. the `element_vector` and the `probability_vector` do not contain the same number of elements
+    /// 2. the vectors are empty
+    /// 3. the `element_vector` contains the same element twice
+    /// 4. one of the probabilities is negative or not a number
+    /// 5. the sum of the elements in `probability_vector` is not equal to 1
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, VoseAliasError};
+    ///
+    /// let va = VoseAlias::try_new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+    /// assert!(va.is_ok());
+    ///
+    /// let va = VoseAlias::try_new(vec![1, 2, 3], vec![0.5, 0.2, 0.2, 0.1]);
+    /// assert_eq!(va, Err(VoseAliasError::SizeMismatch { elements: 3, probabilities: 4 }));
+    /// ```
+    pub fn try_new(element_vector:Vec<T>, probability_vector:Vec<f32>) -> Result<VoseAlias<T>, VoseAliasError> {
+        VoseAlias::try_new_with_index(element_vector, probability_vector)
+    }
+
+
+    /// Returns the Vose-Alias object sampling each element with its observed frequency, given the number of times each element was observed.
+    ///
+    /// An error is returned if both vectors do not have the same size, if the element vector is empty or contains duplicates, or if all the counts are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let va = VoseAlias::from_counts(vec!["the", "cat", "sat"], vec![6, 1, 1]).unwrap();
+    /// assert_eq!(va.prob_of(&"the"), 0.75);
+    /// ```
+    pub fn from_counts(element_vector:Vec<T>, count_vector:Vec<u64>) -> Result<VoseAlias<T>, VoseAliasError> {
+        VoseAlias::try_from_weights(element_vector, count_vector)
+    }
+
+
+    /// Returns the Vose-Alias object built from the counts with additive (Laplace) smoothing: each element is given a weight of its count plus `alpha`. The elements of `extended_support` that are not part of `element_vector` are added after them with the weight `alpha`, as elements observed 0 times.
+    ///
+    /// `alpha` = 0 gives `VoseAlias::from_counts()`. To smooth the probabilities of an existing distribution instead, see `VoseAlias::smooth()`.
+    ///
+    /// An error is returned if both vectors do not have the same size, if the element vector contains duplicates, if `alpha` is negative or not finite, if there is no element, or if all the smoothed counts are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// // add-one smoothing of bigram counts, with an unseen word
+    /// let va = VoseAlias::from_counts_smoothed(vec!["cat", "dog", "cow"], vec![3, 1, 0], 1.0, &["unicorn"]).unwrap();
+    /// // (0 + 1) / (4 + 4)
+    /// assert!((va.prob_of(&"cow") - 0.125).abs() < 1e-6);
+    /// assert!((va.prob_of(&"unicorn") - 0.125).abs() < 1e-6);
+    /// ```
+    pub fn from_counts_smoothed(element_vector:Vec<T>, count_vector:Vec<u64>, alpha:f64, extended_support:&[T]) -> Result<VoseAlias<T>, VoseAliasError> {
+        if !alpha.is_finite() || alpha < 0.0 {
+            return Err(VoseAliasError::InvalidParameter { name: "alpha", value: alpha });
+        }
+        if element_vector.len() != count_vector.len() {
+            return Err(VoseAliasError::SizeMismatch { elements: element_vector.len(), probabilities: count_vector.len() });
+        }
+
+        let mut known:HashSet<T> = element_vector.iter().copied().collect();
+        let mut elements = element_vector;
+        let mut weights:Vec<f64> = count_vector.iter().map(|&c| c as f64 + alpha).collect();
+        for e in extended_support {
+            if known.insert(*e) {
+                elements.push(*e);
+                weights.push(alpha);
+            }
+        }
+        VoseAlias::try_from_weights(elements, weights)
+    }
+
+
+    /// Returns the Vose-Alias object sampling each element proportionally to its weight, given as any `Weight` type: integer counts, `f64` probabilities, non-zero integers... The weights do not need to sum to 1: they are normalized in `f64` before building the tables.
+    ///
+    /// An error is returned if both vectors do not have the same size, if the element vector is empty or contains duplicates, if one of the weights is negative or not finite, or if all the weights are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::num::NonZeroU64;
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let counts = VoseAlias::try_from_weights(vec!["a", "b"], vec![3_u32, 1]).unwrap();
+    /// let probabilities = VoseAlias::try_from_weights(vec!["a", "b"], vec![0.75_f64, 0.25]).unwrap();
+    /// let weights:Vec<NonZeroU64> = vec![NonZeroU64::new(6).unwrap(), NonZeroU64::new(2).unwrap()];
+    /// let non_zero = VoseAlias::try_from_weights(vec!["a", "b"], weights).unwrap();
+    /// assert_eq!(counts, probabilities);
+    /// assert_eq!(counts, non_zero);
+    /// assert_eq!(counts.prob_of(&"a"), 0.75);
+    /// ```
+    pub fn try_from_weights<W: Weight>(element_vector:Vec<T>, weight_vector:Vec<W>) -> Result<VoseAlias<T>, VoseAliasError> {
+        let weight_vector:Vec<f64> = weight_vector.into_iter().map(Weight::to_f64).collect();
+        VoseAlias::from_weights(element_vector, weight_vector)
+    }
+
+
+    /// Returns the Vose-Alias object sampling each element with the same probability, 1 / n.
+    ///
+    /// Unlike a vector of n probabilities 1 / n given to `VoseAlias::try_new()`, which may not sum to 1 exactly for n like 3 or 7, the probabilities are computed from equal weights. An error is returned if the element vector is empty or contains duplicates.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let weekdays = VoseAlias::uniform(vec!["mon", "tue", "wed", "thu", "fri", "sat", "sun"]).unwrap();
+    /// assert!((weekdays.prob_of(&"wed") - 1.0 / 7.0).abs() < 1e-6);
+    /// ```
+    pub fn uniform(element_vector:Vec<T>) -> Result<VoseAlias<T>, VoseAliasError> {
+        let weight_vector = vec![1.0; element_vector.len()];
+        VoseAlias::from_weights(element_vector, weight_vector)
+    }
+
+
+    /// Returns the Vose-Alias object over the `n` elements `element(0)`, ..., `element(n - 1)`, the element `element(i)` having a probability proportional to `weight(i)`.
+    ///
+    /// Both closures are called once per index, in increasing order. For a distribution over the indexes themselves, see `VoseAlias::from_pmf()`.
+    /// An error is returned if `n` is 0, if two indexes give the same element, if a weight is negative or not finite, or if all the weights are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// // the letters of the alphabet, with decreasing weights
+    /// let letters = VoseAlias::from_fn(26, |i| (b'a' + i as u8) as char, |i| (26 - i) as f64).unwrap();
+    /// assert!(letters.prob_of(&'a') > letters.prob_of(&'z'));
+    /// ```
+    pub fn from_fn<E, W>(n:usize, element:E, weight:W) -> Result<VoseAlias<T>, VoseAliasError>
+    where E: FnMut(usize) -> T, W: FnMut(usize) -> f64 {
+        VoseAlias::from_weights((0..n).map(element).collect(), (0..n).map(weight).collect())
+    }
+
+
+    /// Returns the Vose-Alias object always sampling `element`, with probability 1.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::singleton('x');
+    /// assert_eq!(va.sample_n(3), vec!['x', 'x', 'x']);
+    /// # }
+    /// ```
+    pub fn singleton(element:T) -> VoseAlias<T> {
+        VoseAlias::build(vec![element], vec![1.0], vec![1.0])
+    }
+
+
+    /// Returns the empirical distribution of the given occurrences: each distinct element is sampled with its frequency among them. The elements are stored in the order of their first occurrence.
+    ///
+    /// An error is returned if there is no occurrence.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let letters = VoseAlias::from_occurrences("abracadabra".chars()).unwrap();
+    /// assert_eq!(letters.elements, vec!['a', 'b', 'r', 'c', 'd']);
+    /// assert!((letters.prob_of(&'a') - 5.0 / 11.0).abs() < 1e-6);
+    ///
+    /// let words = VoseAlias::from_occurrences("to be or not to be".split_whitespace()).unwrap();
+    /// assert!((words.prob_of(&"be") - 1.0 / 3.0).abs() < 1e-6);
+    /// ```
+    pub fn from_occurrences(occurrences:impl IntoIterator<Item = T>) -> Result<VoseAlias<T>, VoseAliasError> {
+        let mut counts:IndexMap<T, u64> = IndexMap::new();
+        for e in occurrences {
+            *counts.entry(e).or_insert(0) += 1;
+        }
+        let (element_vector, count_vector) = counts.into_iter().unzip();
+        VoseAlias::from_counts(element_vector, count_vector)
+    }
+
+
+    /// Returns the distribution estimated from the observed `samples`: the maximum likelihood estimate, each distinct element being sampled with its frequency, with add-`smoothing` (Laplace) smoothing. The elements are stored in the order of their first occurrence.
+    ///
+    /// Each element is given a weight of its count plus `smoothing`: `smoothing` = 0 gives the empirical distribution (see `VoseAlias::from_occurrences()`), and larger values pull the probabilities towards the uniform distribution, which limits the overfitting of small samples. Only the observed elements are part of the distribution: see `VoseAlias::fit_from_samples_over()` to also give a probability to the unobserved ones.
+    ///
+    /// An error is returned if there is no sample, or if `smoothing` is negative or not finite.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let rolls = vec![6, 6, 6, 1];
+    /// let fitted = VoseAlias::fit_from_samples(rolls.iter().copied(), 1.0).unwrap();
+    /// assert!((fitted.prob_of(&6) - 4.0 / 6.0).abs() < 1e-6);
+    /// ```
+    pub fn fit_from_samples(samples:impl IntoIterator<Item = T>, smoothing:f64) -> Result<VoseAlias<T>, VoseAliasError> {
+        VoseAlias::fit_counts(IndexMap::new(), samples, smoothing, false)
+    }
+
+
+    /// Returns the distribution over `support` estimated from the observed `samples`, with add-`smoothing` (Laplace) smoothing: each element of `support` is given a weight of its count plus `smoothing`, so that the elements never observed keep a probability if `smoothing` is positive.
+    ///
+    /// The elements are stored in the order of `support`. An error is returned if `support` is empty or contains duplicates, if a sample is not part of `support`, if `smoothing` is negative or not finite, or if there is no sample and `smoothing` is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let rolls = vec![6, 6, 6, 1];
+    /// let fitted = VoseAlias::fit_from_samples_over((1..=6).collect(), rolls, 1.0).unwrap();
+    /// assert!((fitted.prob_of(&2) - 0.1).abs() < 1e-6);
+    /// assert!((fitted.prob_of(&6) - 0.4).abs() < 1e-6);
+    /// ```
+    pub fn fit_from_samples_over(support:Vec<T>, samples:impl IntoIterator<Item = T>, smoothing:f64) -> Result<VoseAlias<T>, VoseAliasError> {
+        VoseAlias::<T>::check_elements(&support)?;
+        let counts:IndexMap<T, u64> = support.into_iter().map(|e| (e, 0)).collect();
+        VoseAlias::fit_counts(counts, samples, smoothing, true)
+    }
+
+
+    /// Counts the samples on top of `counts`, and builds the distribution of the counts plus `smoothing`, for `VoseAlias::fit_from_samples()` and `VoseAlias::fit_from_samples_over()`. A sample missing from `counts` is an error if `fixed_support` is set.
+    fn fit_counts(mut counts:IndexMap<T, u64>, samples:impl IntoIterator<Item = T>, smoothing:f64, fixed_support:bool) -> Result<VoseAlias<T>, VoseAliasError> {
+        if !smoothing.is_finite() || smoothing < 0.0 {
+            return Err(VoseAliasError::InvalidParameter { name: "smoothing", value: smoothing });
+        }
+        for e in samples {
+            match counts.get_mut(&e) {
+                Some(count) => *count += 1,
+                None if fixed_support => return Err(VoseAliasError::UnknownElement(format!("{:?}", e))),
+                None => {
+                    counts.insert(e, 1);
+                },
+            }
+        }
+        let (element_vector, weight_vector) = counts.into_iter().map(|(e, c)| (e, c as f64 + smoothing)).unzip();
+        VoseAlias::from_weights(element_vector, weight_vector)
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the Vose-Alias object containing the element vector as well as the alias and probability tables, using `I` to store the slot indexes.
+    ///
+    /// This function behaves like `VoseAlias::new()`, but lets the caller choose the index type of the tables (see `SlotIndex`).
+    ///
+    /// # Panics
+    ///
+    /// The function panics in the same cases as `VoseAlias::new()`, and if there are more elements than `I` can index.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// // Creates a Vose-Alias object storing its indexes on 16 bits
+    /// let va:VoseAlias<i32, u16> = VoseAlias::new_with_index(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+    /// ```
+    pub fn new_with_index(element_vector:Vec<T>, probability_vector:Vec<f32>) -> VoseAlias<T, I> {
+        match VoseAlias::try_new_with_index(element_vector, probability_vector) {
+            Ok(va) => va,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+
+    /// Returns the Vose-Alias object containing the element vector as well as the alias and probability tables, using `I` to store the slot indexes, or an error if the input does not describe a valid distribution.
+    ///
+    /// This function returns the same errors as `VoseAlias::try_new()`, and `VoseAliasError::TooManyElements` if there are more elements than `I` can index.
+    pub fn try_new_with_index(element_vector:Vec<T>, probability_vector:Vec<f32>) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let size = element_vector.len();
+        traced_construction("probabilities", size, || VoseAlias::build_from_probabilities(element_vector, probability_vector))
+    }
+
+
+    /// Checks the probabilities and builds the Vose-Alias object, for `VoseAlias::try_new_with_index()`.
+    fn build_from_probabilities(element_vector:Vec<T>, probability_vector:Vec<f32>) -> Result<VoseAlias<T, I>, VoseAliasError> {
         let size_p = probability_vector.len();
         let size_e = element_vector.len();
         // some sanity checks
         if size_p != size_e {
-            panic!("Both vectors should contain the same number of elements");
+            return Err(VoseAliasError::SizeMismatch { elements: size_e, probabilities: size_p });
         }
+        VoseAlias::<T, I>::check_elements(&element_vector)?;
 
         let mut sum = 0.0;
         for p in &probability_vector {
-            sum = sum + p;
+            if p.is_nan() || *p < 0.0 {
+                return Err(VoseAliasError::InvalidProbability(*p));
+            }
+            sum += p;
         }
 
         if !approx_eq!(f32, sum, 1.0, ulps=4) {
-            panic!("Probability vector does not sum to 1");
+            return Err(VoseAliasError::NotNormalized(sum));
         }
 
-        
-        // starting the actual init
-        let size = probability_vector.len();
-        let mut small:Vec<T> = Vec::new();
-        let mut large:Vec<T> = Vec::new();
-	let mut scaled_probability_vector:HashMap<T, f32> = HashMap::new();
+        // multiply each proba by size
+        let size = probability_vector.len() as f32;
+        let scaled_probability_vector:Vec<f32> = probability_vector.iter().map(|p| p * size).collect();
+        let requested:Vec<f64> = probability_vector.iter().map(|p| f64::from(*p)).collect();
+        Ok(VoseAlias::build(element_vector, scaled_probability_vector, requested))
+    }
+
 
-        let mut alias:HashMap<T, T> = HashMap::new();
-        let mut prob:HashMap<T, f32> = HashMap::new();
+    /// Returns the Vose-Alias object sampling each element proportionally to its weight. The weights do not need to sum to 1: they are normalized before building the tables.
+    ///
+    /// An error is returned if the element vector is not valid (see `VoseAlias::try_new()`), if one of the weights is negative or not finite, or if all the weights are 0.
+    pub(crate) fn from_weights(element_vector:Vec<T>, weight_vector:Vec<f64>) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let size = element_vector.len();
+        traced_construction("weights", size, || VoseAlias::build_from_weights(element_vector, weight_vector))
+    }
 
-        // multiply each proba by size
+
+    /// Checks and normalizes the weights and builds the Vose-Alias object, for `VoseAlias::from_weights()` and `VoseAlias::from_positive_weights()`.
+    fn build_from_weights(element_vector:Vec<T>, weight_vector:Vec<f64>) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let total = check_weights(element_vector.len(), &weight_vector)?;
+        VoseAlias::<T, I>::check_elements(&element_vector)?;
+
+        let size = weight_vector.len() as f64;
+        let requested:Vec<f64> = weight_vector.iter().map(|w| w / total).collect();
+        let scaled_probability_vector:Vec<f32> = requested.iter().map(|p| (p * size) as f32).collect();
+        Ok(VoseAlias::build(element_vector, scaled_probability_vector, requested))
+    }
+
+
+    /// Builds a Vose-Alias object over the elements with a positive weight only, so that the elements of weight 0 can never be sampled. Returns `None` if no weight is positive.
+    ///
+    /// The weights must be finite and non-negative, and the elements must be distinct.
+    pub(crate) fn from_positive_weights(element_vector:&[T], weight_vector:&[f64]) -> Option<VoseAlias<T, I>> {
+        let (elements, weights):(Vec<T>, Vec<f64>) = element_vector.iter().copied().zip(weight_vector.iter().copied())
+            .filter(|(_, w)| *w > 0.0)
+            .unzip();
+        // an empty distribution is an expected outcome here, not an error worth tracing
+        match VoseAlias::build_from_weights(elements, weights) {
+            Ok(va) => Some(va),
+            Err(VoseAliasError::EmptyDistribution) | Err(VoseAliasError::ZeroTotalWeight) => None,
+            Err(e) => panic!("Internal error. The weights are not valid: {}. If this happened, please fill in an issue report.", e),
+        }
+    }
+
+
+    /// Checks that the element vector is not empty, can be indexed with `I`, and does not contain duplicates.
+    fn check_elements(element_vector:&[T]) -> Result<(), VoseAliasError> {
+        let size = element_vector.len();
+        if size == 0 {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        if size > I::CAPACITY {
+            return Err(VoseAliasError::TooManyElements { elements: size, capacity: I::CAPACITY });
+        }
+
+        let mut seen:HashSet<T> = HashSet::with_capacity(size);
+        for e in element_vector {
+            if !seen.insert(*e) {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+        Ok(())
+    }
+
+
+    /// Runs the Vose-Alias initialization, given the probabilities multiplied by the number of elements, and the requested probabilities.
+    fn build(element_vector:Vec<T>, mut scaled_probability_vector:Vec<f32>, requested:Vec<f64>) -> VoseAlias<T, I> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("vose_alias.build", size = element_vector.len()).entered();
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let size = scaled_probability_vector.len();
+        let mut slot_alias:Vec<usize> = vec![0; size];
+        let mut slot_prob:Vec<f32> = vec![0.0; size];
+        build_slots(&mut scaled_probability_vector, &mut slot_prob, &mut slot_alias, &mut Vec::new(), &mut Vec::new());
+        let va:VoseAlias<T, I> = VoseAlias::from_slots(element_vector, slot_prob, &slot_alias, Some(requested));
+        #[cfg(feature = "log")]
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!("built the tables of {} elements", va.elements.len());
+            for (i, e) in va.elements.iter().enumerate() {
+                log::debug!("column {} ({:?}): kept with probability {}, alias {:?}", i, e, va.slot_prob[i], va.elements[va.slot_alias[i].to_usize()]);
+            }
+        }
+        #[cfg(feature = "metrics")]
+        ::metrics::histogram!("vose_alias.build_seconds").record(start.elapsed().as_secs_f64());
+        va
+    }
+
+
+    /// Builds the Vose-Alias object from the elements and the slot tables, indexed like `elements`. An element without alias has its own index in `slot_alias`.
+    ///
+    /// The requested probabilities are computed back from the tables if they are not given.
+    fn from_slots(elements:Vec<T>, slot_prob:Vec<f32>, slot_alias:&[usize], requested:Option<Vec<f64>>) -> VoseAlias<T, I> {
+        let mut alias:IndexMap<T, T> = IndexMap::new();
+        let mut prob:IndexMap<T, f32> = IndexMap::with_capacity(elements.len());
+        for (i, e) in elements.iter().enumerate() {
+            if slot_alias[i] != i {
+                alias.insert(*e, elements[slot_alias[i]]);
+            }
+            prob.insert(*e, slot_prob[i]);
+        }
+
+        let index:HashMap<T, usize> = elements.iter().enumerate().map(|(i, e)| (*e, i)).collect();
+        let mut va = VoseAlias {
+	    elements,
+            alias,
+            prob,
+            slot_prob,
+            slot_alias: slot_alias.iter().map(|a| I::from_usize(*a)).collect(),
+            index,
+            probabilities: Vec::new(),
+            input_probabilities: Vec::new(),
+            observer: ObserverSlot::empty(),
+        };
+        va.probabilities = match requested {
+            Some(requested) => requested,
+            None => va.table_probabilities(),
+        };
+        va.input_probabilities = va.probabilities.iter().map(|p| *p as f32).collect();
+        va
+    }
+
+
+    /// Rebuilds a Vose-Alias object from previously computed tables, checking that they respect the invariants of the method.
+    ///
+    /// `prob` and `alias` are indexed like `elements`: `prob[i]` is the probability of keeping `elements[i]` when its column is selected, and `alias[i]` the index of the element returned otherwise. All checks are performed, so this function can be used on untrusted data.
+    pub(crate) fn from_tables(elements:Vec<T>, prob:Vec<f32>, alias:Vec<Option<usize>>) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        VoseAlias::from_tables_with_probabilities(elements, prob, alias, None)
+    }
+
+
+    /// Rebuilds a Vose-Alias object like `VoseAlias::from_tables()`, with the requested probabilities if they were stored with the tables. They are computed back from the tables otherwise.
+    ///
+    /// The stored probabilities are checked against the tables: they must sum to 1 and match the probabilities the tables represent, up to the precision of the tables. Otherwise `probabilities()`, `prob_of()` and the comparisons would describe another distribution than the one sampled.
+    pub(crate) fn from_tables_with_probabilities(elements:Vec<T>, prob:Vec<f32>, alias:Vec<Option<usize>>, requested:Option<Vec<f64>>) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let size = elements.len();
+        traced_construction("tables", size, || VoseAlias::build_from_tables(elements, prob, alias, requested))
+    }
+
+
+    /// Checks the tables and builds the Vose-Alias object, for `VoseAlias::from_tables()`.
+    fn build_from_tables(elements:Vec<T>, prob:Vec<f32>, alias:Vec<Option<usize>>, requested:Option<Vec<f64>>) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let size = elements.len();
+        if size == 0 {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        if size > I::CAPACITY {
+            return Err(VoseAliasError::TooManyElements { elements: size, capacity: I::CAPACITY });
+        }
+        if prob.len() != size || alias.len() != size {
+            return Err(VoseAliasError::CorruptTable(format!("expected {} probabilities and aliases, found {} and {}", size, prob.len(), alias.len())));
+        }
+
+        let mut seen:HashSet<T> = HashSet::with_capacity(size);
+        for e in &elements {
+            if !seen.insert(*e) {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+
+        let mut slot_alias:Vec<usize> = (0..size).collect();
+        let mut slot_prob:Vec<f32> = vec![1.0; size];
         for i in 0..size {
-            let p:f32 = probability_vector[i];
-            let e:T = element_vector[i];
-            let scaled_proba = p * (size as f32);
-            scaled_probability_vector.insert(e, scaled_proba);
+            let p = prob[i];
+            // the construction can leave rounding errors in the probabilities
+            if !p.is_finite() || !(-TABLE_TOLERANCE..=1.0 + TABLE_TOLERANCE).contains(&p) {
+                return Err(VoseAliasError::CorruptTable(format!("probability {} of element {:?} is not in [0, 1]", p, elements[i])));
+            }
+            match alias[i] {
+                Some(a) if a < size => {
+                    slot_alias[i] = a;
+                    slot_prob[i] = p;
+                },
+                Some(a) => return Err(VoseAliasError::CorruptTable(format!("alias index {} of element {:?} is out of bounds", a, elements[i]))),
+                None if p < 1.0 - TABLE_TOLERANCE => return Err(VoseAliasError::CorruptTable(format!("element {:?} has a probability lower than 1 but no alias", elements[i]))),
+                // without an alias the element must always be kept
+                None => (),
+            }
+        }
 
-            if scaled_proba < 1.0 {
-                small.push(e);
+        if let Some(requested) = &requested {
+            if requested.len() != size {
+                return Err(VoseAliasError::CorruptTable(format!("expected {} probabilities, found {}", size, requested.len())));
             }
-            else {
-                large.push(e);
+            if let Some(p) = requested.iter().find(|p| !p.is_finite() || !(0.0..=1.0).contains(*p)) {
+                return Err(VoseAliasError::CorruptTable(format!("requested probability {} is not in [0, 1]", p)));
             }
         }
 
-	// emptying one column first
-        while !(small.is_empty() || large.is_empty()) {    
-	    // removing the element from small and large
-            if let (Some(l), Some(g)) = (small.pop(), large.pop()) {
-		// put g in the alias vector
-		alias.insert(l, g);
-		// getting the probability of the small element
-		if let Some(p_l) = scaled_probability_vector.get(&l) {
-		    // put it in the prob vector
-		    prob.insert(l, *p_l);
-
-		    // update the probability for g
-		    if let Some(p_g) = scaled_probability_vector.get(&g) { 
-			let new_p_g = (*p_g + *p_l) - 1.0;
-			// update scaled_probability_vector
-			scaled_probability_vector.insert(g, new_p_g);
-			if new_p_g < 1.0 {
-			    small.push(g);
-			}
-			else {
-			    large.push(g);
-			}
-		    };
-		    
-		}
-	    }
+        let checked = requested.is_some();
+        let va:VoseAlias<T, I> = VoseAlias::from_slots(elements, slot_prob, &slot_alias, requested);
+        if checked {
+            let tolerance = f64::from(TABLE_TOLERANCE);
+            let sum:f64 = va.probabilities.iter().sum();
+            if (sum - 1.0).abs() > tolerance {
+                return Err(VoseAliasError::CorruptTable(format!("the requested probabilities sum to {}", sum)));
+            }
+            let represented = va.table_probabilities();
+            if let Some(i) = (0..size).find(|i| (va.probabilities[*i] - represented[*i]).abs() > tolerance) {
+                return Err(VoseAliasError::CorruptTable(format!("requested probability {} of element {:?} does not match the tables ({})", va.probabilities[i], va.elements[i], represented[i])));
+            }
         }
+        Ok(va)
+    }
 
-	// finishing the init
-	while !large.is_empty() {
-	    if let Some(g) = large.pop() {
-		// println!("Last but not least: g = {}", g);
-		prob.insert(g, 1.0);
-	    };
-	}
 
-	while !small.is_empty() {
-	    if let Some(l) = small.pop() {
-		// println!("Last but not least: l = {}", l);
-		prob.insert(l, 1.0);
-	    }
+    /// Returns the probability of each element given at construction, in the order of `elements`.
+    ///
+    /// The slot tables transform the probabilities, so they are kept aside to describe the distribution exactly: the weights given to the constructors taking weights or counts are normalized to sum to 1, while the probabilities given to `VoseAlias::new()` are kept as they are. The probabilities are also written when the object is serialized. For tables restored without them (such as the first version of the serialized format), they are computed back from the tables.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::from_counts(vec!["a", "b", "c"], vec![1, 1, 2]).unwrap();
+    /// assert_eq!(va.probabilities(), &[0.25, 0.25, 0.5]);
+    /// ```
+    pub fn probabilities(&self) -> &[f32] {
+        &self.input_probabilities
+    }
+
+
+    /// Returns the probability of `element`, or an error (`VoseAliasError::UnknownElement`) if it is not part of the distribution.
+    ///
+    /// Unlike `WeightedSampler::prob_of()`, an unknown element is distinguished from an element of probability 0, and unlike indexing `prob`, it does not panic.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["a", "b"], vec![1.0, 0.0]);
+    /// assert_eq!(va.try_prob_of(&"b"), Ok(0.0));
+    /// assert!(va.try_prob_of(&"c").is_err());
+    /// ```
+    pub fn try_prob_of(&self, element:&T) -> Result<f64, VoseAliasError> {
+	match self.index.get(element).and_then(|i| self.probabilities.get(*i)) {
+	    Some(p) => Ok(*p),
+	    None => Err(VoseAliasError::UnknownElement(format!("{:?}", element))),
 	}
+    }
+
 
-        VoseAlias {
-	    elements: element_vector,
-            alias: alias,
-            prob: prob,
-            _private: ()
+    /// Returns the probability of each element, in the order of `elements`, computed back from the slot tables.
+    ///
+    /// The probability of an element is the probability of keeping it in its own column, plus the probability of reaching it as the alias of another column, divided by the number of columns.
+    pub(crate) fn table_probabilities(&self) -> Vec<f64> {
+        let size = self.elements.len();
+        let mut mass:Vec<f64> = vec![0.0; size];
+        for i in 0..size {
+            let p = f64::from(self.slot_prob[i]).clamp(0.0, 1.0);
+            let a = self.slot_alias[i].to_usize();
+            if a == i {
+                mass[i] += 1.0;
+            }
+            else {
+                mass[i] += p;
+                mass[a] += 1.0 - p;
+            }
         }
+        mass.iter().map(|m| m / size as f64).collect()
     }
 
 
-    
     /// Returns a sampled element from a previously created Vose-Alias object.
     ///
     /// This function uses a `VoseAlias` object previously created using the method `vose_alias::new()` to sample in linear time an element of type `T`.
+    /// The random numbers are drawn from `rand::thread_rng()`, or from a faster generator if the `small-rng` or `xoshiro` feature is enabled.
     ///
     /// # Panics
     /// This function panics only if the lists created in `vose_alias::new()` are not correctly form, which would indicate a internal bug in the code.
@@ -180,103 +830,451 @@ where T: Display + Copy + Hash + Eq + Debug {
     /// println!("{}", element);
     /// 
     /// ```
+    #[cfg(feature = "thread-rng")]
     pub fn sample(&self) -> T {
-	let (i, num) = self.roll_die_and_flip_coin();
-	return self.select_element(i, num);
+	rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a sampled element from a previously created Vose-Alias object, using the given random number generator.
+    ///
+    /// This function behaves like `VoseAlias::sample()`, but draws the die and the coin from `rng`. Passing the same generator to consecutive calls avoids fetching the thread-local generator for every sample, and passing a seeded generator makes the sampling reproducible.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..10 {
+    ///     println!("{}", va.sample_with(&mut rng));
+    /// }
+    /// # }
+    /// ```
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> T {
+	let (i, num) = self.roll_die_and_flip_coin(rng);
+	self.select_element(i, num)
+    }
+
+
+    /// Returns a sampled element like `VoseAlias::sample()`, or `None` instead of panicking if there is no element to sample from.
+    ///
+    /// A `VoseAlias` object built by the constructors always has at least one element, but its public `elements` vector can be emptied or truncated afterwards, for instance by the code filtering distributions down: `None` is returned if the element vector is empty or does not match the tables.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let mut va = VoseAlias::singleton("only");
+    /// assert_eq!(va.try_sample(), Some("only"));
+    /// va.elements.clear();
+    /// assert_eq!(va.try_sample(), None);
+    /// ```
+    #[cfg(feature = "thread-rng")]
+    pub fn try_sample(&self) -> Option<T> {
+	rng::with_default_rng(|rng| self.try_sample_with(rng))
     }
 
 
-    /// This function rolls the die and flip the coin to select the right element using `rand` usual RNG. It returns the generated number. This function is used by the `sample` function and has been decoupled from the `sample` function to allow unit tests on the `sample` function, using pre-determined series of numbers. 
-    fn roll_die_and_flip_coin(&self) -> (T, u16) {
-	let i:T;
-	match self.elements.choose(&mut rand::thread_rng()) {
-	    Some(e) => i = *e,
-	    None => panic!("Internal error. The element vector is empty. If this happened, please fill in an issue report."),
+    /// Returns a sampled element like `VoseAlias::sample_with()`, or `None` if there is no element to sample from (see `VoseAlias::try_sample()`).
+    pub fn try_sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<T> {
+	if !self.is_sampleable() {
+	    return None;
 	}
-	let num = rand::thread_rng().gen_range(0, 101);
+	Some(self.sample_with(rng))
+    }
 
-	return (i, num);
-	
+
+    /// Returns `n` sampled elements like `VoseAlias::sample_n()`, or `None` if there is no element to sample from (see `VoseAlias::try_sample()`).
+    #[cfg(feature = "thread-rng")]
+    pub fn try_sample_n(&self, n:usize) -> Option<Vec<T>> {
+	if !self.is_sampleable() {
+	    return None;
+	}
+	Some(self.sample_n(n))
     }
 
 
-    /// This function selects an element from the VoseAlias table given a die (a column) and a coin (the element or its alias). This function has been separated from the `sample` function to allow unit testing, but should never be called by itself. 
-    fn select_element(&self, die:T, coin:u16) -> T {
-	// choose randomly an element from the element vector
-	let p_i:f32;
-	match self.prob.get(&die) {
-	    Some(p) => p_i = *p,
-	    None => panic!("Internal error. The probability vector is empty. If this happened, please fill in an issue report."),
+    /// Returns a sampled element like `VoseAlias::sample_constant_time()`, or `None` if there is no element to sample from (see `VoseAlias::try_sample()`).
+    pub fn try_sample_constant_time<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<T> {
+	if !self.is_sampleable() {
+	    return None;
 	}
-	if (coin as f32) <= (p_i * 100.0) {
-	    return die;
+	Some(self.sample_constant_time(rng))
+    }
+
+
+    /// Checks that there is at least one element, and that the element vector matches the tables, for the sampling methods that do not panic.
+    fn is_sampleable(&self) -> bool {
+	let size = self.elements.len();
+	size > 0 && self.slot_prob.len() == size && self.slot_alias.len() == size && self.probabilities.len() == size
+    }
+
+
+    /// Returns a vector of `n` elements sampled independently from a previously created Vose-Alias object.
+    ///
+    /// The thread-local random number generator (see the `small-rng` and `xoshiro` features) is only fetched once for the whole batch, and the batches of more than 512 samples draw their random values in blocks with a `BufferedRng`.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+    /// let elements = va.sample_n(100);
+    /// assert_eq!(elements.len(), 100);
+    /// ```
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_n(&self, n:usize) -> Vec<T> {
+	rng::with_default_rng(|rng| {
+	    if n > buffered::BLOCK_SIZE {
+		let mut rng = BufferedRng::new(rng);
+		(0..n).map(|_| self.sample_with(&mut rng)).collect()
+	    }
+	    else {
+		(0..n).map(|_| self.sample_with(rng)).collect()
+	    }
+	})
+    }
+
+
+    /// Returns a sampled element, in a time that does not depend on which element is sampled.
+    ///
+    /// This function is meant for security-sensitive selections (such as leader election), where the outcome should not leak through timing side channels. Instead of looking up the selected column in the tables, every column is read and the selected one is extracted with branchless bit masks, so a sample costs O(n) instead of O(1).
+    /// The final read of the element in `elements` is a direct indexed access: if this is a concern, use `VoseAlias::sample_index_constant_time()` and select the element yourself.
+    ///
+    /// Constant-time behaviour is provided on a best-effort basis: it relies on the compiler not re-introducing branches, which is discouraged using `std::hint::black_box`.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["alice", "bob", "carol"], vec![0.5, 0.3, 0.2]);
+    /// let leader = va.sample_constant_time(&mut rand::thread_rng());
+    /// assert!(va.elements.contains(&leader));
+    /// # }
+    /// ```
+    pub fn sample_constant_time<R: RngCore + ?Sized>(&self, rng:&mut R) -> T {
+	self.elements[self.sample_index_constant_time(rng)]
+    }
+
+
+    /// Returns the index in `elements` of a sampled element, in a time that does not depend on which element is sampled.
+    ///
+    /// See `VoseAlias::sample_constant_time()` for more details.
+    pub fn sample_index_constant_time<R: RngCore + ?Sized>(&self, rng:&mut R) -> usize {
+	let die = rng.gen_range(0, self.elements.len());
+	let coin:f32 = rng.gen();
+	self.select_index_constant_time(die, coin)
+    }
+
+
+    /// This function selects the index of an element given a die (a column) and a coin in [0, 1), without any branch depending on the die or the coin. The element of the column is kept if `coin` is strictly lower than its probability. It has been separated from `sample_index_constant_time` to allow unit testing.
+    fn select_index_constant_time(&self, die:usize, coin:f32) -> usize {
+	// positive floats are ordered like their bit representation
+	let coin_bits = u64::from(coin.to_bits());
+	let mut selected:usize = 0;
+	for j in 0..self.slot_prob.len() {
+	    // all ones if j is the selected column, zero otherwise
+	    let diff = j ^ die;
+	    let in_column = ((diff | diff.wrapping_neg()) >> (usize::BITS - 1)).wrapping_sub(1);
+	    // all ones if the coin selects the alias (coin >= prob), zero otherwise
+	    let prob_bits = u64::from(self.slot_prob[j].max(0.0).to_bits());
+	    let use_alias = 0_usize.wrapping_sub((prob_bits.wrapping_sub(coin_bits + 1) >> 63) as usize);
+	    let target = j ^ ((j ^ self.slot_alias[j].to_usize()) & black_box(use_alias));
+	    selected |= target & black_box(in_column);
+	}
+	selected
+    }
+
+
+    /// This function rolls the die and flip the coin to select the right element using the given RNG. It returns the generated number. This function is used by the `sample` function and has been decoupled from the `sample` function to allow unit tests on the `sample` function, using pre-determined series of numbers. 
+    fn roll_die_and_flip_coin<R: RngCore + ?Sized>(&self, rng:&mut R) -> (usize, u32) {
+	if self.elements.is_empty() {
+	    panic!("Internal error. The element vector is empty. If this happened, please fill in an issue report.");
+	}
+	die_and_coin(rng, self.elements.len())
+    }
+
+
+    /// This function selects an element from the VoseAlias table given a die (a column) and a coin in [0, 2^24) (the element or its alias). This function has been separated from the `sample` function to allow unit testing, but should never be called by itself. 
+    fn select_element(&self, die:usize, coin:u32) -> T {
+	self.elements[self.select_index(die, coin)]
+    }
+
+
+    /// This function works like `select_element`, but returns the index of the selected element in `elements`. The element of the column is kept if the coin is strictly lower than its probability × 2^24, so an element of probability 0 is never kept, and an element of probability 1 always is.
+    fn select_index(&self, die:usize, coin:u32) -> usize {
+	let (index, alias_used) = self.select_slot(die, coin);
+	self.observer.notify(index, die, alias_used);
+	index
+    }
+
+
+    /// This function works like `select_index`, but does not notify the observer, and also returns whether the alias was selected. It is used by the sampling functions rejecting some draws, which only notify the returned one.
+    fn select_slot(&self, die:usize, coin:u32) -> (usize, bool) {
+	let p_i:f32 = match self.slot_prob.get(die) {
+	    Some(p) => *p,
+	    None => panic!("Internal error. The die {} is out of the probability table. If this happened, please fill in an issue report.", die),
+	};
+	// both sides are exact: the coin has 24 bits, and scaling by a power of 2 keeps the mantissa
+	if (coin as f32) < p_i * COIN_SCALE {
+	    (die, false)
 	}
 	else {
-	    match self.alias.get(&die) {
-		Some(alias_i) => return *alias_i,
-		None => panic!("Internal error. No alias found for element {:?}. If this happened, please fill in an issue report.", die),
+	    let alias_i = self.slot_alias[die].to_usize();
+	    if alias_i == die {
+		panic!("Internal error. No alias found for element {:?}. If this happened, please fill in an issue report.", self.elements[die]);
 	    }
-	};
+	    (alias_i, true)
+	}
     }
     
 }
 
 
+/// Checks that a weight is finite and non-negative, for the samplers updating their weights one at a time. An error is returned otherwise (`VoseAliasError::InvalidWeight`).
+pub(crate) fn check_weight(weight:f64) -> Result<(), VoseAliasError> {
+    if !weight.is_finite() || weight < 0.0 {
+        return Err(VoseAliasError::InvalidWeight(weight));
+    }
+    Ok(())
+}
+
+
+/// Checks that there are `size` weights, at least one, that they are all finite and non-negative, and that their sum is positive: the checks of every constructor taking weights. Returns the sum of the weights.
+///
+/// An error is returned for the first check that fails, in this order: `SizeMismatch`, `EmptyDistribution`, `InvalidWeight` (with the first invalid weight) and `ZeroTotalWeight` (also returned if the sum overflows). The samplers accepting weights that are all 0 ignore the last one.
+pub(crate) fn check_weights<W: Weight>(size:usize, weight_vector:&[W]) -> Result<f64, VoseAliasError> {
+    if weight_vector.len() != size {
+        return Err(VoseAliasError::SizeMismatch { elements: size, probabilities: weight_vector.len() });
+    }
+    if size == 0 {
+        return Err(VoseAliasError::EmptyDistribution);
+    }
+    let mut total = 0.0;
+    for w in weight_vector {
+        let w = w.to_f64();
+        check_weight(w)?;
+        total += w;
+    }
+    if total <= 0.0 || !total.is_finite() {
+        return Err(VoseAliasError::ZeroTotalWeight);
+    }
+    Ok(total)
+}
+
+
+/// Draws a die in [0, `size`) and a coin in [0, 2^24) from a single 64-bit random value: the high 32 bits are mapped to the die by a multiplication, and the low 24 bits form the coin. This halves the calls to the generator compared to drawing them separately.
+///
+/// The die is biased by less than `size` / 2^32, which is negligible for the distributions that fit in memory. Distributions of more than 2^32 elements draw the die separately.
+fn die_and_coin<R: RngCore + ?Sized>(rng:&mut R, size:usize) -> (usize, u32) {
+    let bits = rng.next_u64();
+    let coin = (bits as u32) & ((1 << COIN_BITS) - 1);
+    let die = match u32::try_from(size) {
+	Ok(size) => (((bits >> 32) * u64::from(size)) >> 32) as usize,
+	Err(_) => rng.gen_range(0, size),
+    };
+    (die, coin)
+}
+
+
+/// Runs a constructor, within a `tracing` span carrying the source (the kind of input) and the size of the distribution if the `tracing` feature is enabled. A failed construction is reported as a warning event in the span.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn traced_construction<O, F>(source:&'static str, size:usize, construct:F) -> Result<O, VoseAliasError>
+where F: FnOnce() -> Result<O, VoseAliasError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("vose_alias.construct", source, size).entered();
+    let result = construct();
+    #[cfg(feature = "tracing")]
+    if let Err(e) = &result {
+        tracing::warn!(error = %e, "invalid distribution");
+    }
+    result
+}
+
+
+/// Runs the Vose-Alias initialization on one distribution, given its probabilities multiplied by the number of elements.
+///
+/// The slot tables are written in `slot_prob` and `slot_alias`, which must have the same size as `scaled_probability_vector`. An element without alias gets its own index in `slot_alias`. `small` and `large` are work buffers, that can be reused between calls to avoid allocations.
+pub(crate) fn build_slots<S: SlotStack>(scaled_probability_vector:&mut [f32], slot_prob:&mut [f32], slot_alias:&mut [usize], small:&mut S, large:&mut S) {
+    small.clear();
+    large.clear();
+    for (i, scaled_proba) in scaled_probability_vector.iter().enumerate() {
+        slot_alias[i] = i;
+        slot_prob[i] = 1.0;
+        if *scaled_proba < 1.0 {
+            small.push(i);
+        }
+        else {
+            large.push(i);
+        }
+    }
+
+    // emptying one column first
+    while !(small.is_empty() || large.is_empty()) {    
+	// removing the element from small and large
+        if let (Some(l), Some(g)) = (small.pop(), large.pop()) {
+	    // put g in the alias vector
+	    slot_alias[l] = g;
+	    // getting the probability of the small element and put it in the prob vector
+	    let p_l = scaled_probability_vector[l];
+	    slot_prob[l] = p_l;
+
+	    // update the probability for g
+	    let new_p_g = (scaled_probability_vector[g] + p_l) - 1.0;
+	    #[cfg(feature = "log")]
+	    log::trace!("pairing small column {} (scaled probability {}) with large column {} (scaled probability {} -> {})", l, p_l, g, scaled_probability_vector[g], new_p_g);
+	    scaled_probability_vector[g] = new_p_g;
+	    if new_p_g < 1.0 {
+		small.push(g);
+	    }
+	    else {
+		large.push(g);
+	    }
+	}
+    }
+
+    // finishing the init: the remaining elements are always kept when their column is selected, which is already the case in slot_prob
+    #[cfg(feature = "log")]
+    if !(small.is_empty() && large.is_empty()) {
+        log::trace!("columns left without alias: small {:?}, large {:?}", small, large);
+    }
+}
+
+/// A work stack of column indexes for `build_slots()`, so that the stacks can be allocated in any allocator (see `VoseAliasIn`).
+pub(crate) trait SlotStack: Debug {
+    fn clear(&mut self);
+    fn push(&mut self, i:usize);
+    fn pop(&mut self) -> Option<usize>;
+    fn is_empty(&self) -> bool;
+}
+
+
+impl SlotStack for Vec<usize> {
+    fn clear(&mut self) {
+	Vec::clear(self)
+    }
+
+    fn push(&mut self, i:usize) {
+	Vec::push(self, i)
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+	Vec::pop(self)
+    }
+
+    fn is_empty(&self) -> bool {
+	Vec::is_empty(self)
+    }
+}
+
+
+
 ////////////////////////////
 // Traits Implementation  //
 ////////////////////////////
-impl <T> Display for VoseAlias<T>
-where T: Display + Copy + Hash + Eq + Debug {
+impl <T, I> Display for VoseAlias<T, I>
+where T: Display + Copy + Hash + Eq + Debug, I: SlotIndex {
+    /// Formats the elements with the alias and probability tables, in the order of `elements`.
+    ///
+    /// The precision (2 by default) sets the number of decimals of the probabilities, and the alternate flag (`{:#}`) formats the tables as an aligned table with one row per element. Only the first 20 elements are shown, followed by the number of elements left out.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let precision = f.precision().unwrap_or(2);
+	// bounded by the tables, in case `elements` was edited after the construction
+	let shown = self.elements.len().min(self.slot_prob.len()).min(self.slot_alias.len()).min(DISPLAY_MAX_ELEMENTS);
+	let hidden = self.elements.len() - shown;
+	let alias_of = |i:usize| match self.slot_alias[i].to_usize() {
+	    a if a == i => None,
+	    a => self.elements.get(a).copied(),
+	};
+
+	if f.alternate() {
+	    let rows:Vec<(String, String, String)> = (0..shown).map(|i| (
+		self.elements[i].to_string(),
+		format!("{:.*}", precision, self.slot_prob[i]),
+		alias_of(i).map(|a| a.to_string()).unwrap_or_else(|| String::from("-")),
+	    )).collect();
+	    let element_width = rows.iter().map(|r| r.0.chars().count()).chain(std::iter::once("element".len())).max().unwrap_or(0);
+	    let prob_width = rows.iter().map(|r| r.1.len()).chain(std::iter::once("prob".len())).max().unwrap_or(0);
+
+	    write!(f, "{:<ew$}  {:>pw$}  alias", "element", "prob", ew = element_width, pw = prob_width)?;
+	    for (element, prob, alias) in &rows {
+		write!(f, "\n{:<ew$}  {:>pw$}  {}", element, prob, alias, ew = element_width, pw = prob_width)?;
+	    }
+	    if hidden > 0 {
+		write!(f, "\n... and {} more elements", hidden)?;
+	    }
+	    return Ok(());
+	}
+
 	// format the elements
-	let mut str_elements = String::from("[ ");
-	for e in &self.elements {
-	    str_elements = str_elements + &e.to_string() + " ";
+	write!(f, "{{ elements: [ ")?;
+	for e in &self.elements[..shown] {
+	    write!(f, "{} ", e)?;
+	}
+	if hidden > 0 {
+	    write!(f, "(+{} more) ", hidden)?;
 	}
-	str_elements = str_elements + "]";
 
 	// format the alias table
-	let mut str_alias = String::from("{ ");
-	for k in self.alias.keys() {
-	    let a:T;
-	    match self.alias.get(&k) {
-		Some(element) => a = *element,
-		None => panic!("Internal error. The alias map does not contain element for {}. If you encountered this error, please fill in an issue report.", k),
+	write!(f, "], alias: {{")?;
+	let mut separator = " ";
+	for i in 0..shown {
+	    if let Some(a) = alias_of(i) {
+		write!(f, "{}{}:{}", separator, self.elements[i], a)?;
+		separator = ", ";
 	    }
-	    str_alias = str_alias + &String::from(format!("{}:{}, ", k, a));
 	}
-	// remove the last two characters, that are not needed for the last element
-	str_alias = str_alias[..str_alias.len() - 2].to_string() + " }";
-
-	// fomat the probability table
-	let mut str_prob = String::from("{");
-	for k in self.prob.keys() {
-	    let p:f32;
-	    match self.prob.get(&k) {
-		Some(element) => p = *element,
-		None => panic!("Internal error. The alias map does not contain element for {}. If you encountered this error, please fill in an issue report.", k),
-	    }
-	    str_prob = str_prob + &String::from(format!("{}:{:.2}, ", k, p));
+	if hidden > 0 {
+	    write!(f, "{}...", separator)?;
 	}
-	// remove the last two characters, that are not needed for the last element
-	str_prob = str_prob[..str_prob.len() - 2].to_string() + " }";
 
-	// return all of this in a nice string
-	write!(f, "{{ elements: {}, alias: {}, prob: {}}}", str_elements, str_alias, str_prob)
+	// format the probability table
+	write!(f, " }}, prob: {{")?;
+	for i in 0..shown {
+	    write!(f, "{}{}:{:.*}", if i == 0 { " " } else { ", " }, self.elements[i], precision, self.slot_prob[i])?;
+	}
+	if hidden > 0 {
+	    write!(f, ", ...")?;
+	}
+	write!(f, " }} }}")
     }
 }
 
-impl<T> PartialEq for VoseAlias<T>
-where T:Display + Copy + Hash + Eq + Debug {
+
+impl <T, I> Debug for VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+    /// Summarizes the distribution with its number of elements, its entropy (in bits) and its 5 most probable elements, instead of dumping the tables.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	let entropy:f64 = self.probabilities.iter().filter(|p| **p > 0.0).fold(0.0, |h, p| h + p * (1.0 / p).log2());
+	let mut top:Vec<(T, f64)> = self.elements.iter().copied().zip(self.probabilities.iter().copied()).collect();
+	// the sort is stable, so ties keep the order of `elements`
+	top.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+	top.truncate(DEBUG_TOP_ELEMENTS);
+
+	f.debug_struct("VoseAlias")
+	    .field("elements", &self.elements.len())
+	    .field("entropy", &entropy)
+	    .field("top", &top)
+	    .finish()
+    }
+}
+
+
+impl<T, I> PartialEq for VoseAlias<T, I>
+where T:Copy + Hash + Eq + Debug, I: SlotIndex {
+    /// Two objects are equal if they sample the same elements, in the same order, with the same requested probabilities, compared in `f64`.
     fn eq(&self, other: &Self) -> bool {
-	self.alias == other.alias
+	self.elements == other.elements && self.probabilities == other.probabilities
     }
     
 }
 
 
-impl <T> Eq for VoseAlias<T>
-where T:Display + Copy + Hash + Eq + Debug{
+impl <T, I> Eq for VoseAlias<T, I>
+where T:Copy + Hash + Eq + Debug, I: SlotIndex {
 }
 
 
@@ -292,6 +1290,11 @@ where T:Display + Copy + Hash + Eq + Debug{
 mod tests{
     use super::*;
 
+    /// Returns the column of `element` in the tables of `va`.
+    fn slot<T: Copy + Hash + Eq + Debug>(va:&VoseAlias<T>, element:T) -> usize {
+	va.elements.iter().position(|e| *e == element).unwrap()
+    }
+
     ////////////////////////////////////////
     // Tests of the Struct Implementation //
     ////////////////////////////////////////
@@ -320,98 +1323,385 @@ mod tests{
 	VoseAlias::new(element_vector, probability_vector);
     }
     
+    #[test]
+    fn try_new_errors() {
+	assert!(VoseAlias::try_new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]).is_ok());
+	assert_eq!(VoseAlias::try_new(vec![1, 2, 3], vec![0.5, 0.2, 0.2, 0.1]), Err(VoseAliasError::SizeMismatch { elements: 3, probabilities: 4 }));
+	assert_eq!(VoseAlias::<u16>::try_new(Vec::new(), Vec::new()), Err(VoseAliasError::EmptyDistribution));
+	assert_eq!(VoseAlias::try_new(vec![1, 2, 1], vec![0.5, 0.2, 0.3]), Err(VoseAliasError::DuplicateElement(String::from("1"))));
+	assert_eq!(VoseAlias::try_new(vec![1, 2, 3], vec![1.2, -0.2, 0.0]), Err(VoseAliasError::InvalidProbability(-0.2)));
+	assert!(matches!(VoseAlias::try_new(vec![1, 2, 3], vec![0.5, 0.2, 0.2]), Err(VoseAliasError::NotNormalized(_))));
+    }
+
+    #[test]
+    fn index_types() {
+	let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+	let va16:VoseAlias<&str, u16> = VoseAlias::new_with_index(va.elements.clone(), vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+	let va64:VoseAlias<&str, usize> = VoseAlias::new_with_index(va.elements.clone(), vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+	assert!(va.alias == va16.alias && va.alias == va64.alias);
+	assert!(va.slot_prob == va16.slot_prob);
+
+	let elements:Vec<u32> = (0..65537).collect();
+	let probabilities = vec![1.0 / 65537.0; 65537];
+	assert_eq!(VoseAlias::<u32, u16>::try_new_with_index(elements, probabilities), Err(VoseAliasError::TooManyElements { elements: 65537, capacity: 65536 }));
+    }
+
+    #[test]
+    fn test_table_probabilities() {
+	let probabilities = vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125];
+	let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], probabilities.clone());
+	for (i, (p, expected)) in va.table_probabilities().iter().zip(probabilities).enumerate() {
+	    assert!(approx_eq!(f64, *p, expected as f64, epsilon=0.000001));
+	    assert!(approx_eq!(f64, va.probabilities[i], expected as f64, epsilon=0.000001));
+	}
+    }
+
+    #[cfg(feature = "thread-rng")]
     #[test]
     fn test_roll_die_flip_coin() {
 	let element_vector = vec![1, 2, 3, 4];
 	let va = VoseAlias::new(element_vector.clone(), vec![0.5, 0.2, 0.2, 0.1]);
-	let (die, coin) = va.roll_die_and_flip_coin();
-	assert!(element_vector.contains(&die));
-	assert!(coin <= 100);
+	let (die, coin) = va.roll_die_and_flip_coin(&mut rand::thread_rng());
+	assert!(die < element_vector.len());
+	assert!(coin < 1 << COIN_BITS);
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn test_elements_without_display() {
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+	enum Direction { North, South }
+	let va = VoseAlias::new(vec![Direction::North, Direction::South], vec![0.25, 0.75]);
+	let direction = va.sample();
+	assert!(direction == Direction::North || direction == Direction::South);
+	assert!(format!("{:?}", va).contains("South"));
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn test_die_and_coin() {
+	// the high 32 bits pick the die, the low 24 bits form the coin
+	let mut rng = rand::rngs::mock::StepRng::new(0xffff_ffff_0000_0001, 0);
+	assert_eq!(die_and_coin(&mut rng, 4), (3, 1));
+	let mut rng = rand::rngs::mock::StepRng::new(0x8000_0000_ff00_0000, 0);
+	assert_eq!(die_and_coin(&mut rng, 4), (2, 0));
+
+	let va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.5, 0.0]);
+	let mut rng = rand::thread_rng();
+	assert!((0..10000).all(|_| va.sample_with(&mut rng) != 3));
     }
 
     #[test]
     fn test_select_element_ok() {
 	let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+	// the coin of a fraction of the range, the largest coin for 1.0
+	let coin = |fraction:f64| ((fraction * f64::from(COIN_SCALE)) as u32).min((1 << COIN_BITS) - 1);
 	// column orange / alias yellow
-	let element = va.select_element("orange", 0);
+	let element = va.select_element(slot(&va, "orange"), coin(0.0));
 	assert!(element == "orange");
-	let element = va.select_element("orange", 87);
+	let element = va.select_element(slot(&va, "orange"), coin(0.86));
 	assert!(element == "orange");
-	let element = va.select_element("orange", 88);
+	let element = va.select_element(slot(&va, "orange"), coin(0.88));
 	assert!(element == "yellow");
-	let element = va.select_element("orange", 100);
+	let element = va.select_element(slot(&va, "orange"), coin(1.0));
 	assert!(element == "yellow");
 
 	// column yellow / no alias
-	let element = va.select_element("yellow", 0);
+	let element = va.select_element(slot(&va, "yellow"), coin(0.0));
 	assert!(element == "yellow");
-	let element = va.select_element("yellow", 100);
+	let element = va.select_element(slot(&va, "yellow"), coin(1.0));
 	assert!(element == "yellow");
 
 	// column green / alias turquoise
-	let element = va.select_element("green", 0);
+	let element = va.select_element(slot(&va, "green"), coin(0.0));
 	assert!(element == "green");
-	let element = va.select_element("green", 70);
+	let element = va.select_element(slot(&va, "green"), coin(0.69));
 	assert!(element == "green");
-	let element = va.select_element("green", 71);
+	let element = va.select_element(slot(&va, "green"), coin(0.71));
 	assert!(element == "turquoise");
-	let element = va.select_element("green", 100);
+	let element = va.select_element(slot(&va, "green"), coin(1.0));
 	assert!(element == "turquoise");
 
 	// column turquoise / alias yellow
-	let element = va.select_element("turquoise", 0);
+	let element = va.select_element(slot(&va, "turquoise"), coin(0.0));
 	assert!(element == "turquoise");
-	let element = va.select_element("turquoise", 72);
+	let element = va.select_element(slot(&va, "turquoise"), coin(0.71));
 	assert!(element == "turquoise");
-	let element = va.select_element("turquoise", 73);
+	let element = va.select_element(slot(&va, "turquoise"), coin(0.73));
 	assert!(element == "yellow");
-	let element = va.select_element("turquoise", 100);
+	let element = va.select_element(slot(&va, "turquoise"), coin(1.0));
 	assert!(element == "yellow");
 
 	// column grey / alias turquoise
-	let element = va.select_element("grey", 0);
+	let element = va.select_element(slot(&va, "grey"), coin(0.0));
 	assert!(element == "grey");
-	let element = va.select_element("grey", 70);
+	let element = va.select_element(slot(&va, "grey"), coin(0.69));
 	assert!(element == "grey");
-	let element = va.select_element("grey", 71);
+	let element = va.select_element(slot(&va, "grey"), coin(0.71));
 	assert!(element == "turquoise");
-	let element = va.select_element("grey", 100);
+	let element = va.select_element(slot(&va, "grey"), coin(1.0));
 	assert!(element == "turquoise");
 
 	// column blue / alias turquoise
-	let element = va.select_element("blue", 0);
+	let element = va.select_element(slot(&va, "blue"), coin(0.0));
 	assert!(element == "blue");
-	let element = va.select_element("blue", 70);
+	let element = va.select_element(slot(&va, "blue"), coin(0.69));
 	assert!(element == "blue");
-	let element = va.select_element("blue", 71);
+	let element = va.select_element(slot(&va, "blue"), coin(0.71));
 	assert!(element == "turquoise");
-	let element = va.select_element("blue", 100);
+	let element = va.select_element(slot(&va, "blue"), coin(1.0));
 	assert!(element == "turquoise");
 
 	// column pink / alias turquoise
-	let element = va.select_element("pink", 0);
+	let element = va.select_element(slot(&va, "pink"), coin(0.0));
 	assert!(element == "pink");
-	let element = va.select_element("pink", 87);
+	let element = va.select_element(slot(&va, "pink"), coin(0.86));
 	assert!(element == "pink");
-	let element = va.select_element("pink", 88);
+	let element = va.select_element(slot(&va, "pink"), coin(0.88));
 	assert!(element == "turquoise");
-	let element = va.select_element("pink", 100);
+	let element = va.select_element(slot(&va, "pink"), coin(1.0));
 	assert!(element == "turquoise");
     }
 
 
+    #[test]
+    fn test_select_index_constant_time() {
+	let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+	for die in 0..va.elements.len() {
+	    for c in 0..100 {
+		let coin = c as f32 / 100.0;
+		let expected = if coin < va.slot_prob[die] { die } else { va.slot_alias[die].to_usize() };
+		assert_eq!(va.select_index_constant_time(die, coin), expected);
+	    }
+	}
+	// column orange / alias yellow
+	assert_eq!(va.elements[va.select_index_constant_time(0, 0.0)], "orange");
+	assert_eq!(va.elements[va.select_index_constant_time(0, 0.99)], "yellow");
+	// column yellow / no alias
+	assert_eq!(va.elements[va.select_index_constant_time(1, 0.99)], "yellow");
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn test_sample_constant_time() {
+	let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.3, 0.0]);
+	let mut rng = rand::thread_rng();
+	for _ in 0..1000 {
+	    let element = va.sample_constant_time(&mut rng);
+	    assert!(element == 1 || element == 2 || element == 3);
+	}
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+	let elements = vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"];
+	let va = VoseAlias::new(elements.clone(), vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+	assert_eq!(va.prob.keys().copied().collect::<Vec<&str>>(), elements);
+	let alias_keys:Vec<&str> = va.alias.keys().copied().collect();
+	let expected:Vec<&str> = elements.iter().copied().filter(|e| va.alias.contains_key(e)).collect();
+	assert_eq!(alias_keys, expected);
+	assert!(va.to_string().starts_with("{ elements: [ orange yellow green turquoise grey blue pink ], alias: { orange:"));
+    }
+
+    #[test]
+    fn test_display() {
+	let va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+	assert_eq!(va.to_string(), "{ elements: [ 1 2 ], alias: { }, prob: { 1:1.00, 2:1.00 } }");
+	let va = VoseAlias::new(vec!["a"], vec![1.0]);
+	assert_eq!(format!("{:.1}", va), "{ elements: [ a ], alias: { }, prob: { a:1.0 } }");
+	assert_eq!(format!("{:#}", va), "element  prob  alias\na        1.00  -");
+	let va = VoseAlias::new(vec!["long name", "b"], vec![0.75, 0.25]);
+	assert_eq!(format!("{:#}", va), "element    prob  alias\nlong name  1.00  -\nb          0.50  long name");
+
+	let va = VoseAlias::new((0..25).collect(), vec![0.04; 25]);
+	assert!(va.to_string().contains("18 19 (+5 more) ]"));
+	assert!(format!("{:#}", va).ends_with("\n... and 5 more elements"));
+    }
+
+    #[test]
+    fn test_debug() {
+	let va = VoseAlias::new(vec![1, 2, 3, 4, 5, 6, 7, 8], vec![0.125; 8]);
+	assert_eq!(format!("{:?}", va), "VoseAlias { elements: 8, entropy: 3.0, top: [(1, 0.125), (2, 0.125), (3, 0.125), (4, 0.125), (5, 0.125)] }");
+	let va = VoseAlias::new(vec!["a", "b"], vec![0.0, 1.0]);
+	assert_eq!(format!("{:?}", va), "VoseAlias { elements: 2, entropy: 0.0, top: [(\"b\", 1.0), (\"a\", 0.0)] }");
+    }
+
     #[test]
     #[should_panic]
     fn select_element_proba_too_high() {
 	let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
-	va.select_element("yellow", 101);
+	va.select_element(slot(&va, "yellow"), 1 << COIN_BITS);
     }
 
     #[test]
     #[should_panic]
     fn select_element_not_in_list() {
 	let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
-	va.select_element("red", 100);
+	// there are only 7 columns
+	va.select_element(7, 0);
+    }
+
+    #[test]
+    fn test_from_counts_smoothed() {
+	let va = VoseAlias::from_counts_smoothed(vec![1, 2], vec![2, 0], 1.0, &[2, 3, 3]).unwrap();
+	assert_eq!(va.elements, vec![1, 2, 3]);
+	// (2 + 1) / (2 + 3)
+	assert!((va.prob_of(&1) - 0.6).abs() < 1e-6);
+	assert!((va.prob_of(&3) - 0.2).abs() < 1e-6);
+	assert_eq!(VoseAlias::from_counts_smoothed(vec![1, 2], vec![2, 0], 0.0, &[]).unwrap(), VoseAlias::from_counts(vec![1, 2], vec![2, 0]).unwrap());
+	assert!(matches!(VoseAlias::from_counts_smoothed(vec![1, 2], vec![2, 0], f64::NAN, &[]), Err(VoseAliasError::InvalidParameter { name: "alpha", .. })));
+	assert_eq!(VoseAlias::from_counts_smoothed(vec![1, 2], vec![2], 1.0, &[]).err(), Some(VoseAliasError::SizeMismatch { elements: 2, probabilities: 1 }));
+	assert_eq!(VoseAlias::from_counts_smoothed(vec![1, 2], vec![0, 0], 0.0, &[3]).err(), Some(VoseAliasError::ZeroTotalWeight));
+    }
+
+    #[test]
+    fn test_from_occurrences() {
+        let va = VoseAlias::from_occurrences(vec![3, 1, 3, 3]).unwrap();
+        assert_eq!(va.elements, vec![3, 1]);
+        assert!((va.probabilities[0] - 0.75).abs() < 1e-6);
+        assert_eq!(VoseAlias::from_occurrences(Vec::<u8>::new()).err(), Some(VoseAliasError::EmptyDistribution));
+    }
+
+    #[test]
+    fn test_try_from_weights() {
+        let va = VoseAlias::try_from_weights(vec![1, 2, 3], vec![2_u8, 1, 1]).unwrap();
+        assert!((va.probabilities[0] - 0.5).abs() < 1e-12);
+        assert_eq!(va, VoseAlias::try_from_weights(vec![1, 2, 3], vec![0.5_f32, 0.25, 0.25]).unwrap());
+        // weights beyond the range of f32 are normalized in f64
+        let large = VoseAlias::try_from_weights(vec![1, 2], vec![u64::MAX, u64::MAX]).unwrap();
+        assert!((large.probabilities[0] - 0.5).abs() < 1e-12);
+        assert_eq!(VoseAlias::try_from_weights(vec![1, 2], vec![1.0, -1.0]).err(), Some(VoseAliasError::InvalidWeight(-1.0)));
+        assert_eq!(VoseAlias::try_from_weights(vec![1, 2], vec![0_u32, 0]).err(), Some(VoseAliasError::ZeroTotalWeight));
+    }
+
+    #[test]
+    fn test_uniform() {
+        let va = VoseAlias::uniform(vec![1, 2, 3]).unwrap();
+        assert!(va.probabilities.iter().all(|p| (p - 1.0 / 3.0).abs() < 1e-12));
+        assert!(va.slot_prob.iter().all(|p| *p == 1.0));
+        assert_eq!(VoseAlias::uniform(Vec::<u8>::new()).err(), Some(VoseAliasError::EmptyDistribution));
+        assert!(matches!(VoseAlias::uniform(vec![1, 1]), Err(VoseAliasError::DuplicateElement(_))));
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn test_singleton_and_try_sample() {
+        let va = VoseAlias::singleton(5);
+        assert_eq!(va, VoseAlias::new(vec![5], vec![1.0]));
+        let mut rng = rand::thread_rng();
+        assert_eq!(va.try_sample_with(&mut rng), Some(5));
+
+        let mut truncated = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.25, 0.25]);
+        truncated.elements.truncate(2);
+        assert_eq!(truncated.try_sample_with(&mut rng), None);
+        truncated.elements.clear();
+        assert_eq!(truncated.try_sample(), None);
+    }
+
+    #[test]
+    fn test_from_fn() {
+        let va = VoseAlias::from_fn(4, |i| i * 10, |i| (i + 1) as f64).unwrap();
+        assert_eq!(va.elements, vec![0, 10, 20, 30]);
+        assert!((va.probabilities[3] - 0.4).abs() < 1e-12);
+        assert!(matches!(VoseAlias::from_fn(3, |i| i % 2, |_| 1.0), Err(VoseAliasError::DuplicateElement(_))));
+        assert_eq!(VoseAlias::from_fn(0, |i| i, |_| 1.0).err(), Some(VoseAliasError::EmptyDistribution));
+    }
+
+    #[test]
+    fn test_fit_from_samples() {
+        let va = VoseAlias::fit_from_samples(vec![2, 1, 2, 2], 0.0).unwrap();
+        assert_eq!(va, VoseAlias::from_occurrences(vec![2, 1, 2, 2]).unwrap());
+        let smoothed = VoseAlias::fit_from_samples(vec![2, 1, 2, 2], 2.0).unwrap();
+        assert!((smoothed.probabilities[0] - 5.0 / 8.0).abs() < 1e-12);
+        assert_eq!(VoseAlias::fit_from_samples(Vec::<u8>::new(), 1.0).err(), Some(VoseAliasError::EmptyDistribution));
+        assert!(matches!(VoseAlias::fit_from_samples(vec![1], -1.0), Err(VoseAliasError::InvalidParameter { name: "smoothing", .. })));
+
+        let uniform = VoseAlias::fit_from_samples_over(vec![1, 2, 3, 4], Vec::new(), 0.5).unwrap();
+        assert!(uniform.probabilities.iter().all(|p| (p - 0.25).abs() < 1e-12));
+        assert_eq!(VoseAlias::fit_from_samples_over(vec![1, 2], Vec::new(), 0.0).err(), Some(VoseAliasError::ZeroTotalWeight));
+        assert!(matches!(VoseAlias::fit_from_samples_over(vec![1, 2], vec![3], 1.0), Err(VoseAliasError::UnknownElement(_))));
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn test_panic_free_api() {
+        let mut rng = rand::thread_rng();
+        let mut va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.25, 0.25]);
+        assert_eq!(va.try_sample_n(3).map(|s| s.len()), Some(3));
+        assert_eq!(va.try_prob_of(&4), Err(VoseAliasError::UnknownElement(String::from("4"))));
+
+        va.elements.push(4);
+        assert_eq!(va.try_sample_n(3), None);
+        assert_eq!(va.try_sample_constant_time(&mut rng), None);
+        assert_eq!(WeightedSampler::sample(&va, &mut rng), None);
+        assert!(format!("{} {:#} {:?}", va, va, va).contains("more"));
+        va.elements.clear();
+        assert_eq!(va.to_string(), "{ elements: [ ], alias: { }, prob: { } }");
+    }
+
+
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn traced_construction_failures() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Level, Metadata};
+
+        // counts the warning events, and the construction spans
+        #[derive(Default)]
+        struct Counter { warnings:AtomicUsize, spans:AtomicUsize }
+        impl tracing::Subscriber for Counter {
+            fn enabled(&self, _:&Metadata<'_>) -> bool { true }
+            fn new_span(&self, span:&Attributes<'_>) -> Id {
+                if span.metadata().name() == "vose_alias.construct" {
+                    self.spans.fetch_add(1, Ordering::Relaxed);
+                }
+                Id::from_u64(1)
+            }
+            fn record(&self, _:&Id, _:&Record<'_>) {}
+            fn record_follows_from(&self, _:&Id, _:&Id) {}
+            fn event(&self, event:&Event<'_>) {
+                if *event.metadata().level() == Level::WARN {
+                    self.warnings.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            fn enter(&self, _:&Id) {}
+            fn exit(&self, _:&Id) {}
+        }
+
+        let counter = std::sync::Arc::new(Counter::default());
+        tracing::subscriber::with_default(counter.clone(), || {
+            assert!(VoseAlias::try_new(vec![1, 2], vec![0.5, 0.5]).is_ok());
+            assert!(VoseAlias::try_new(vec![1, 2], vec![0.5, 0.6]).is_err());
+            assert!(VoseAlias::from_counts(vec![1], vec![0]).is_err());
+        });
+        assert_eq!(counter.spans.load(Ordering::Relaxed), 3);
+        assert_eq!(counter.warnings.load(Ordering::Relaxed), 2);
+    }
+
+
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn logged_construction() {
+        use std::sync::Mutex;
+
+        // the logger is global, so this is the only test installing one
+        struct Recorder(Mutex<Vec<String>>);
+        impl log::Log for Recorder {
+            fn enabled(&self, _:&log::Metadata<'_>) -> bool { true }
+            fn log(&self, record:&log::Record<'_>) { self.0.lock().unwrap().push(record.args().to_string()); }
+            fn flush(&self) {}
+        }
+        static RECORDER:Recorder = Recorder(Mutex::new(Vec::new()));
+        log::set_logger(&RECORDER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        VoseAlias::new(vec!['a', 'b'], vec![0.25, 0.75]);
+        let lines = RECORDER.0.lock().unwrap();
+        assert!(lines.iter().any(|l| l == "pairing small column 0 (scaled probability 0.5) with large column 1 (scaled probability 1.5 -> 1)"));
+        assert!(lines.iter().any(|l| l == "column 0 ('a'): kept with probability 0.5, alias 'b'"));
     }
 
 
@@ -432,5 +1722,23 @@ mod tests{
 	let va2 = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
 	assert!(va!=va2);
     }
+
+    #[test]
+    fn requested_probabilities() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+        assert_eq!(va.probabilities(), &[0.5, 0.2, 0.2, 0.1]);
+        let va2 = VoseAlias::from_weights(vec![1, 2, 3, 4], vec![5.0, 2.0, 2.0, 1.0]).unwrap();
+        assert_eq!(va2.probabilities(), &[0.5, 0.2, 0.2, 0.1]);
+        // the probabilities given to `new()` are f32 values, which are not exactly the normalized weights
+        assert!(va != va2);
+        assert!(va == VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]));
+        // weights differing below the precision of f32 give different distributions
+        let even:VoseAlias<i32> = VoseAlias::from_weights(vec![1, 2], vec![1.0, 1.0]).unwrap();
+        let close = VoseAlias::from_weights(vec![1, 2], vec![1.0, 1.0 + 1e-9]).unwrap();
+        assert_eq!(even.probabilities(), close.probabilities());
+        assert!(even != close);
+        let va3 = VoseAlias::new(vec![4, 3, 2, 1], vec![0.1, 0.2, 0.2, 0.5]);
+        assert!(va != va3);
+    }
     
 }
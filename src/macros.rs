@@ -0,0 +1,97 @@
+//! The `vose_alias!` macro, building a distribution from literal elements and weights.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::VoseAlias;
+
+
+/// Builds a `VoseAlias` from literal elements and their weights, written `element => weight`.
+///
+/// The weights can be integers or floats and do not need to sum to 1: they are normalized before building the tables.
+/// Obviously malformed input is rejected at compile time: an empty distribution does not compile, and neither does an element appearing twice (through the `unreachable_patterns` lint, which the macro denies).
+///
+/// # Examples
+/// ```
+/// use vose_alias::{vose_alias, WeightedSampler};
+///
+/// let loot = vose_alias!{"common" => 70, "rare" => 25, "epic" => 5};
+/// assert_eq!(loot.elements, vec!["common", "rare", "epic"]);
+/// assert!((loot.prob_of(&"rare") - 0.25).abs() < 1e-6);
+/// ```
+///
+/// ```compile_fail
+/// use vose_alias::vose_alias;
+///
+/// let loot = vose_alias!{"common" => 70, "rare" => 25, "common" => 5};
+/// ```
+///
+/// ```compile_fail
+/// use vose_alias::vose_alias;
+///
+/// let loot:vose_alias::VoseAlias<&str> = vose_alias!{};
+/// ```
+///
+/// # Panics
+/// The macro panics if a weight is negative or not finite, or if all the weights are 0.
+#[macro_export]
+macro_rules! vose_alias {
+    (@first $first:literal $(, $rest:literal)*) => {
+        $first
+    };
+    () => {
+        compile_error!("vose_alias! needs at least one `element => weight` pair")
+    };
+    ($($element:literal => $weight:expr),+ $(,)?) => {{
+        // two identical literals make an arm unreachable, which turns duplicates into compile errors
+        #[deny(unreachable_patterns)]
+        #[allow(dead_code)]
+        fn duplicate_elements() {
+            match $crate::vose_alias!(@first $($element),+) {
+                $($element => {},)+
+                #[allow(unreachable_patterns)]
+                _ => {},
+            }
+        }
+        $crate::VoseAlias::from_macro(vec![$($element),+], vec![$(($weight) as f64),+])
+    }};
+}
+
+
+impl<T> VoseAlias<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Builds the distribution expanded by `vose_alias!`, panicking on invalid weights. This function is not part of the public API.
+    #[doc(hidden)]
+    pub fn from_macro(element_vector:Vec<T>, weight_vector:Vec<f64>) -> VoseAlias<T> {
+        match VoseAlias::from_weights(element_vector, weight_vector) {
+            Ok(va) => va,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn literal_distributions() {
+        let va = vose_alias!{1 => 1, 2 => 3,};
+        assert_eq!(va.elements, vec![1, 2]);
+        assert!((va.probabilities[1] - 0.75).abs() < 1e-9);
+        let va = vose_alias!{'x' => 0.5};
+        assert_eq!(va.sample(), 'x');
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_weights() {
+        vose_alias!{"a" => 0, "b" => 0};
+    }
+}
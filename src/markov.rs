@@ -0,0 +1,215 @@
+//! A Markov chain sampler, storing one Vose-Alias table per state.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use indexmap::IndexSet;
+use rand_core::RngCore;
+
+use crate::{VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A discrete-time Markov chain over states of type `T`, with one Vose-Alias table per state to sample the next state in constant time.
+///
+/// The chain is built from a transition map, giving for each state the list of the possible next states with their weight. The weights of each state are normalized, so they do not need to sum exactly to 1.
+/// A state without transitions (for instance a state only appearing as a target) is a dead end: no step can be taken from it.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use std::collections::HashMap;
+/// use vose_alias::MarkovChain;
+///
+/// let mut transitions = HashMap::new();
+/// transitions.insert("sunny", vec![("sunny", 0.8), ("rainy", 0.2)]);
+/// transitions.insert("rainy", vec![("sunny", 0.4), ("rainy", 0.6)]);
+/// let chain = MarkovChain::new(transitions).unwrap();
+///
+/// let walk = chain.walk("sunny", 10);
+/// assert_eq!(walk.len(), 10);
+/// assert_eq!(walk[0], "sunny");
+///
+/// let stationary = chain.stationary_distribution(1000, 1e-9);
+/// assert!((stationary[&"sunny"] - 2.0 / 3.0).abs() < 1e-6);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarkovChain<T> where T: Copy + Hash + Eq + Debug {
+    transitions:HashMap<T, VoseAlias<T>>,
+    states:Vec<T>,
+}
+
+
+impl<T> MarkovChain<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Builds the chain from a map (or any sequence of pairs) giving the next states of each state, with their weights.
+    ///
+    /// The states are listed by `states()` in the order they first appear in `transitions`, as a source or as a target. Give an ordered collection, such as a `Vec`, a `BTreeMap` or an `IndexMap`, for this order to be reproducible: the iteration order of a `HashMap` changes between runs.
+    ///
+    /// An error is returned if a state is given twice (`VoseAliasError::DuplicateElement`), or if the transitions of a state are empty, contain the same next state twice, or contain negative or zero-sum weights.
+    pub fn new<M: IntoIterator<Item = (T, Vec<(T, f32)>)>>(transitions:M) -> Result<MarkovChain<T>, VoseAliasError> {
+        let mut tables:HashMap<T, VoseAlias<T>> = HashMap::new();
+        let mut states:IndexSet<T> = IndexSet::new();
+        for (state, next) in transitions {
+            let (elements, weights):(Vec<T>, Vec<f64>) = next.iter().map(|(e, w)| (*e, f64::from(*w))).unzip();
+            states.insert(state);
+            states.extend(elements.iter().copied());
+            if tables.insert(state, VoseAlias::from_weights(elements, weights)?).is_some() {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", state)));
+            }
+        }
+
+        Ok(MarkovChain {
+            transitions: tables,
+            states: states.into_iter().collect(),
+        })
+    }
+
+
+    /// Returns all the states of the chain, including the dead ends.
+    pub fn states(&self) -> &[T] {
+        &self.states
+    }
+
+
+    /// Returns the table used to sample the next state from `state`, or `None` if `state` is a dead end.
+    pub fn transitions(&self, state:&T) -> Option<&VoseAlias<T>> {
+        self.transitions.get(state)
+    }
+
+
+    /// Returns a next state sampled from `state`, or `None` if no transition leaves `state`.
+    #[cfg(feature = "thread-rng")]
+    pub fn step(&self, state:&T) -> Option<T> {
+        rng::with_default_rng(|rng| self.step_with(state, rng))
+    }
+
+
+    /// Returns a next state sampled from `state` using the given generator, or `None` if no transition leaves `state`.
+    pub fn step_with<R: RngCore + ?Sized>(&self, state:&T, rng:&mut R) -> Option<T> {
+        self.transitions.get(state).map(|table| table.sample_with(rng))
+    }
+
+
+    /// Returns a walk of `len` states starting with `start` (included). The walk is shorter if it reaches a dead end.
+    #[cfg(feature = "thread-rng")]
+    pub fn walk(&self, start:T, len:usize) -> Vec<T> {
+        rng::with_default_rng(|rng| self.walk_with(start, len, rng))
+    }
+
+
+    /// Returns a walk of `len` states starting with `start` (included), using the given generator. The walk is shorter if it reaches a dead end.
+    pub fn walk_with<R: RngCore + ?Sized>(&self, start:T, len:usize, rng:&mut R) -> Vec<T> {
+        let mut walk:Vec<T> = Vec::with_capacity(len);
+        if len == 0 {
+            return walk;
+        }
+        walk.push(start);
+        let mut current = start;
+        while walk.len() < len {
+            match self.step_with(&current, rng) {
+                Some(next) => {
+                    walk.push(next);
+                    current = next;
+                },
+                None => break,
+            }
+        }
+        walk
+    }
+
+
+    /// Estimates the stationary distribution of the chain by power iteration, starting from the uniform distribution over the states.
+    ///
+    /// The iteration is run on the lazy chain (staying in place with probability 1/2), which has the same stationary distribution but also converges for periodic chains. It stops after `max_iterations` iterations, or when no probability changes by more than `tolerance`.
+    /// The probability mass reaching a dead end stays there. For chains with several closed classes, the result depends on the initial uniform distribution.
+    pub fn stationary_distribution(&self, max_iterations:usize, tolerance:f64) -> HashMap<T, f64> {
+        let size = self.states.len();
+        let index:HashMap<T, usize> = self.states.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+        // the transition probabilities of each state, as (target index, probability)
+        let rows:Vec<Vec<(usize, f64)>> = self.states.iter().map(|s| match self.transitions.get(s) {
+            Some(table) => table.elements.iter().zip(table.probabilities.iter().copied()).map(|(e, p)| (index[e], p)).collect(),
+            None => vec![(index[s], 1.0)],
+        }).collect();
+
+        let mut distribution:Vec<f64> = vec![1.0 / size as f64; size];
+        for _ in 0..max_iterations {
+            let mut next:Vec<f64> = distribution.iter().map(|p| p / 2.0).collect();
+            for (i, row) in rows.iter().enumerate() {
+                for (j, p) in row {
+                    next[*j] += distribution[i] * p / 2.0;
+                }
+            }
+            let change = distribution.iter().zip(next.iter()).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+            distribution = next;
+            if change <= tolerance {
+                break;
+            }
+        }
+
+        self.states.iter().copied().zip(distribution).collect()
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn periodic_chain() {
+        let mut transitions = HashMap::new();
+        transitions.insert(1, vec![(2, 1.0)]);
+        transitions.insert(2, vec![(3, 1.0)]);
+        transitions.insert(3, vec![(1, 1.0)]);
+        let chain = MarkovChain::new(transitions).unwrap();
+        assert_eq!(chain.walk(1, 7), vec![1, 2, 3, 1, 2, 3, 1]);
+        let stationary = chain.stationary_distribution(100, 1e-12);
+        for s in 1..=3 {
+            assert!((stationary[&s] - 1.0 / 3.0).abs() < 1e-9);
+        }
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn dead_end() {
+        let mut transitions = HashMap::new();
+        transitions.insert("start", vec![("end", 1.0)]);
+        let chain = MarkovChain::new(transitions).unwrap();
+        assert_eq!(chain.states().len(), 2);
+        assert_eq!(chain.step(&"end"), None);
+        assert_eq!(chain.walk("start", 5), vec!["start", "end"]);
+        assert!(chain.walk("start", 0).is_empty());
+        let stationary = chain.stationary_distribution(1000, 1e-12);
+        assert!((stationary[&"end"] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn invalid_transitions() {
+        let mut transitions:HashMap<u32, Vec<(u32, f32)>> = HashMap::new();
+        transitions.insert(1, vec![]);
+        assert!(MarkovChain::new(transitions).is_err());
+        let mut transitions = HashMap::new();
+        transitions.insert(1, vec![(1, 0.5), (1, 0.5)]);
+        assert!(MarkovChain::new(transitions).is_err());
+        assert_eq!(MarkovChain::new(vec![(1, vec![(2, 1.0)]), (1, vec![(1, 1.0)])]).err(), Some(VoseAliasError::DuplicateElement("1".to_string())));
+    }
+
+    #[test]
+    fn states_in_first_seen_order() {
+        let transitions = vec![(5, vec![(3, 1.0), (5, 1.0)]), (1, vec![(4, 2.0)]), (3, vec![(1, 1.0)])];
+        for _ in 0..10 {
+            let chain = MarkovChain::new(transitions.clone()).unwrap();
+            assert_eq!(chain.states(), &[5, 3, 1, 4]);
+        }
+    }
+}
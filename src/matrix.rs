@@ -0,0 +1,252 @@
+//! Row-wise Vose-Alias sampling from a stochastic matrix.
+
+use std::fmt::Debug;
+
+use rand_core::RngCore;
+
+use crate::{build_slots, check_weights, die_and_coin, VoseAliasError, COIN_SCALE};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A set of Vose-Alias tables, one per row of an n×m probability matrix, sampling a column given a row.
+///
+/// Compared to building one `VoseAlias` per row, all the tables are stored in two contiguous arrays of n×m values, the column labels are stored only once, and construction reuses the same work buffers for every row.
+/// The weights of each row are normalized, so they do not need to sum exactly to 1.
+///
+/// The type `L` of the column labels defaults to `usize`. Use `VoseAliasMatrix::new()` to sample column indexes, and `VoseAliasMatrix::with_labels()` to attach a label to each column.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::VoseAliasMatrix;
+///
+/// // 2 rows, 3 columns
+/// let matrix = VoseAliasMatrix::new(2, 3, &[0.5, 0.5, 0.0,
+///                                           0.0, 0.0, 1.0]).unwrap();
+/// assert!(matrix.sample(0) < 2);
+/// assert_eq!(matrix.sample(1), 2);
+///
+/// let labelled = VoseAliasMatrix::with_labels(vec!["a", "b", "c"], &[0.5, 0.5, 0.0, 0.0, 0.0, 1.0]).unwrap();
+/// assert_eq!(labelled.sample_label(1), "c");
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoseAliasMatrix<L = usize> where L: Copy + Debug {
+    rows:usize,
+    labels:Vec<L>,
+    // row-major tables, row r uses the slots [r * cols, (r + 1) * cols)
+    prob:Vec<f32>,
+    alias:Vec<u32>,
+}
+
+
+impl VoseAliasMatrix<usize> {
+
+    /// Builds the tables of a `rows`×`cols` matrix given in row-major order, labelling each column with its index.
+    ///
+    /// An error is returned if `probabilities` does not contain `rows * cols` values, if the matrix is empty, if a weight is negative or not finite, or if all the weights of a row are 0.
+    pub fn new(rows:usize, cols:usize, probabilities:&[f32]) -> Result<VoseAliasMatrix<usize>, VoseAliasError> {
+        if rows.checked_mul(cols) != Some(probabilities.len()) {
+            return Err(VoseAliasError::SizeMismatch { elements: rows.saturating_mul(cols), probabilities: probabilities.len() });
+        }
+        VoseAliasMatrix::with_labels((0..cols).collect(), probabilities)
+    }
+}
+
+
+impl<L> VoseAliasMatrix<L>
+where L: Copy + Debug {
+
+    /// Builds the tables of the matrix given in row-major order, with one column per label. The number of rows is deduced from the number of probabilities.
+    ///
+    /// An error is returned if the number of probabilities is not a multiple of the number of labels, if the matrix is empty, if a weight is negative or not finite, or if all the weights of a row are 0.
+    pub fn with_labels(labels:Vec<L>, probabilities:&[f32]) -> Result<VoseAliasMatrix<L>, VoseAliasError> {
+        let cols = labels.len();
+        if cols == 0 || probabilities.is_empty() {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        if probabilities.len() % cols != 0 {
+            return Err(VoseAliasError::SizeMismatch { elements: cols, probabilities: probabilities.len() });
+        }
+        if cols > u32::MAX as usize {
+            return Err(VoseAliasError::TooManyElements { elements: cols, capacity: u32::MAX as usize });
+        }
+        let rows = probabilities.len() / cols;
+
+        let mut prob:Vec<f32> = vec![0.0; rows * cols];
+        let mut alias:Vec<u32> = Vec::with_capacity(rows * cols);
+        let mut scaled:Vec<f32> = vec![0.0; cols];
+        let mut row_alias:Vec<usize> = vec![0; cols];
+        let (mut small, mut large) = (Vec::with_capacity(cols), Vec::with_capacity(cols));
+        for (r, row) in probabilities.chunks(cols).enumerate() {
+            let total = check_weights(cols, row)?;
+            for (s, w) in scaled.iter_mut().zip(row) {
+                *s = (f64::from(*w) / total * cols as f64) as f32;
+            }
+            build_slots(&mut scaled, &mut prob[r * cols..(r + 1) * cols], &mut row_alias, &mut small, &mut large);
+            alias.extend(row_alias.iter().map(|a| *a as u32));
+        }
+
+        Ok(VoseAliasMatrix {
+            rows,
+            labels,
+            prob,
+            alias,
+        })
+    }
+
+
+    /// Returns the number of rows of the matrix.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+
+    /// Returns the number of columns of the matrix.
+    pub fn cols(&self) -> usize {
+        self.labels.len()
+    }
+
+
+    /// Returns the labels of the columns.
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+
+    /// Returns the index of a column sampled from the distribution of `row`, using the default generator of the crate.
+    ///
+    /// # Panics
+    /// This function panics if `row` is not lower than the number of rows.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self, row:usize) -> usize {
+        rng::with_default_rng(|rng| self.sample_with(row, rng))
+    }
+
+
+    /// Returns the index of a column sampled from the distribution of `row`, using the given generator.
+    ///
+    /// # Panics
+    /// This function panics if `row` is not lower than the number of rows. See `VoseAliasMatrix::try_sample_with()` for a non-panicking version.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, row:usize, rng:&mut R) -> usize {
+        match self.try_sample_with(row, rng) {
+            Some(col) => col,
+            None => panic!("Row {} is out of the matrix, which has {} rows", row, self.rows),
+        }
+    }
+
+
+    /// Returns the index of a column sampled from the distribution of `row`, using the given generator, or `None` if `row` is not lower than the number of rows.
+    pub fn try_sample_with<R: RngCore + ?Sized>(&self, row:usize, rng:&mut R) -> Option<usize> {
+        if row >= self.rows {
+            return None;
+        }
+        let cols = self.labels.len();
+        let (die, coin) = die_and_coin(rng, cols);
+        let slot = row * cols + die;
+        if (coin as f32) < self.prob[slot] * COIN_SCALE {
+            Some(die)
+        }
+        else {
+            Some(self.alias[slot] as usize)
+        }
+    }
+
+
+    /// Returns the label of a column sampled from the distribution of `row`, using the default generator of the crate.
+    ///
+    /// # Panics
+    /// This function panics if `row` is not lower than the number of rows.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_label(&self, row:usize) -> L {
+        self.labels[self.sample(row)]
+    }
+
+
+    /// Returns the label of a column sampled from the distribution of `row`, using the given generator.
+    ///
+    /// # Panics
+    /// This function panics if `row` is not lower than the number of rows. See `VoseAliasMatrix::try_sample_label_with()` for a non-panicking version.
+    pub fn sample_label_with<R: RngCore + ?Sized>(&self, row:usize, rng:&mut R) -> L {
+        self.labels[self.sample_with(row, rng)]
+    }
+
+
+    /// Returns the label of a column sampled from the distribution of `row`, using the given generator, or `None` if `row` is not lower than the number of rows.
+    pub fn try_sample_label_with<R: RngCore + ?Sized>(&self, row:usize, rng:&mut R) -> Option<L> {
+        self.try_sample_with(row, rng).map(|col| self.labels[col])
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VoseAlias;
+
+    #[test]
+    fn same_tables_as_vose_alias() {
+        let probabilities = [0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125];
+        let matrix = VoseAliasMatrix::new(2, 7, &[probabilities, probabilities].concat()).unwrap();
+        let va = VoseAlias::new((0..7).collect::<Vec<usize>>(), probabilities.to_vec());
+        for r in 0..2 {
+            for c in 0..7 {
+                assert!((matrix.prob[r * 7 + c] - va.slot_prob[c]).abs() < 0.00001);
+                assert_eq!(matrix.alias[r * 7 + c], va.slot_alias[c]);
+            }
+        }
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn rows_are_independent() {
+        let matrix = VoseAliasMatrix::with_labels(vec!['a', 'b', 'c'], &[1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 3.0]).unwrap();
+        assert_eq!(matrix.rows(), 3);
+        assert_eq!(matrix.cols(), 3);
+        for _ in 0..100 {
+            assert_eq!(matrix.sample_label(0), 'a');
+            assert_eq!(matrix.sample_label(1), 'b');
+            assert_eq!(matrix.sample_label(2), 'c');
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert!(VoseAliasMatrix::new(2, 2, &[0.5, 0.5, 1.0]).is_err());
+        assert!(VoseAliasMatrix::new(0, 2, &[]).is_err());
+        assert!(VoseAliasMatrix::new(2, 2, &[0.5, 0.5, 0.0, 0.0]).is_err());
+        assert!(VoseAliasMatrix::new(1, 2, &[-0.5, 1.5]).is_err());
+
+        let matrix = VoseAliasMatrix::with_labels(vec!['a', 'b'], &[1.0, 0.0]).unwrap();
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0x9e37_79b9_7f4a_7c15);
+        assert_eq!(matrix.try_sample_with(0, &mut rng), Some(0));
+        assert_eq!(matrix.try_sample_label_with(0, &mut rng), Some('a'));
+        assert_eq!(matrix.try_sample_with(1, &mut rng), None);
+        assert_eq!(matrix.try_sample_label_with(1, &mut rng), None);
+    }
+
+    #[test]
+    fn same_draws_as_vose_alias() {
+        let weights = [0.1, 0.6, 0.3, 0.25, 0.25, 0.5];
+        let matrix = VoseAliasMatrix::new(2, 3, &weights).unwrap();
+        let row = crate::VoseAlias::new(vec![0, 1, 2], vec![0.25, 0.25, 0.5]);
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0x9e37_79b9_7f4a_7c15);
+        let mut rng2 = rng.clone();
+        for _ in 0..100 {
+            assert_eq!(matrix.sample_with(1, &mut rng), row.sample_with(&mut rng2));
+        }
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    #[should_panic]
+    fn row_out_of_bounds() {
+        let matrix = VoseAliasMatrix::new(1, 2, &[0.5, 0.5]).unwrap();
+        matrix.sample(1);
+    }
+}
@@ -0,0 +1,190 @@
+//! An n-gram model of sequences, storing one Vose-Alias table per context, to generate new sequences resembling the training ones (names, words, melodies...).
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use indexmap::IndexMap;
+use rand_core::RngCore;
+
+use crate::{VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A builder of `NGramModel`, counting the transitions of the training sequences.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::NGramBuilder;
+///
+/// let mut builder = NGramBuilder::new(2);
+/// for name in ["aldric", "alwin", "aldwin", "edric", "edwin"].iter() {
+///     builder.add_sequence(name.chars());
+/// }
+/// let model = builder.build().unwrap();
+///
+/// let name:String = model.generate(12).into_iter().collect();
+/// assert!(name.starts_with('a') || name.starts_with('e'));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NGramBuilder<T> where T: Copy + Hash + Eq + Debug {
+    context_len:usize,
+    // the counts of the next elements of each context, `None` counting the ends of the sequences
+    counts:HashMap<Vec<T>, IndexMap<Option<T>, u64>>,
+}
+
+
+impl<T> NGramBuilder<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns a builder of a model whose contexts are the last `context_len` elements of the sequence (a model of order `context_len + 1`).
+    ///
+    /// The first elements of a sequence have a shorter context: the elements before them. With a `context_len` of 0, the elements are drawn independently of each other, with their overall frequency.
+    pub fn new(context_len:usize) -> NGramBuilder<T> {
+        NGramBuilder {
+            context_len,
+            counts: HashMap::new(),
+        }
+    }
+
+
+    /// Counts the transitions of a training sequence: each element follows the context of the elements before it, and the end of the sequence follows its last context.
+    pub fn add_sequence<S: IntoIterator<Item = T>>(&mut self, sequence:S) -> &mut NGramBuilder<T> {
+        let mut context:Vec<T> = Vec::with_capacity(self.context_len);
+        for e in sequence {
+            *self.counts.entry(context.clone()).or_default().entry(Some(e)).or_insert(0) += 1;
+            push_to_context(&mut context, e, self.context_len);
+        }
+        *self.counts.entry(context).or_default().entry(None).or_insert(0) += 1;
+        self
+    }
+
+
+    /// Returns the model sampling the transitions of each context with their frequency in the training sequences.
+    ///
+    /// An error (`VoseAliasError::EmptyDistribution`) is returned if no sequence was added.
+    pub fn build(&self) -> Result<NGramModel<T>, VoseAliasError> {
+        if self.counts.is_empty() {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        let mut tables:HashMap<Vec<T>, VoseAlias<Option<T>>> = HashMap::with_capacity(self.counts.len());
+        for (context, next) in &self.counts {
+            let (elements, weights):(Vec<Option<T>>, Vec<f64>) = next.iter().map(|(e, c)| (*e, *c as f64)).unzip();
+            tables.insert(context.clone(), VoseAlias::from_weights(elements, weights)?);
+        }
+        Ok(NGramModel {
+            context_len: self.context_len,
+            tables,
+        })
+    }
+}
+
+
+/// An n-gram model generating sequences of elements of type `T`, built by a `NGramBuilder`.
+///
+/// The next element of a sequence is sampled in constant time from the table of its context, the last elements of the sequence, until the end of the sequence is sampled. Every generated context was seen during the training, as the generated sequences follow the transitions of the training ones.
+#[derive(Debug, Clone)]
+pub struct NGramModel<T> where T: Copy + Hash + Eq + Debug {
+    context_len:usize,
+    // the table of the next elements of each context, `None` ending the sequence
+    tables:HashMap<Vec<T>, VoseAlias<Option<T>>>,
+}
+
+
+impl<T> NGramModel<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns the number of elements of the contexts.
+    pub fn context_len(&self) -> usize {
+        self.context_len
+    }
+
+
+    /// Returns the number of distinct contexts seen during the training.
+    pub fn contexts(&self) -> usize {
+        self.tables.len()
+    }
+
+
+    /// Returns the table of the elements following `context`, `None` standing for the end of the sequence, or `None` if the context was not seen during the training.
+    pub fn transitions(&self, context:&[T]) -> Option<&VoseAlias<Option<T>>> {
+        self.tables.get(context)
+    }
+
+
+    /// Returns a generated sequence of at most `max_len` elements, using the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn generate(&self, max_len:usize) -> Vec<T> {
+        rng::with_default_rng(|rng| self.generate_with(max_len, rng))
+    }
+
+
+    /// Returns a generated sequence of at most `max_len` elements, using the given generator. The sequence stops when the end of a sequence is sampled, or when it reaches `max_len` elements.
+    pub fn generate_with<R: RngCore + ?Sized>(&self, max_len:usize, rng:&mut R) -> Vec<T> {
+        let mut sequence:Vec<T> = Vec::new();
+        let mut context:Vec<T> = Vec::with_capacity(self.context_len);
+        while sequence.len() < max_len {
+            match self.tables.get(&context).and_then(|table| table.sample_with(rng)) {
+                Some(e) => {
+                    sequence.push(e);
+                    push_to_context(&mut context, e, self.context_len);
+                },
+                None => break,
+            }
+        }
+        sequence
+    }
+}
+
+
+/// Appends `e` to `context`, dropping its first element if it would exceed `context_len` elements.
+fn push_to_context<T>(context:&mut Vec<T>, e:T, context_len:usize) {
+    if context_len == 0 {
+        return;
+    }
+    if context.len() == context_len {
+        context.remove(0);
+    }
+    context.push(e);
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic_sequences() {
+        let mut builder = NGramBuilder::new(2);
+        builder.add_sequence(vec![1, 2, 3, 4]).add_sequence(vec![1, 2, 3, 4]);
+        let model = builder.build().unwrap();
+        // the contexts [], [1], [1, 2], [2, 3] and [3, 4]
+        assert_eq!(model.contexts(), 5);
+        assert_eq!(model.generate(10), vec![1, 2, 3, 4]);
+        assert_eq!(model.generate(2), vec![1, 2]);
+        assert_eq!(model.transitions(&[3, 4]).unwrap().elements, vec![None]);
+        assert!(model.transitions(&[4, 1]).is_none());
+    }
+
+    #[test]
+    fn branching_sequences() {
+        let mut builder = NGramBuilder::new(1);
+        builder.add_sequence("ab".chars()).add_sequence("b".chars()).add_sequence("".chars());
+        let model = builder.build().unwrap();
+        let start = model.transitions(&[]).unwrap();
+        assert_eq!(start.elements, vec![Some('a'), Some('b'), None]);
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let sequence:String = model.generate_with(5, &mut rng).into_iter().collect();
+            assert!(sequence.is_empty() || sequence == "ab" || sequence == "b");
+        }
+        assert_eq!(NGramBuilder::<char>::new(1).build().err(), Some(VoseAliasError::EmptyDistribution));
+    }
+}
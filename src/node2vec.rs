@@ -0,0 +1,272 @@
+//! Second-order random walks biased by a return parameter and an in-out parameter, as in node2vec (feature `petgraph`).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use petgraph::graph::{DefaultIx, IndexType, NodeIndex};
+use rand_core::RngCore;
+
+use crate::{GraphSampler, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// The conditional tables of the walk, by (previous node, current node).
+type TransitionTables<Ix> = HashMap<(NodeIndex<Ix>, NodeIndex<Ix>), Arc<VoseAlias<NodeIndex<Ix>>>>;
+
+
+/// A sampler of node2vec random walks over the graph of a `GraphSampler`, where the next node depends on the current node and on the previous one.
+///
+/// From the current node `v`, reached from the node `t`, the weight of the edge to a neighbor `x` of `v` is multiplied by:
+/// - `1 / p` if `x` is `t` (going back),
+/// - `1` if `x` is also a neighbor of `t` (staying at the same distance from `t`),
+/// - `1 / q` otherwise (moving away from `t`).
+///
+/// A low return parameter `p` keeps the walk close to its start, and a low in-out parameter `q` pushes it outwards, like a depth-first search. With `p = q = 1`, the walk is the first-order walk of `GraphSampler`. The first step of a walk has no previous node, and is a first-order step.
+///
+/// The table of each (previous node, current node) pair is built the first time the pair is visited, and cached for the following visits: visiting every pair of a graph stores one table per edge direction, with as many entries as the neighbors of the current node. The cache is shared between the threads using the sampler, and released with `Node2VecSampler::clear_cache()`.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use petgraph::graph::Graph;
+/// use vose_alias::{GraphSampler, Node2VecSampler};
+///
+/// // a path a - b - c: from b, reached from a, the walk goes back to a or moves on to c
+/// let mut graph = Graph::new_undirected();
+/// let a = graph.add_node("a");
+/// let b = graph.add_node("b");
+/// let c = graph.add_node("c");
+/// graph.add_edge(a, b, 1.0);
+/// graph.add_edge(b, c, 1.0);
+///
+/// let outwards = Node2VecSampler::new(GraphSampler::new(&graph, |w| *w).unwrap(), 100.0, 0.01).unwrap();
+/// let mut rng = rand::thread_rng();
+/// let forward = (0..1000).filter(|_| outwards.step_with(Some(a), b, &mut rng) == Some(c)).count();
+/// assert!(forward > 990);
+/// assert_eq!(outwards.cached_tables(), 1);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Node2VecSampler<Ix = DefaultIx> where Ix: IndexType {
+    graph:GraphSampler<Ix>,
+    p:f64,
+    q:f64,
+    tables:Mutex<TransitionTables<Ix>>,
+}
+
+
+impl<Ix> Node2VecSampler<Ix>
+where Ix: IndexType {
+
+    /// Returns the sampler of the walks over the graph of `graph`, biased by the return parameter `p` and the in-out parameter `q`.
+    ///
+    /// An error is returned if `p` or `q` is not a strictly positive finite number whose inverse is also finite (a subnormal number is rejected).
+    pub fn new(graph:GraphSampler<Ix>, p:f64, q:f64) -> Result<Node2VecSampler<Ix>, VoseAliasError> {
+        if !(p.is_finite() && p > 0.0 && (1.0 / p).is_finite()) {
+            return Err(VoseAliasError::InvalidParameter { name: "p", value: p });
+        }
+        if !(q.is_finite() && q > 0.0 && (1.0 / q).is_finite()) {
+            return Err(VoseAliasError::InvalidParameter { name: "q", value: q });
+        }
+        Ok(Node2VecSampler {
+            graph,
+            p,
+            q,
+            tables: Mutex::new(HashMap::new()),
+        })
+    }
+
+
+    /// Returns the first-order sampler of the graph.
+    pub fn graph(&self) -> &GraphSampler<Ix> {
+        &self.graph
+    }
+
+
+    /// Returns the number of conditional tables currently cached.
+    pub fn cached_tables(&self) -> usize {
+        self.lock().len()
+    }
+
+
+    /// Removes the cached conditional tables. They are built again when the walks visit their pairs of nodes.
+    pub fn clear_cache(&self) {
+        self.lock().clear();
+    }
+
+
+    /// Returns the next node of a walk at `current`, reached from `previous`, sampled with the given generator, or `None` if `current` is a dead end.
+    ///
+    /// Without a previous node, the step is a first-order step (see `GraphSampler::sample_neighbor_with()`).
+    pub fn step_with<R: RngCore + ?Sized>(&self, previous:Option<NodeIndex<Ix>>, current:NodeIndex<Ix>, rng:&mut R) -> Option<NodeIndex<Ix>> {
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return self.graph.sample_neighbor_with(current, rng),
+        };
+        self.transitions(previous, current).map(|table| table.sample_with(rng))
+    }
+
+
+    /// Returns a walk of `len` nodes starting with `start` (included), using the default generator of the crate. The walk is shorter if it reaches a dead end.
+    #[cfg(feature = "thread-rng")]
+    pub fn walk(&self, start:NodeIndex<Ix>, len:usize) -> Vec<NodeIndex<Ix>> {
+        rng::with_default_rng(|rng| self.walk_with(start, len, rng))
+    }
+
+
+    /// Returns a walk of `len` nodes starting with `start` (included), using the given generator. The walk is shorter if it reaches a dead end.
+    pub fn walk_with<R: RngCore + ?Sized>(&self, start:NodeIndex<Ix>, len:usize, rng:&mut R) -> Vec<NodeIndex<Ix>> {
+        let mut walk:Vec<NodeIndex<Ix>> = Vec::with_capacity(len);
+        if len == 0 {
+            return walk;
+        }
+        walk.push(start);
+        let mut previous = None;
+        let mut current = start;
+        while walk.len() < len {
+            match self.step_with(previous, current, rng) {
+                Some(next) => {
+                    walk.push(next);
+                    previous = Some(current);
+                    current = next;
+                },
+                None => break,
+            }
+        }
+        walk
+    }
+
+
+    /// Returns `walks_per_node` walks of `len` nodes starting from each node of the graph, using the default generator of the crate. See `GraphSampler::walks_with()` for their order.
+    #[cfg(feature = "thread-rng")]
+    pub fn walks(&self, walks_per_node:usize, len:usize) -> Vec<Vec<NodeIndex<Ix>>> {
+        rng::with_default_rng(|rng| self.walks_with(walks_per_node, len, rng))
+    }
+
+
+    /// Returns `walks_per_node` walks of `len` nodes starting from each node of the graph, using the given generator, as the corpus of a node2vec embedding. See `GraphSampler::walks_with()` for their order.
+    pub fn walks_with<R: RngCore + ?Sized>(&self, walks_per_node:usize, len:usize, rng:&mut R) -> Vec<Vec<NodeIndex<Ix>>> {
+        let nodes = self.graph.node_count();
+        let mut walks:Vec<Vec<NodeIndex<Ix>>> = Vec::with_capacity(walks_per_node * nodes);
+        for _ in 0..walks_per_node {
+            for node in 0..nodes {
+                walks.push(self.walk_with(NodeIndex::new(node), len, rng));
+            }
+        }
+        walks
+    }
+
+
+    /// Returns the conditional table of the neighbors of `current` reached from `previous`, building and caching it if needed, or `None` if `current` is a dead end.
+    fn transitions(&self, previous:NodeIndex<Ix>, current:NodeIndex<Ix>) -> Option<Arc<VoseAlias<NodeIndex<Ix>>>> {
+        if let Some(table) = self.lock().get(&(previous, current)) {
+            return Some(Arc::clone(table));
+        }
+
+        let neighbors = self.graph.neighbors(current)?;
+        let previous_neighbors = self.graph.neighbors(previous);
+        let factors:Vec<f64> = neighbors.elements.iter().map(|x| {
+            if *x == previous {
+                1.0 / self.p
+            }
+            else if previous_neighbors.is_some_and(|table| table.index.contains_key(x)) {
+                1.0
+            }
+            else {
+                1.0 / self.q
+            }
+        }).collect();
+        // the factors are divided by the largest one of a reachable neighbor, which keeps its weight unchanged: the biased weights
+        // cannot overflow, and cannot all underflow to 0, whatever the values of p and q
+        let largest = factors.iter().zip(neighbors.probabilities.iter()).filter(|(_, w)| **w > 0.0).map(|(f, _)| *f).fold(0.0, f64::max);
+        let weights:Vec<f64> = factors.iter().zip(neighbors.probabilities.iter()).map(|(f, w)| w * (f / largest)).collect();
+        let table = match VoseAlias::from_weights(neighbors.elements.clone(), weights) {
+            Ok(table) => Arc::new(table),
+            Err(e) => panic!("Internal error. The biased weights are not valid: {}. If this happened, please fill in an issue report.", e),
+        };
+        self.lock().insert((previous, current), Arc::clone(&table));
+        Some(table)
+    }
+
+
+    fn lock(&self) -> MutexGuard<'_, TransitionTables<Ix>> {
+        // a panic while holding the lock cannot leave the map in an inconsistent state
+        match self.tables.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::graph::Graph;
+
+    #[test]
+    fn biased_transitions() {
+        // a triangle a - b - c, with a pendant node d attached to b
+        let mut graph:Graph<(), f64, petgraph::Undirected> = Graph::new_undirected();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let c = graph.add_node(());
+        let d = graph.add_node(());
+        graph.add_edge(a, b, 1.0);
+        graph.add_edge(b, c, 1.0);
+        graph.add_edge(a, c, 1.0);
+        graph.add_edge(b, d, 1.0);
+        let sampler = Node2VecSampler::new(GraphSampler::new(&graph, |w| *w).unwrap(), 0.5, 2.0).unwrap();
+
+        // from b reached from a: back to a ×2, to c (a neighbor of a) ×1, to d ×1/2
+        let table = sampler.transitions(a, b).unwrap();
+        let probability = |x:NodeIndex| table.probabilities[table.index[&x]];
+        assert!((probability(a) - 4.0 / 7.0).abs() < 1e-12);
+        assert!((probability(c) - 2.0 / 7.0).abs() < 1e-12);
+        assert!((probability(d) - 1.0 / 7.0).abs() < 1e-12);
+        assert_eq!(sampler.cached_tables(), 1);
+        assert!(Arc::ptr_eq(&table, &sampler.transitions(a, b).unwrap()));
+
+        let walks = sampler.walks_with(3, 5, &mut rand::rngs::mock::StepRng::new(0, 0x9e37_79b9_7f4a_7c15));
+        assert_eq!(walks.len(), 12);
+        assert!(walks.iter().all(|w| w.len() == 5));
+        sampler.clear_cache();
+        assert_eq!(sampler.cached_tables(), 0);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let graph:Graph<(), f64> = Graph::new();
+        let sampler = GraphSampler::new(&graph, |w| *w).unwrap();
+        assert!(matches!(Node2VecSampler::new(sampler.clone(), 0.0, 1.0), Err(VoseAliasError::InvalidParameter { name: "p", .. })));
+        assert!(matches!(Node2VecSampler::new(sampler.clone(), 1.0, f64::NAN), Err(VoseAliasError::InvalidParameter { name: "q", .. })));
+        assert!(matches!(Node2VecSampler::new(sampler, 1e-310, 1.0), Err(VoseAliasError::InvalidParameter { name: "p", .. })));
+    }
+
+    #[test]
+    fn extreme_parameters() {
+        // a star around b: from b reached from a, every other neighbor moves away from a
+        let mut graph:Graph<(), f64, petgraph::Undirected> = Graph::new_undirected();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let c = graph.add_node(());
+        let d = graph.add_node(());
+        graph.add_edge(a, b, 1.0);
+        graph.add_edge(b, c, 1e-300);
+        graph.add_edge(b, d, 1e-300);
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0x9e37_79b9_7f4a_7c15);
+
+        let away = Node2VecSampler::new(GraphSampler::new(&graph, |w| *w).unwrap(), f64::MAX, 1e-300).unwrap();
+        let table = away.transitions(a, b).unwrap();
+        assert_eq!(table.probabilities[table.index[&a]], 0.0);
+        assert!(away.step_with(Some(a), b, &mut rng).is_some());
+
+        let back = Node2VecSampler::new(GraphSampler::new(&graph, |w| *w).unwrap(), 1e-300, f64::MAX).unwrap();
+        assert_eq!(back.step_with(Some(a), b, &mut rng), Some(a));
+    }
+}
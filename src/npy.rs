@@ -0,0 +1,154 @@
+//! Loading of the weights from NumPy `.npy` and `.npz` files (feature `npy`).
+//!
+//! The weights should be stored in a one-dimensional array of `float64` or `float32` values, such as the one written by `numpy.save(path, weights)`.
+
+use std::fmt::Debug;
+use std::fs::File;
+use std::hash::Hash;
+use std::path::Path;
+
+use ndarray::{Array1, Ix1, OwnedRepr};
+use ndarray_npy::{NpzReader, ReadNpyError, ReadNpyExt, ReadNpzError};
+
+use crate::{VoseAlias, VoseAliasError};
+
+
+impl VoseAlias<usize> {
+
+    /// Returns the Vose-Alias object sampling the indexes of the weights stored in the `.npy` file at `path`, each proportionally to its weight. The weights do not need to sum to 1.
+    ///
+    /// An error is returned if the file cannot be read or does not contain a one-dimensional array of floats, or in the same cases as `VoseAlias::from_counts()`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// // written in Python with numpy.save("weights.npy", weights)
+    /// let va = VoseAlias::from_npy("weights.npy").unwrap();
+    /// let index = va.sample();
+    /// # }
+    /// ```
+    pub fn from_npy<P: AsRef<Path>>(path:P) -> Result<VoseAlias<usize>, VoseAliasError> {
+        let weights = read_npy_weights(path.as_ref())?;
+        VoseAlias::from_weights((0..weights.len()).collect(), weights)
+    }
+
+
+    /// Returns the Vose-Alias object sampling the indexes of the weights stored in the array `name` of the `.npz` file at `path`. The name of an array is the keyword it was given to `numpy.savez()`, such as `"weights"`.
+    ///
+    /// An error is returned in the same cases as `VoseAlias::from_npy()`, and if the archive does not contain the array.
+    pub fn from_npz<P: AsRef<Path>>(path:P, name:&str) -> Result<VoseAlias<usize>, VoseAliasError> {
+        let weights = read_npz_weights(path.as_ref(), name)?;
+        VoseAlias::from_weights((0..weights.len()).collect(), weights)
+    }
+}
+
+
+impl<T> VoseAlias<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns the Vose-Alias object sampling `labels`, each proportionally to the weight at the same position in the `.npy` file at `path`.
+    ///
+    /// An error is returned in the same cases as `VoseAlias::from_npy()`, and if the number of labels does not match the number of weights.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::from_npy_labeled("class_weights.npy", vec!["cat", "dog", "bird"]).unwrap();
+    /// let label = va.sample();
+    /// # }
+    /// ```
+    pub fn from_npy_labeled<P: AsRef<Path>>(path:P, labels:Vec<T>) -> Result<VoseAlias<T>, VoseAliasError> {
+        VoseAlias::from_weights(labels, read_npy_weights(path.as_ref())?)
+    }
+
+
+    /// Returns the Vose-Alias object sampling `labels`, each proportionally to the weight at the same position in the array `name` of the `.npz` file at `path`.
+    ///
+    /// An error is returned in the same cases as `VoseAlias::from_npz()`, and if the number of labels does not match the number of weights.
+    pub fn from_npz_labeled<P: AsRef<Path>>(path:P, name:&str, labels:Vec<T>) -> Result<VoseAlias<T>, VoseAliasError> {
+        VoseAlias::from_weights(labels, read_npz_weights(path.as_ref(), name)?)
+    }
+}
+
+
+/// Reads the weights stored in a `.npy` file, as `float64` or `float32` values.
+fn read_npy_weights(path:&Path) -> Result<Vec<f64>, VoseAliasError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => return Err(VoseAliasError::InvalidFile(format!("{} cannot be read ({})", path.display(), e))),
+    };
+    let weights = match Array1::<f64>::read_npy(bytes.as_slice()) {
+        Err(ReadNpyError::WrongDescriptor(_)) => Array1::<f32>::read_npy(bytes.as_slice()).map(|a| a.iter().map(|w| f64::from(*w)).collect()),
+        result => result.map(|a| a.to_vec()),
+    };
+    weights.map_err(|e| VoseAliasError::InvalidFile(format!("{} does not contain a one-dimensional array of floats ({})", path.display(), e)))
+}
+
+
+/// Reads the weights stored in the array `name` of a `.npz` file, as `float64` or `float32` values.
+fn read_npz_weights(path:&Path, name:&str) -> Result<Vec<f64>, VoseAliasError> {
+    let invalid = |e:ReadNpzError| VoseAliasError::InvalidFile(format!("{} does not contain a one-dimensional array of floats named {} ({})", path.display(), name, e));
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => return Err(VoseAliasError::InvalidFile(format!("{} cannot be read ({})", path.display(), e))),
+    };
+    let mut npz = NpzReader::new(file).map_err(invalid)?;
+    // numpy.savez() adds the extension to the name of each array
+    let entry = if name.ends_with(".npy") { name.to_string() } else { format!("{}.npy", name) };
+    match npz.by_name::<OwnedRepr<f64>, Ix1>(&entry) {
+        Ok(a) => Ok(a.to_vec()),
+        Err(ReadNpzError::Npy(ReadNpyError::WrongDescriptor(_))) => {
+            let a = npz.by_name::<OwnedRepr<f32>, Ix1>(&entry).map_err(invalid)?;
+            Ok(a.iter().map(|w| f64::from(*w)).collect())
+        },
+        Err(e) => Err(invalid(e)),
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+    use ndarray_npy::{write_npy, NpzWriter};
+
+    fn temp_path(name:&str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("vose_alias_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn npy_files() {
+        let path = temp_path("weights.npy");
+        write_npy(&path, &array![1.0f32, 3.0]).unwrap();
+        let va = VoseAlias::from_npy(&path).unwrap();
+        assert_eq!(va.elements, vec![0, 1]);
+        let va = VoseAlias::from_npy_labeled(&path, vec!['a', 'b']).unwrap();
+        assert!((va.probabilities[1] - 0.75).abs() < 1e-6);
+        assert!(matches!(VoseAlias::from_npy_labeled(&path, vec!['a']), Err(VoseAliasError::SizeMismatch { .. })));
+
+        write_npy(&path, &array![[1.0f64, 2.0], [3.0, 4.0]]).unwrap();
+        assert!(matches!(VoseAlias::from_npy(&path), Err(VoseAliasError::InvalidFile(_))));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(VoseAlias::from_npy(&path), Err(VoseAliasError::InvalidFile(_))));
+    }
+
+    #[test]
+    fn npz_files() {
+        let path = temp_path("arrays.npz");
+        let mut npz = NpzWriter::new(File::create(&path).unwrap());
+        npz.add_array("weights", &array![2.0f64, 2.0, 4.0]).unwrap();
+        npz.finish().unwrap();
+        let va = VoseAlias::from_npz_labeled(&path, "weights", vec![10, 20, 30]).unwrap();
+        assert!((va.probabilities[2] - 0.5).abs() < 1e-6);
+        assert!(matches!(VoseAlias::from_npz(&path, "bias"), Err(VoseAliasError::InvalidFile(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
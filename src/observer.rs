@@ -0,0 +1,145 @@
+//! Observer hooks, notified of every sample drawn from a Vose-Alias object.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::{SlotIndex, VoseAlias};
+
+
+/// The description of a sample, passed to the observers of a `VoseAlias` object.
+///
+/// The event refers to the sampled element by its position, so that observers do not depend on the element type: the element is `elements[event.index]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleEvent {
+    /// The position of the sampled element in the `elements` vector.
+    pub index:usize,
+    /// The slot (column) of the tables selected by the die.
+    pub slot:usize,
+    /// `true` if the coin selected the alias of the slot rather than its own element.
+    pub alias_used:bool,
+}
+
+
+/// An observer notified of every sample drawn from a `VoseAlias` object it is attached to, for metrics, logging or anomaly detection.
+///
+/// The observer is called by all the sampling functions of `VoseAlias`, except the constant-time ones, from the thread drawing the sample. It is implemented for all the closures taking a `&SampleEvent`.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use vose_alias::VoseAlias;
+///
+/// let aliased = Arc::new(AtomicUsize::new(0));
+/// let counter = aliased.clone();
+///
+/// let mut va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+/// va.set_observer(Arc::new(move |event:&vose_alias::SampleEvent| {
+///     if event.alias_used {
+///         counter.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }));
+/// for _ in 0..100 {
+///     va.sample();
+/// }
+/// assert!(aliased.load(Ordering::Relaxed) <= 100);
+/// # }
+/// ```
+pub trait SampleObserver: Send + Sync {
+    /// Called after each sample.
+    fn on_sample(&self, event:&SampleEvent);
+}
+
+
+impl<F> SampleObserver for F
+where F: Fn(&SampleEvent) + Send + Sync {
+    fn on_sample(&self, event:&SampleEvent) {
+        self(event)
+    }
+}
+
+
+/// The observer attached to a `VoseAlias` object, if any.
+#[derive(Clone)]
+pub(crate) struct ObserverSlot(Option<Arc<dyn SampleObserver>>);
+
+
+impl ObserverSlot {
+
+    pub(crate) fn empty() -> ObserverSlot {
+        ObserverSlot(None)
+    }
+
+
+    pub(crate) fn notify(&self, index:usize, slot:usize, alias_used:bool) {
+        if let Some(observer) = &self.0 {
+            observer.on_sample(&SampleEvent { index, slot, alias_used });
+        }
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Attaches an observer, notified of every sample drawn from this object. It replaces the previous observer, if any.
+    ///
+    /// The observer is shared with the clones of this object made afterwards.
+    pub fn set_observer(&mut self, observer:Arc<dyn SampleObserver>) {
+        self.observer = ObserverSlot(Some(observer));
+    }
+
+
+    /// Detaches the observer of this object, and returns it.
+    pub fn clear_observer(&mut self) -> Option<Arc<dyn SampleObserver>> {
+        self.observer.0.take()
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn observed_samples() {
+        let events:Arc<Mutex<Vec<SampleEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        let mut va = VoseAlias::new(vec!["a", "b"], vec![0.25, 0.75]);
+        va.set_observer(Arc::new(move |event:&SampleEvent| log.lock().unwrap().push(*event)));
+
+        let samples:Vec<&str> = (0..100).map(|_| va.sample()).collect();
+        {
+            let events = events.lock().unwrap();
+            assert_eq!(events.iter().map(|e| va.elements[e.index]).collect::<Vec<&str>>(), samples);
+            for event in events.iter() {
+                assert_eq!(event.alias_used, event.slot != event.index);
+            }
+        }
+
+        assert!(va.clear_observer().is_some());
+        va.sample();
+        assert_eq!(events.lock().unwrap().len(), 100);
+    }
+
+    #[test]
+    fn observer_sampling() {
+        // the observer samples from another distribution, with the default generator borrowed by the outer sample
+        let inner = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        let draws:Arc<Mutex<Vec<i32>>> = Arc::new(Mutex::new(Vec::new()));
+        let log = draws.clone();
+        let mut va = VoseAlias::new(vec!["a", "b"], vec![0.25, 0.75]);
+        va.set_observer(Arc::new(move |_:&SampleEvent| log.lock().unwrap().push(inner.sample())));
+
+        assert_eq!(va.sample_n(10).len(), 10);
+        va.sample();
+        assert_eq!(draws.lock().unwrap().len(), 11);
+    }
+}
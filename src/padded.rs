@@ -0,0 +1,164 @@
+//! A Vose-Alias table padded to a power of two columns, so that the column is selected with a bit mask.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{build_slots, SlotIndex, VoseAlias, VoseAliasError, COIN_BITS, COIN_SCALE};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A Vose-Alias table whose number of columns is padded to the next power of two, for tight sampling loops.
+///
+/// With a power of two columns, the die is a mask of the bits of the random value, instead of a multiplication by the number of columns. The die is also exactly uniform, where the multiplication is biased by less than n / 2^32. The padding columns have a probability of 0 of keeping their own (nonexistent) element, so they always return their alias, which is a real element: the sampled distribution is the same as the one of the `VoseAlias` object.
+/// The tables take up to twice the memory of the unpadded ones.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::VoseAlias;
+///
+/// let va = VoseAlias::new(vec!["a", "b", "c"], vec![0.5, 0.3, 0.2]);
+/// let padded = va.padded().unwrap();
+/// assert_eq!(padded.columns(), 4);
+/// let element = padded.sample();
+/// assert!(va.elements.contains(&element));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaddedVoseAlias<T> where T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    // the tables of the columns, padded with columns always returning their alias
+    prob:Vec<f32>,
+    alias:Vec<u32>,
+    mask:u32,
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns a copy of the distribution, with its tables padded to the next power of two columns.
+    ///
+    /// See `PaddedVoseAlias` for more details. An error is returned if the padded tables would have more than 2^32 columns.
+    pub fn padded(&self) -> Result<PaddedVoseAlias<T>, VoseAliasError> {
+        let size = self.elements.len();
+        let columns = size.next_power_of_two();
+        // the mask of the columns and the aliases are stored as u32
+        if columns - 1 > u32::MAX as usize {
+            return Err(VoseAliasError::TooManyElements { elements: size, capacity: u32::MAX as usize });
+        }
+
+        let mut scaled:Vec<f32> = self.probabilities.iter().map(|p| (p * columns as f64) as f32).collect();
+        scaled.resize(columns, 0.0);
+        let mut prob:Vec<f32> = vec![0.0; columns];
+        let mut alias:Vec<usize> = vec![0; columns];
+        build_slots(&mut scaled, &mut prob, &mut alias, &mut Vec::new(), &mut Vec::new());
+
+        // a padding column left without alias by rounding errors is given to the most probable element
+        let mut most_probable = 0;
+        for (i, p) in self.probabilities.iter().enumerate() {
+            if *p > self.probabilities[most_probable] {
+                most_probable = i;
+            }
+        }
+        for j in size..columns {
+            if alias[j] == j {
+                alias[j] = most_probable;
+            }
+            prob[j] = 0.0;
+        }
+
+        Ok(PaddedVoseAlias {
+            elements: self.elements.clone(),
+            prob,
+            alias: alias.into_iter().map(|a| a as u32).collect(),
+            mask: (columns - 1) as u32,
+        })
+    }
+}
+
+
+impl<T> PaddedVoseAlias<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns the elements sampled from.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the number of columns of the padded tables, a power of two.
+    pub fn columns(&self) -> usize {
+        self.prob.len()
+    }
+
+
+    /// Returns a sampled element, using the default generator of the crate (see `VoseAlias::sample()`).
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> T {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a sampled element, using the given random number generator. A single 64-bit value is drawn: the column is taken from its high 32 bits, and the coin from its low 24 bits.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> T {
+        let bits = rng.next_u64();
+        let die = ((bits >> 32) as u32 & self.mask) as usize;
+        let coin = (bits as u32) & ((1 << COIN_BITS) - 1);
+        self.elements[self.select_index(die, coin)]
+    }
+
+
+    /// This function selects the index of an element given a die (a column) and a coin in [0, 2^24), like `VoseAlias::sample_with()`.
+    fn select_index(&self, die:usize, coin:u32) -> usize {
+        if (coin as f32) < self.prob[die] * COIN_SCALE {
+            die
+        }
+        else {
+            self.alias[die] as usize
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_tables() {
+        let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+        let padded = va.padded().unwrap();
+        assert_eq!(padded.columns(), 8);
+        assert_eq!(padded.prob[7], 0.0);
+        assert!((padded.alias[7] as usize) < 7);
+
+        // the probability of each element, from the padded tables
+        let mut mass = [0.0; 7];
+        for j in 0..8 {
+            let p = f64::from(padded.prob[j]);
+            let a = padded.alias[j] as usize;
+            if j < 7 {
+                mass[j] += if a == j { 1.0 } else { p };
+            }
+            if a != j {
+                mass[a] += 1.0 - p;
+            }
+        }
+        for (m, p) in mass.iter().zip(va.probabilities.iter()) {
+            assert!((m / 8.0 - p).abs() < 1e-6);
+        }
+
+        let mut rng = rand::thread_rng();
+        assert!((0..1000).all(|_| va.elements.contains(&padded.sample_with(&mut rng))));
+        let single = VoseAlias::new(vec![1], vec![1.0]).padded().unwrap();
+        assert_eq!((single.columns(), single.sample()), (1, 1));
+    }
+}
@@ -0,0 +1,107 @@
+//! Parallel iterators of samples, backed by rayon.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use crate::keyed::splitmix64;
+use crate::{rng, SlotIndex, VoseAlias};
+
+
+/// Number of samples drawn from each sub-stream by `VoseAlias::par_samples_seeded()`.
+const SEEDED_CHUNK_SIZE:usize = 4096;
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug + Send + Sync, I: SlotIndex + Sync {
+
+    /// Returns a parallel iterator over `n` elements sampled independently, for data-parallel processing of large batches of draws.
+    ///
+    /// Each split of the iterator owns a generator of the default type of the crate (see the `small-rng` and `xoshiro` features), seeded from the thread-local generator of `rand`, so the workers never share a generator. The samples are not reproducible: their values depend on how the work is split between the threads.
+    ///
+    /// # Examples
+    /// ```
+    /// use rayon::prelude::*;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let payouts = VoseAlias::new(vec![0, 10, 100], vec![0.9, 0.09, 0.01]);
+    /// let total:u64 = payouts.par_samples(1_000_000).map(|p| p as u64).sum();
+    /// let mean = total as f64 / 1_000_000.0;
+    /// assert!(mean > 1.5 && mean < 2.3);
+    /// ```
+    pub fn par_samples(&self, n:usize) -> impl ParallelIterator<Item = T> + '_ {
+        (0..n).into_par_iter().map_init(rng::new_default_rng, move |rng, _| self.sample_with(rng))
+    }
+
+
+    /// Returns a parallel iterator over `n` elements sampled independently, which are the same for a given `seed` whatever the number of threads and the order in which the work is done.
+    ///
+    /// The draws are split into chunks of fixed size, and the chunk `k` is drawn from its own sub-stream: a `rand::rngs::StdRng` seeded from `seed` and `k`. Since a chunk only depends on its seed, collecting the iterator (which keeps the order of the samples) gives a bit-identical vector on any machine with the same versions of this crate and of `rand`. The sub-streams are derived from different seeds, which makes overlaps between them negligible.
+    ///
+    /// # Examples
+    /// ```
+    /// use rayon::prelude::*;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["a", "b", "c"], vec![0.5, 0.3, 0.2]);
+    /// let batch:Vec<&str> = va.par_samples_seeded(100_000, 42).collect();
+    ///
+    /// let single_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    /// let same:Vec<&str> = single_thread.install(|| va.par_samples_seeded(100_000, 42).collect());
+    /// assert_eq!(batch, same);
+    /// ```
+    pub fn par_samples_seeded(&self, n:usize, seed:u64) -> impl ParallelIterator<Item = T> + '_ {
+        let chunks = n.div_ceil(SEEDED_CHUNK_SIZE);
+        (0..chunks).into_par_iter().flat_map_iter(move |k| {
+            let mut rng = sub_stream(seed, k as u64);
+            let size = SEEDED_CHUNK_SIZE.min(n - k * SEEDED_CHUNK_SIZE);
+            (0..size).map(move |_| self.sample_with(&mut rng))
+        })
+    }
+}
+
+
+/// Returns the generator of the sub-stream `stream`, derived from `seed`.
+fn sub_stream(seed:u64, stream:u64) -> StdRng {
+    // mixing the seed before adding the stream id keeps close seeds from sharing sub-streams
+    let mut state = seed;
+    let mut state = splitmix64(&mut state).wrapping_add(stream);
+    StdRng::seed_from_u64(splitmix64(&mut state))
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_samples() {
+        let va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.25, 0.25]);
+        assert_eq!(va.par_samples(10000).count(), 10000);
+        let ones = va.par_samples(10000).filter(|e| *e == 1).count();
+        assert!(ones > 4500 && ones < 5500);
+        assert_eq!(va.par_samples(0).count(), 0);
+    }
+
+    #[test]
+    fn reproducible_samples() {
+        let va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.25, 0.25]);
+        let n = 3 * SEEDED_CHUNK_SIZE + 7;
+        let expected:Vec<i32> = va.par_samples_seeded(n, 7).collect();
+        assert_eq!(expected.len(), n);
+        for threads in [1, 2, 5] {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let samples:Vec<i32> = pool.install(|| va.par_samples_seeded(n, 7).collect());
+            assert_eq!(samples, expected);
+        }
+        let other:Vec<i32> = va.par_samples_seeded(n, 8).collect();
+        assert_ne!(other, expected);
+    }
+}
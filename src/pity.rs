@@ -0,0 +1,182 @@
+//! A sampler guaranteeing a rare element after a number of draws without it ("pity timer").
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A stateful sampler that counts the draws since a designated rare element was last sampled, and guarantees it after a threshold, as in the drop systems of gacha games.
+///
+/// The draws follow the wrapped distribution until the pity kicks in:
+/// - with a hard pity of N, the N-th consecutive draw without the rare element is forced to return it
+/// - with an optional soft pity starting at draw S, the probability of the rare element grows by a fixed increment at each draw from S on, the other elements keeping their relative probabilities
+///
+/// The counter is reset each time the rare element is sampled, whether it was forced or not.
+/// Each draw first decides whether the rare element is returned, then samples the other elements from their own table, so that an element of probability 0 is never returned.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::{PitySampler, VoseAlias};
+///
+/// let drops = VoseAlias::new(vec!["common", "epic", "legendary"], vec![0.89, 0.1, 0.01]);
+/// let mut banner = PitySampler::new(drops, "legendary", 90).unwrap().with_soft_pity(75, 0.06).unwrap();
+///
+/// let draws:Vec<&str> = (0..90).map(|_| banner.sample()).collect();
+/// assert!(draws.contains(&"legendary"));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PitySampler<T> where T: Copy + Hash + Eq + Debug {
+    // the distribution without the rare element, or `None` if the rare element is the only one with a positive probability
+    others:Option<VoseAlias<T>>,
+    rare:T,
+    base_probability:f64,
+    hard_pity:u32,
+    soft_pity:Option<(u32, f64)>,
+    draws_since_rare:u32,
+}
+
+
+impl<T> PitySampler<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns a sampler following `va`, which forces `rare` on the `hard_pity`-th consecutive draw without it.
+    ///
+    /// An error is returned if `rare` is not an element of `va`, or if `hard_pity` is 0.
+    pub fn new(va:VoseAlias<T>, rare:T, hard_pity:u32) -> Result<PitySampler<T>, VoseAliasError> {
+        let rare_index = match va.index.get(&rare) {
+            Some(i) => *i,
+            None => return Err(VoseAliasError::UnknownElement(format!("{:?}", rare))),
+        };
+        if hard_pity == 0 {
+            return Err(VoseAliasError::InvalidParameter { name: "hard_pity", value: 0.0 });
+        }
+        let weights:Vec<f64> = va.probabilities.iter().enumerate().map(|(i, p)| if i == rare_index { 0.0 } else { *p }).collect();
+        Ok(PitySampler {
+            others: VoseAlias::from_positive_weights(&va.elements, &weights),
+            base_probability: va.probabilities[rare_index],
+            rare,
+            hard_pity,
+            soft_pity: None,
+            draws_since_rare: 0,
+        })
+    }
+
+
+    /// Adds a soft pity: from the `start`-th consecutive draw without the rare element on, its probability is raised by `increment` per draw, the draw `start` getting one increment.
+    ///
+    /// An error is returned if `start` is 0, or if `increment` is negative or not finite.
+    pub fn with_soft_pity(mut self, start:u32, increment:f64) -> Result<PitySampler<T>, VoseAliasError> {
+        if start == 0 {
+            return Err(VoseAliasError::InvalidParameter { name: "start", value: 0.0 });
+        }
+        if !increment.is_finite() || increment < 0.0 {
+            return Err(VoseAliasError::InvalidParameter { name: "increment", value: increment });
+        }
+        self.soft_pity = Some((start, increment));
+        Ok(self)
+    }
+
+
+    /// Returns the rare element.
+    pub fn rare(&self) -> T {
+        self.rare
+    }
+
+
+    /// Returns the number of consecutive draws without the rare element since it was last sampled (or since the creation of the sampler).
+    pub fn draws_since_rare(&self) -> u32 {
+        self.draws_since_rare
+    }
+
+
+    /// Resets the counter of draws without the rare element.
+    pub fn reset(&mut self) {
+        self.draws_since_rare = 0;
+    }
+
+
+    /// Returns the probability that the next draw returns the rare element, given the current counter.
+    pub fn rare_probability(&self) -> f64 {
+        let draw = self.draws_since_rare.saturating_add(1);
+        if draw >= self.hard_pity {
+            return 1.0;
+        }
+        match self.soft_pity {
+            Some((start, increment)) if draw >= start => (self.base_probability + increment * f64::from(draw - start + 1)).min(1.0),
+            _ => self.base_probability,
+        }
+    }
+
+
+    /// Returns a sampled element using the default generator of the crate, and updates the counter.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&mut self) -> T {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a sampled element using the given generator, and updates the counter.
+    pub fn sample_with<R: RngCore + ?Sized>(&mut self, rng:&mut R) -> T {
+        // the rare element is drawn first, so that its probability can be raised without rebuilding any table
+        let p = self.rare_probability();
+        let sampled = match &self.others {
+            Some(others) if rng.gen::<f64>() >= p => others.sample_with(rng),
+            _ => self.rare,
+        };
+
+        if sampled == self.rare {
+            self.draws_since_rare = 0;
+        }
+        else {
+            self.draws_since_rare = self.draws_since_rare.saturating_add(1);
+        }
+        sampled
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hard_pity() {
+        let va = VoseAlias::new(vec!['a', 'b'], vec![1.0, 0.0]);
+        let mut sampler = PitySampler::new(va, 'b', 3).unwrap();
+        for _ in 0..4 {
+            assert_eq!(sampler.rare_probability(), 0.0);
+            assert_eq!(sampler.sample(), 'a');
+            assert_eq!(sampler.sample(), 'a');
+            assert_eq!(sampler.rare_probability(), 1.0);
+            assert_eq!(sampler.sample(), 'b');
+            assert_eq!(sampler.draws_since_rare(), 0);
+        }
+        assert!(PitySampler::new(VoseAlias::new(vec!['a'], vec![1.0]), 'z', 3).is_err());
+        assert!(PitySampler::new(VoseAlias::new(vec!['a'], vec![1.0]), 'a', 0).is_err());
+    }
+
+    #[test]
+    fn soft_pity() {
+        let va = VoseAlias::new(vec![1, 2], vec![0.9, 0.1]);
+        let mut sampler = PitySampler::new(va, 2, 10).unwrap().with_soft_pity(3, 0.25).unwrap();
+        assert!((sampler.rare_probability() - 0.1).abs() < 1e-6);
+        sampler.draws_since_rare = 2;
+        assert!((sampler.rare_probability() - 0.35).abs() < 1e-6);
+        sampler.draws_since_rare = 6;
+        assert_eq!(sampler.rare_probability(), 1.0);
+        assert_eq!(sampler.sample(), 2);
+        assert!(PitySampler::new(VoseAlias::new(vec![1], vec![1.0]), 1, 5).unwrap().with_soft_pity(2, -1.0).is_err());
+    }
+}
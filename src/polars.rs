@@ -0,0 +1,143 @@
+//! Interoperability with Polars: construction from series and weighted subsampling of data frames (feature `polars`).
+
+use polars::prelude::{DataFrame, DataType, IdxCa, IdxSize, Series};
+use rand_core::RngCore;
+
+use crate::{VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+impl<'a> VoseAlias<&'a str> {
+
+    /// Returns the Vose-Alias object sampling the strings of the `elements` series, each proportionally to the value of the `weights` series at the same position. The weights do not need to sum to 1.
+    ///
+    /// `elements` should be a string series, which is borrowed and not copied, and `weights` a numeric series.
+    /// An error is returned if one of the series has another data type or contains a null value, or in the same cases as `VoseAlias::from_counts()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use polars::prelude::*;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let elements = Series::new("color".into(), &["red", "green", "blue"]);
+    /// let weights = Series::new("weight".into(), &[2, 1, 1]);
+    /// let va = VoseAlias::from_series(&elements, &weights).unwrap();
+    /// assert_eq!(va.elements, vec!["red", "green", "blue"]);
+    /// ```
+    pub fn from_series(elements:&'a Series, weights:&Series) -> Result<VoseAlias<&'a str>, VoseAliasError> {
+        if elements.len() != weights.len() {
+            return Err(VoseAliasError::SizeMismatch { elements: elements.len(), probabilities: weights.len() });
+        }
+        let strings = match elements.str() {
+            Ok(strings) => strings,
+            Err(_) => return Err(VoseAliasError::InvalidColumn(format!("series {} should contain strings, not {}", elements.name(), elements.dtype()))),
+        };
+        let weight_vector = series_weights(weights)?;
+        if let Some(row) = strings.iter().position(|e| e.is_none()) {
+            return Err(VoseAliasError::NullValue { row });
+        }
+        VoseAlias::from_weights(strings.iter().flatten().collect(), weight_vector)
+    }
+}
+
+
+impl VoseAlias<usize> {
+
+    /// Returns `n` rows of `df`, sampled with replacement, each proportionally to its value in the numeric column `weights`, using the default generator of the crate.
+    ///
+    /// An error is returned if the column does not exist, is not numeric or contains a null value, or in the same cases as `VoseAlias::from_counts()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use polars::prelude::*;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let df = df!("user" => &["ann", "bob", "cid"], "activity" => &[10.0, 5.0, 1.0]).unwrap();
+    /// let subsample = VoseAlias::sample_dataframe(&df, "activity", 100).unwrap();
+    /// assert_eq!(subsample.height(), 100);
+    /// assert_eq!(subsample.get_column_names(), df.get_column_names());
+    /// ```
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_dataframe(df:&DataFrame, weights:&str, n:usize) -> Result<DataFrame, VoseAliasError> {
+        rng::with_default_rng(|rng| VoseAlias::sample_dataframe_with(df, weights, n, rng))
+    }
+
+
+    /// Returns `n` rows of `df`, sampled with replacement, each proportionally to its value in the numeric column `weights`, using the given generator.
+    ///
+    /// See `VoseAlias::sample_dataframe()` for more details.
+    pub fn sample_dataframe_with<R: RngCore + ?Sized>(df:&DataFrame, weights:&str, n:usize, rng:&mut R) -> Result<DataFrame, VoseAliasError> {
+        let column = match df.column(weights) {
+            Ok(column) => column,
+            Err(_) => return Err(VoseAliasError::InvalidColumn(format!("the data frame has no column {}", weights))),
+        };
+        let weight_vector = series_weights(column.as_materialized_series())?;
+        let va:VoseAlias<usize> = VoseAlias::from_weights((0..weight_vector.len()).collect(), weight_vector)?;
+
+        let rows:Vec<IdxSize> = (0..n).map(|_| {
+            let (die, coin) = va.roll_die_and_flip_coin(rng);
+            va.select_index(die, coin) as IdxSize
+        }).collect();
+        match df.take(&IdxCa::from_vec("rows".into(), rows)) {
+            Ok(sample) => Ok(sample),
+            Err(e) => panic!("Internal error. The sampled rows are out of the data frame ({}). If this happened, please fill in an issue report.", e),
+        }
+    }
+}
+
+
+/// Returns the values of a numeric series as weights.
+fn series_weights(weights:&Series) -> Result<Vec<f64>, VoseAliasError> {
+    if !weights.dtype().is_primitive_numeric() {
+        return Err(VoseAliasError::InvalidColumn(format!("series {} should contain numbers, not {}", weights.name(), weights.dtype())));
+    }
+    let values = match weights.cast(&DataType::Float64) {
+        Ok(values) => values,
+        Err(e) => return Err(VoseAliasError::InvalidColumn(format!("series {} cannot be read as weights ({})", weights.name(), e))),
+    };
+    let values = match values.f64() {
+        Ok(values) => values.clone(),
+        Err(e) => panic!("Internal error. A series cast to f64 does not contain f64 values ({}). If this happened, please fill in an issue report.", e),
+    };
+    values.iter().enumerate()
+        .map(|(row, w)| w.ok_or(VoseAliasError::NullValue { row }))
+        .collect()
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::NamedFrom;
+
+    #[test]
+    fn invalid_series() {
+        let elements = Series::new("e".into(), &["a", "b"]);
+        let numbers = Series::new("n".into(), &[1u32, 2]);
+        assert!(matches!(VoseAlias::from_series(&numbers, &numbers), Err(VoseAliasError::InvalidColumn(_))));
+        assert!(matches!(VoseAlias::from_series(&elements, &elements), Err(VoseAliasError::InvalidColumn(_))));
+
+        let missing = Series::new("w".into(), &[Some(1.0), None]);
+        assert_eq!(VoseAlias::from_series(&elements, &missing).err(), Some(VoseAliasError::NullValue { row: 1 }));
+        let va = VoseAlias::from_series(&elements, &numbers).unwrap();
+        assert_eq!(va.elements, vec!["a", "b"]);
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn dataframe_rows() {
+        use polars::df;
+
+        let df = df!("id" => &[0i64, 1, 2], "w" => &[0.0, 1.0, 1.0]).unwrap();
+        assert!(matches!(VoseAlias::sample_dataframe(&df, "missing", 1), Err(VoseAliasError::InvalidColumn(_))));
+        let sample = VoseAlias::sample_dataframe(&df, "w", 40).unwrap();
+        assert_eq!(sample.height(), 40);
+        let ids = sample.column("id").unwrap().i64().unwrap();
+        assert!(ids.iter().all(|id| matches!(id, Some(0..=2))));
+    }
+}
@@ -0,0 +1,168 @@
+//! A reaction sampler for stochastic simulation algorithms, such as Gillespie's direct method.
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{check_weight, check_weights, VoseAliasError};
+use crate::sum_tree::SumTree;
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A sampler of reaction indexes proportionally to their propensities, for exact stochastic simulation (SSA) loops.
+///
+/// After each event, only the propensities of the reactions depending on the fired one change: they are updated in O(log n) each, and the total propensity, which sets the rate of the next event, is available in O(1).
+/// The propensities are stored in a sum tree whose sums are recomputed, rather than incremented, on each update: the total does not drift over millions of updates.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::PropensitySampler;
+///
+/// // A + B -> C at rate 0.01, C -> A + B at rate 0.5
+/// let (mut a, mut b, mut c) = (100.0, 80.0, 0.0);
+/// let mut ssa = PropensitySampler::new(vec![0.01 * a * b, 0.5 * c]).unwrap();
+/// let mut time = 0.0;
+/// while time < 1.0 {
+///     let (dt, reaction) = match ssa.next_event() {
+///         Some(event) => event,
+///         None => break,
+///     };
+///     time += dt;
+///     if reaction == 0 { a -= 1.0; b -= 1.0; c += 1.0; } else { a += 1.0; b += 1.0; c -= 1.0; }
+///     ssa.set_propensity(0, 0.01 * a * b).unwrap();
+///     ssa.set_propensity(1, 0.5 * c).unwrap();
+/// }
+/// assert_eq!(a + c, 100.0);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PropensitySampler {
+    size:usize,
+    propensities:SumTree,
+}
+
+
+impl PropensitySampler {
+
+    /// Returns a sampler over the reactions `0..propensities.len()`, built in O(n).
+    ///
+    /// An error is returned if there is no reaction, or if a propensity is negative or not finite.
+    pub fn new(propensities:Vec<f64>) -> Result<PropensitySampler, VoseAliasError> {
+        // all the propensities can be 0
+        match check_weights(propensities.len(), &propensities) {
+            Ok(_) | Err(VoseAliasError::ZeroTotalWeight) => (),
+            Err(e) => return Err(e),
+        }
+
+        Ok(PropensitySampler { size: propensities.len(), propensities: SumTree::from_values(&propensities) })
+    }
+
+
+    /// Returns the number of reactions.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+
+    /// Returns `true` if there is no reaction, which never happens for a sampler built with `PropensitySampler::new()`.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+
+    /// Returns the propensity of a reaction, or `None` if there is no such reaction.
+    pub fn propensity(&self, reaction:usize) -> Option<f64> {
+        if reaction < self.size { Some(self.propensities.leaf(reaction)) } else { None }
+    }
+
+
+    /// Returns the sum of the propensities of all the reactions, which is the rate of the next event.
+    pub fn total(&self) -> f64 {
+        self.propensities.total()
+    }
+
+
+    /// Changes the propensity of a reaction, in O(log n), and returns its previous propensity.
+    ///
+    /// An error is returned if the propensity is negative or not finite. If there is no such reaction, `Ok(None)` is returned and nothing is changed.
+    pub fn set_propensity(&mut self, reaction:usize, propensity:f64) -> Result<Option<f64>, VoseAliasError> {
+        check_weight(propensity)?;
+        if reaction >= self.size {
+            return Ok(None);
+        }
+        Ok(Some(self.propensities.set(reaction, propensity)))
+    }
+
+
+    /// Returns a reaction sampled proportionally to the propensities, using the default generator of the crate, or `None` if all the propensities are 0.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> Option<usize> {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a reaction sampled proportionally to the propensities, using the given generator, or `None` if all the propensities are 0.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<usize> {
+        let total = self.total();
+        if total <= 0.0 {
+            return None;
+        }
+        Some(self.propensities.find(rng.gen::<f64>() * total))
+    }
+
+
+    /// Returns the time until the next event and the reaction firing then, using the default generator of the crate, or `None` if all the propensities are 0 (no reaction can fire anymore).
+    #[cfg(feature = "thread-rng")]
+    pub fn next_event(&self) -> Option<(f64, usize)> {
+        rng::with_default_rng(|rng| self.next_event_with(rng))
+    }
+
+
+    /// Returns the time until the next event and the reaction firing then, using the given generator, as in one step of Gillespie's direct method.
+    ///
+    /// The time is drawn from the exponential distribution of rate `total()`. `None` is returned if all the propensities are 0.
+    pub fn next_event_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<(f64, usize)> {
+        let reaction = self.sample_with(rng)?;
+        // 1 - u is in (0, 1], so its logarithm is finite
+        let u:f64 = 1.0 - rng.gen::<f64>();
+        Some((-u.ln() / self.total(), reaction))
+    }
+}
+
+
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates() {
+        let mut ssa = PropensitySampler::new(vec![1.0, 0.0, 3.0]).unwrap();
+        assert_eq!(ssa.total(), 4.0);
+        assert_eq!(ssa.set_propensity(2, 0.0), Ok(Some(3.0)));
+        assert_eq!(ssa.set_propensity(5, 1.0), Ok(None));
+        assert!(ssa.set_propensity(0, f64::NAN).is_err());
+        assert_eq!(ssa.total(), 1.0);
+        for _ in 0..50 {
+            assert_eq!(ssa.sample(), Some(0));
+        }
+        ssa.set_propensity(0, 0.0).unwrap();
+        assert_eq!(ssa.next_event(), None);
+        assert!(PropensitySampler::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn waiting_times() {
+        let ssa = PropensitySampler::new(vec![2.0, 2.0]).unwrap();
+        let n = 20_000;
+        let mean:f64 = (0..n).map(|_| ssa.next_event().unwrap().0).fold(0.0, |s, dt| s + dt) / n as f64;
+        // the expected waiting time is 1 / 4
+        assert!((mean - 0.25).abs() < 0.02);
+    }
+}
@@ -0,0 +1,228 @@
+//! A Vose-Alias table storing its probabilities as fixed-point integer thresholds.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// An unsigned integer type used to store the fixed-point probabilities of a `QuantizedVoseAlias`.
+///
+/// A probability `p` is stored as the threshold `round(p * 2^BITS)`, and the element of a column is kept when a uniformly drawn integer of the same type is strictly lower than the threshold. The probability is therefore known with a precision of `2^-BITS`:
+/// - `u16`: precision of about 1.5e-5
+/// - `u32`: precision of about 2.3e-10
+///
+/// This trait is sealed: it cannot be implemented outside of this crate.
+pub trait FixedPoint: Copy + Ord + Debug + private::Sealed where Standard: Distribution<Self> {
+    /// Converts a probability in [0, 1] to a threshold, saturating at the maximum value of the type.
+    fn from_probability(p:f32) -> Self;
+
+    /// Converts a threshold back to the probability it represents.
+    fn to_probability(self) -> f64;
+}
+
+
+macro_rules! impl_fixed_point {
+    ($($t:ty),*) => {
+        $(
+            impl private::Sealed for $t {}
+
+            impl FixedPoint for $t {
+                fn from_probability(p:f32) -> Self {
+                    let threshold = (f64::from(p) * (<$t>::MAX as f64 + 1.0)).round();
+                    if threshold <= 0.0 {
+                        0
+                    } else if threshold >= <$t>::MAX as f64 {
+                        <$t>::MAX
+                    } else {
+                        threshold as $t
+                    }
+                }
+
+                fn to_probability(self) -> f64 {
+                    self as f64 / (<$t>::MAX as f64 + 1.0)
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_point!(u16, u32);
+
+
+mod private {
+    pub trait Sealed {}
+}
+
+
+/// A Vose-Alias table storing the probability of each column as a fixed-point threshold of type `Q` instead of an `f32`.
+///
+/// The sampling only performs integer comparisons against integer random draws, which removes floating point computations from the hot path and makes the sampled sequence exactly reproducible across platforms for a given seeded generator. With `Q = u16`, the probability table also takes half the memory of the `f32` one.
+///
+/// Columns without alias are stored with their own index as alias, so the saturation of the threshold of a probability of 1 does not change the sampled distribution.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::{QuantizedVoseAlias, VoseAlias};
+///
+/// let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+/// let qva:QuantizedVoseAlias<i32, u16> = va.quantize();
+/// let element = qva.sample();
+/// assert!(va.elements.contains(&element));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizedVoseAlias<T, Q = u32, I = u32> where T: Copy + Hash + Eq + Debug, Q: FixedPoint, I: SlotIndex, Standard: Distribution<Q> {
+    elements:Vec<T>,
+    thresholds:Vec<Q>,
+    alias:Vec<I>,
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns a copy of this table, with its probabilities stored as fixed-point thresholds of type `Q`.
+    ///
+    /// See `QuantizedVoseAlias` for more details.
+    pub fn quantize<Q>(&self) -> QuantizedVoseAlias<T, Q, I>
+    where Q: FixedPoint, Standard: Distribution<Q> {
+        QuantizedVoseAlias {
+            elements: self.elements.clone(),
+            thresholds: self.slot_prob.iter().map(|p| Q::from_probability(*p)).collect(),
+            alias: self.slot_alias.clone(),
+        }
+    }
+}
+
+
+impl<T, Q, I> QuantizedVoseAlias<T, Q, I>
+where T: Copy + Hash + Eq + Debug, Q: FixedPoint, I: SlotIndex, Standard: Distribution<Q> {
+
+    /// Builds the quantized table of the given distribution.
+    ///
+    /// Returns the same errors as `VoseAlias::try_new_with_index()`.
+    pub fn try_new(element_vector:Vec<T>, probability_vector:Vec<f32>) -> Result<QuantizedVoseAlias<T, Q, I>, VoseAliasError> {
+        Ok(VoseAlias::<T, I>::try_new_with_index(element_vector, probability_vector)?.quantize())
+    }
+
+
+    /// Returns the elements sampled from.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the fixed-point thresholds of the columns, in the order of `elements()`.
+    pub fn thresholds(&self) -> &[Q] {
+        &self.thresholds
+    }
+
+
+    /// Returns a sampled element, using the default generator of the crate (see `VoseAlias::sample()`).
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> T {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a sampled element, using the given random number generator.
+    ///
+    /// The column is drawn as a `u64`, independently of the size of `usize`, so that a given seeded generator produces the same sequence on every platform.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> T {
+        self.elements[self.sample_index_with(rng)]
+    }
+
+
+    /// Returns the index in `elements()` of a sampled element, using the given random number generator.
+    pub(crate) fn sample_index_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> usize {
+        let die = rng.gen_range(0, self.elements.len() as u64) as usize;
+        let coin:Q = rng.gen();
+        self.select_index(die, coin)
+    }
+
+
+    /// Returns the probability of sampling the element at index `i` in `elements()`, as represented by the fixed-point tables.
+    pub(crate) fn table_probability(&self, i:usize) -> f64 {
+        let mut mass = 0.0;
+        for (j, threshold) in self.thresholds.iter().enumerate() {
+            let kept = if self.alias[j].to_usize() == j { 1.0 } else { threshold.to_probability() };
+            if j == i {
+                mass += kept;
+            }
+            else if self.alias[j].to_usize() == i {
+                mass += 1.0 - kept;
+            }
+        }
+        mass / self.elements.len() as f64
+    }
+
+
+    /// This function selects an element given a die (a column) and an integer coin. It has been separated from `sample_with` to allow unit testing.
+    #[cfg(test)]
+    fn select_element(&self, die:usize, coin:Q) -> T {
+        self.elements[self.select_index(die, coin)]
+    }
+
+
+    /// This function works like `select_element`, but returns the index of the selected element.
+    fn select_index(&self, die:usize, coin:Q) -> usize {
+        if coin < self.thresholds[die] {
+            die
+        }
+        else {
+            self.alias[die].to_usize()
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn thresholds() {
+        assert_eq!(u16::from_probability(0.0), 0);
+        assert_eq!(u16::from_probability(0.5), 32768);
+        assert_eq!(u16::from_probability(1.0), u16::MAX);
+        assert_eq!(u32::from_probability(0.25), 1 << 30);
+        assert_eq!(u32::from_probability(-0.0001), 0);
+    }
+
+    #[test]
+    fn select_element() {
+        let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+        let qva:QuantizedVoseAlias<&str, u16> = va.quantize();
+        // column orange / alias yellow, kept with probability 0.875
+        assert_eq!(qva.select_element(0, 0), "orange");
+        assert_eq!(qva.select_element(0, 57343), "orange");
+        assert_eq!(qva.select_element(0, 57344), "yellow");
+        // column yellow / no alias
+        assert_eq!(qva.select_element(1, u16::MAX), "yellow");
+    }
+
+    #[test]
+    fn reproducible() {
+        let qva:QuantizedVoseAlias<u32> = QuantizedVoseAlias::try_new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]).unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        let first:Vec<u32> = (0..100).map(|_| qva.sample_with(&mut rng)).collect();
+        let mut rng = StdRng::seed_from_u64(42);
+        let second:Vec<u32> = (0..100).map(|_| qva.sample_with(&mut rng)).collect();
+        assert_eq!(first, second);
+        assert!(first.iter().all(|e| qva.elements().contains(e)));
+    }
+}
@@ -0,0 +1,261 @@
+//! A prioritized experience replay buffer, sampling stored items proportionally to their priority.
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{check_weight, VoseAliasError};
+use crate::sum_tree::SumTree;
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A bounded buffer of items sampled proportionally to their priority, as used by prioritized experience replay in reinforcement learning.
+///
+/// The buffer holds at most `capacity` items: once full, each new item replaces the oldest one. An item of priority p is sampled with a probability proportional to p^α, where α is chosen at construction (α = 0 samples uniformly).
+/// New items get the highest priority given so far, so that they are sampled at least once before their priority is known.
+///
+/// The priorities are stored in a sum tree, so pushing an item, updating a priority and drawing a sample all take O(log n). Batches are drawn with stratified sampling: the total priority is split into as many segments as the batch size, and one item is drawn in each segment.
+///
+/// Each sampled item comes with its importance-sampling weight (N × P(i))^-β, normalized by the largest possible weight so that all weights are at most 1.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::ReplayBuffer;
+///
+/// let mut buffer = ReplayBuffer::new(1000, 0.6).unwrap();
+/// for transition in 0..100 {
+///     buffer.push(transition);
+/// }
+///
+/// let batch = buffer.sample_batch(32, 0.4);
+/// assert_eq!(batch.len(), 32);
+/// for sample in &batch {
+///     assert!(sample.weight > 0.0 && sample.weight <= 1.0);
+/// }
+///
+/// // after a training step, the priorities are set to the new TD errors
+/// let updates:Vec<(usize, f64)> = batch.iter().map(|sample| (sample.index, 0.5)).collect();
+/// for (index, td_error) in updates {
+///     buffer.update_priority(index, td_error).unwrap();
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplayBuffer<T> {
+    capacity:usize,
+    alpha:f64,
+    items:Vec<T>,
+    priorities:Vec<f64>,
+    // the position of the next item to push, which is the oldest item once the buffer is full
+    next:usize,
+    max_priority:f64,
+    // the sums of the priorities raised to α, and a binary tree with the same layout (leaf i is the node leaves + i) holding the minimums of the positive ones
+    sums:SumTree,
+    minimums:Vec<f64>,
+}
+
+
+/// An item sampled from a `ReplayBuffer`, with its position in the buffer and its importance-sampling weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplaySample<'a, T> {
+    /// The position of the item in the buffer, to use with `ReplayBuffer::update_priority()`.
+    pub index:usize,
+    /// The sampled item.
+    pub item:&'a T,
+    /// The normalized importance-sampling weight of the item.
+    pub weight:f64,
+}
+
+
+impl<T> ReplayBuffer<T> {
+
+    /// Returns an empty buffer holding at most `capacity` items, with the priority exponent `alpha`.
+    ///
+    /// An error is returned if `capacity` is 0 (`VoseAliasError::EmptyDistribution`), or if `alpha` is negative or not finite (`VoseAliasError::InvalidParameter`).
+    pub fn new(capacity:usize, alpha:f64) -> Result<ReplayBuffer<T>, VoseAliasError> {
+        if capacity == 0 {
+            return Err(VoseAliasError::EmptyDistribution);
+        }
+        if !alpha.is_finite() || alpha < 0.0 {
+            return Err(VoseAliasError::InvalidParameter { name: "alpha", value: alpha });
+        }
+
+        let leaves = capacity.next_power_of_two();
+        Ok(ReplayBuffer {
+            capacity,
+            alpha,
+            items: Vec::new(),
+            priorities: Vec::new(),
+            next: 0,
+            max_priority: 1.0,
+            sums: SumTree::new(capacity),
+            minimums: vec![f64::INFINITY; 2 * leaves],
+        })
+    }
+
+
+    /// Returns the maximum number of items of the buffer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+
+    /// Returns the number of items in the buffer.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+
+    /// Returns `true` if the buffer does not contain any item.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+
+    /// Returns the item at `index`, or `None` if there is no item at this position.
+    pub fn get(&self, index:usize) -> Option<&T> {
+        self.items.get(index)
+    }
+
+
+    /// Returns the priority of the item at `index`, or `None` if there is no item at this position.
+    pub fn priority(&self, index:usize) -> Option<f64> {
+        self.priorities.get(index).copied()
+    }
+
+
+    /// Adds an item with the highest priority given so far, replacing the oldest item if the buffer is full, in O(log n). Returns the position of the item.
+    pub fn push(&mut self, item:T) -> usize {
+        let index = self.next;
+        if index < self.items.len() {
+            self.items[index] = item;
+            self.priorities[index] = self.max_priority;
+        }
+        else {
+            self.items.push(item);
+            self.priorities.push(self.max_priority);
+        }
+        self.next = (index + 1) % self.capacity;
+        self.set_leaf(index, self.max_priority.powf(self.alpha));
+        index
+    }
+
+
+    /// Changes the priority of the item at `index`, in O(log n), and returns its previous priority.
+    ///
+    /// An error is returned if the priority is negative or not finite. If there is no item at this position, `Ok(None)` is returned and nothing is changed.
+    pub fn update_priority(&mut self, index:usize, priority:f64) -> Result<Option<f64>, VoseAliasError> {
+        check_weight(priority)?;
+        if index >= self.items.len() {
+            return Ok(None);
+        }
+
+        let previous = self.priorities[index];
+        self.priorities[index] = priority;
+        self.max_priority = self.max_priority.max(priority);
+        self.set_leaf(index, priority.powf(self.alpha));
+        Ok(Some(previous))
+    }
+
+
+    /// Returns `batch_size` items sampled proportionally to their priority, with replacement, using the default generator of the crate.
+    ///
+    /// See `ReplayBuffer::sample_batch_with()`.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_batch(&self, batch_size:usize, beta:f64) -> Vec<ReplaySample<'_, T>> {
+        rng::with_default_rng(|rng| self.sample_batch_with(batch_size, beta, rng))
+    }
+
+
+    /// Returns `batch_size` items sampled proportionally to their priority, with replacement, using the given generator.
+    ///
+    /// `beta` is the exponent of the importance-sampling weights: 0 gives weights of 1, and 1 fully compensates for the non-uniform sampling. The batch is empty if the buffer is empty or if all the priorities are 0.
+    pub fn sample_batch_with<R: RngCore + ?Sized>(&self, batch_size:usize, beta:f64, rng:&mut R) -> Vec<ReplaySample<'_, T>> {
+        let total = self.sums.total();
+        if total <= 0.0 {
+            return Vec::new();
+        }
+
+        let len = self.items.len() as f64;
+        let max_weight = (len * self.minimums[1] / total).powf(-beta);
+        let segment = total / batch_size as f64;
+        (0..batch_size).map(|k| {
+            let target = segment * (k as f64 + rng.gen::<f64>());
+            let index = self.sums.find(target);
+            let p = self.sums.leaf(index) / total;
+            ReplaySample {
+                index,
+                item: &self.items[index],
+                weight: (len * p).powf(-beta) / max_weight,
+            }
+        }).collect()
+    }
+
+
+    /// Sets the value of a leaf of the trees, and updates its ancestors.
+    fn set_leaf(&mut self, index:usize, value:f64) {
+        self.sums.set(index, value);
+        let mut node = self.minimums.len() / 2 + index;
+        self.minimums[node] = if value > 0.0 { value } else { f64::INFINITY };
+        while node > 1 {
+            node /= 2;
+            self.minimums[node] = self.minimums[2 * node].min(self.minimums[2 * node + 1]);
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn ring_buffer() {
+        let mut buffer = ReplayBuffer::new(3, 1.0).unwrap();
+        assert!(buffer.sample_batch(4, 0.5).is_empty());
+        for i in 0..5 {
+            buffer.push(i);
+        }
+        assert_eq!(buffer.len(), 3);
+        assert_eq!((buffer.get(0), buffer.get(1), buffer.get(2)), (Some(&3), Some(&4), Some(&2)));
+        assert_eq!(buffer.update_priority(3, 1.0), Ok(None));
+        assert!(buffer.update_priority(0, -1.0).is_err());
+        assert!(ReplayBuffer::<u32>::new(0, 1.0).is_err());
+        assert!(matches!(ReplayBuffer::<u32>::new(4, -1.0), Err(VoseAliasError::InvalidParameter { name: "alpha", .. })));
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn proportional_sampling() {
+        let mut buffer = ReplayBuffer::new(4, 1.0).unwrap();
+        for (i, priority) in [1.0, 3.0, 0.0, 4.0].iter().enumerate() {
+            buffer.push(i);
+            buffer.update_priority(i, *priority).unwrap();
+        }
+        let batch = buffer.sample_batch(8000, 1.0);
+        let count = |item:usize| batch.iter().filter(|s| *s.item == item).count();
+        assert_eq!(count(2), 0);
+        assert!(count(0) > 900 && count(0) < 1100);
+        assert!(count(1) > 2800 && count(1) < 3200);
+        for sample in &batch {
+            // the rarest item has weight 1, the others have a weight inversely proportional to their priority
+            let priority = buffer.priority(sample.index).unwrap();
+            assert!((sample.weight - 1.0 / priority).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn new_items_get_max_priority() {
+        let mut buffer = ReplayBuffer::new(2, 0.5).unwrap();
+        buffer.push('a');
+        buffer.update_priority(0, 9.0).unwrap();
+        buffer.push('b');
+        assert_eq!(buffer.priority(1), Some(9.0));
+    }
+}
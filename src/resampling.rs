@@ -0,0 +1,185 @@
+//! Resampling schemes of particle filters.
+//!
+//! Each function draws `n` particle indexes from a vector of weights, which do not need to sum to 1: a particle of weight w is selected w / total × `n` times on average.
+//! The schemes differ in their variance: `systematic()` and `stratified()` spread the draws evenly over the cumulative weights, `residual()` copies the deterministic part of the expected counts and only draws the rest, while `multinomial()` draws all the indexes independently from an alias table.
+//! The particles of weight 0 are never selected.
+//!
+//! An error is returned if the weight vector is empty, if a weight is negative or not finite, or if all the weights are 0.
+//!
+//! # Examples
+//! ```
+//! # #[cfg(feature = "thread-rng")] {
+//! use vose_alias::resampling;
+//!
+//! let weights = vec![0.1, 0.6, 0.0, 0.3];
+//! let mut rng = rand::thread_rng();
+//! let indexes = resampling::systematic(&weights, 10, &mut rng).unwrap();
+//! let counts = resampling::counts(&indexes, weights.len());
+//! assert_eq!(counts[1], 6);
+//! assert_eq!(counts[2], 0);
+//! # }
+//! ```
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{check_weights, VoseAlias, VoseAliasError};
+
+
+/// Returns `n` indexes drawn independently, each proportionally to its weight, using an alias table.
+///
+/// The indexes are in the order of the draws.
+pub fn multinomial<R: RngCore + ?Sized>(weights:&[f64], n:usize, rng:&mut R) -> Result<Vec<usize>, VoseAliasError> {
+    check_weights(weights.len(), weights)?;
+    Ok(draw_independently(weights, n, rng))
+}
+
+
+/// Returns `n` indexes selected by systematic resampling: a single uniform offset u in [0, 1/`n`) selects the particles at the cumulative weights u, u + 1/`n`, u + 2/`n`, and so on.
+///
+/// The indexes are in increasing order.
+pub fn systematic<R: RngCore + ?Sized>(weights:&[f64], n:usize, rng:&mut R) -> Result<Vec<usize>, VoseAliasError> {
+    let cumulative = cumulative_weights(weights)?;
+    let offset:f64 = rng.gen();
+    Ok(select_sorted(&cumulative, (0..n).map(|i| (i as f64 + offset) / n as f64)))
+}
+
+
+/// Returns `n` indexes selected by stratified resampling: the cumulative weights are split into `n` strata of size 1/`n`, and one uniform position is drawn in each stratum.
+///
+/// The indexes are in increasing order.
+pub fn stratified<R: RngCore + ?Sized>(weights:&[f64], n:usize, rng:&mut R) -> Result<Vec<usize>, VoseAliasError> {
+    let cumulative = cumulative_weights(weights)?;
+    let positions:Vec<f64> = (0..n).map(|i| (i as f64 + rng.gen::<f64>()) / n as f64).collect();
+    Ok(select_sorted(&cumulative, positions.into_iter()))
+}
+
+
+/// Returns `n` indexes selected by residual resampling: each particle is first copied ⌊`n` × w / total⌋ times, and the remaining indexes are drawn independently, proportionally to the fractional parts of the expected counts.
+///
+/// The copied indexes come first, in increasing order, followed by the drawn ones.
+pub fn residual<R: RngCore + ?Sized>(weights:&[f64], n:usize, rng:&mut R) -> Result<Vec<usize>, VoseAliasError> {
+    let total = check_weights(weights.len(), weights)?;
+    let expected:Vec<f64> = weights.iter().map(|w| w / total * n as f64).collect();
+
+    let mut indexes:Vec<usize> = Vec::with_capacity(n);
+    for (i, e) in expected.iter().enumerate() {
+        let copies = (e.floor() as usize).min(n - indexes.len());
+        indexes.extend(std::iter::repeat_n(i, copies));
+    }
+    let remaining = n - indexes.len();
+    if remaining > 0 {
+        let residuals:Vec<f64> = expected.iter().map(|e| e - e.floor()).collect();
+        // rounding errors can leave a few draws without any fractional part to draw them from
+        let residuals = if residuals.iter().any(|r| *r > 0.0) { residuals } else { weights.to_vec() };
+        indexes.extend(draw_independently(&residuals, remaining, rng));
+    }
+    Ok(indexes)
+}
+
+
+/// Returns the number of times each particle of `0..len` appears in `indexes`, as returned by the resampling functions.
+///
+/// # Panics
+/// This function panics if an index is not lower than `len`. See `try_counts()` for a non-panicking version.
+pub fn counts(indexes:&[usize], len:usize) -> Vec<usize> {
+    match try_counts(indexes, len) {
+        Ok(counts) => counts,
+        Err(_) => panic!("An index is out of the {} particles", len),
+    }
+}
+
+
+/// Returns the number of times each particle of `0..len` appears in `indexes`, like `counts()`.
+///
+/// # Errors
+/// An error (`VoseAliasError::InvalidParameter`) is returned with the first index that is not lower than `len`.
+pub fn try_counts(indexes:&[usize], len:usize) -> Result<Vec<usize>, VoseAliasError> {
+    let mut counts = vec![0; len];
+    for i in indexes {
+        match counts.get_mut(*i) {
+            Some(count) => *count += 1,
+            None => return Err(VoseAliasError::InvalidParameter { name: "index", value: *i as f64 }),
+        }
+    }
+    Ok(counts)
+}
+
+
+/// Returns the normalized cumulative weights, the last one being exactly 1.
+fn cumulative_weights(weights:&[f64]) -> Result<Vec<f64>, VoseAliasError> {
+    let total = check_weights(weights.len(), weights)?;
+    let mut sum = 0.0;
+    let mut cumulative:Vec<f64> = weights.iter().map(|w| { sum += w; sum / total }).collect();
+    // rounding errors can leave the last cumulative weight slightly below 1, after which no position would be selected
+    if let Some(last) = cumulative.last_mut() {
+        *last = 1.0;
+    }
+    Ok(cumulative)
+}
+
+
+/// Returns the index of the cumulative weight interval of each position, the positions being in increasing order and in [0, 1).
+fn select_sorted<P: Iterator<Item = f64>>(cumulative:&[f64], positions:P) -> Vec<usize> {
+    let mut indexes:Vec<usize> = Vec::new();
+    let mut j = 0;
+    for u in positions {
+        // the interval of a particle of weight 0 is empty, so it is always skipped
+        while j + 1 < cumulative.len() && u >= cumulative[j] {
+            j += 1;
+        }
+        indexes.push(j);
+    }
+    indexes
+}
+
+
+/// Draws `n` indexes independently from valid weights, using an alias table over the positive weights.
+fn draw_independently<R: RngCore + ?Sized>(weights:&[f64], n:usize, rng:&mut R) -> Vec<usize> {
+    let particles:Vec<usize> = (0..weights.len()).collect();
+    let va:VoseAlias<usize> = match VoseAlias::from_positive_weights(&particles, weights) {
+        Some(va) => va,
+        None => panic!("Internal error. The resampling weights do not contain any positive weight. If this happened, please fill in an issue report."),
+    };
+    (0..n).map(|_| va.sample_with(rng)).collect()
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_variance_schemes() {
+        let weights = vec![0.25, 0.0, 0.5, 0.25];
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            assert_eq!(counts(&systematic(&weights, 8, &mut rng).unwrap(), 4), vec![2, 0, 4, 2]);
+            assert_eq!(counts(&residual(&weights, 8, &mut rng).unwrap(), 4), vec![2, 0, 4, 2]);
+            let strata = stratified(&weights, 8, &mut rng).unwrap();
+            assert!(strata.windows(2).all(|w| w[0] <= w[1]));
+            assert!(!strata.contains(&1));
+        }
+    }
+
+    #[test]
+    fn residual_draws() {
+        let weights = vec![1.0, 1.0, 1.0];
+        let mut rng = rand::thread_rng();
+        let indexes = residual(&weights, 4, &mut rng).unwrap();
+        assert_eq!(&indexes[0..3], &[0, 1, 2]);
+        assert_eq!(indexes.len(), 4);
+        let indexes = multinomial(&[0.0, 2.0], 50, &mut rng).unwrap();
+        assert!(indexes.iter().all(|i| *i == 1));
+
+        assert_eq!(multinomial(&[], 1, &mut rng).err(), Some(VoseAliasError::EmptyDistribution));
+        assert_eq!(systematic(&[0.0], 1, &mut rng).err(), Some(VoseAliasError::ZeroTotalWeight));
+        assert_eq!(stratified(&[-1.0], 1, &mut rng).err(), Some(VoseAliasError::InvalidWeight(-1.0)));
+        assert_eq!(try_counts(&[0, 2, 2], 3), Ok(vec![1, 0, 2]));
+        assert_eq!(try_counts(&[0, 3], 3), Err(VoseAliasError::InvalidParameter { name: "index", value: 3.0 }));
+    }
+}
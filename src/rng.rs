@@ -0,0 +1,69 @@
+//! Random number generator used by the built-in sampling functions (`VoseAlias::sample()`, `VoseAlias::sample_n()`, ...).
+//!
+//! By default, the thread-local generator of `rand` (`rand::thread_rng()`) is used. It is cryptographically secure, but slower than non-cryptographic generators.
+//! For users who care more about raw throughput than about the quality of the randomness, two features replace it by a fast, thread-local, generator seeded from the operating system:
+//! 1. `small-rng`: uses `rand::rngs::SmallRng`
+//! 2. `xoshiro`: uses xoshiro256++ from the `rand_xoshiro` crate
+//!
+//! If both features are enabled, xoshiro256++ is used.
+//!
+//! The functions taking a generator as parameter (such as `VoseAlias::sample_with()`) are not affected by these features.
+//!
+//! This module is only compiled with the `thread-rng` feature, enabled by default. The functions using the default generator are not available without it.
+
+#[cfg(any(feature = "small-rng", feature = "xoshiro"))]
+use std::cell::RefCell;
+#[cfg(any(feature = "small-rng", feature = "xoshiro"))]
+use rand::SeedableRng;
+
+
+#[cfg(feature = "xoshiro")]
+pub(crate) type DefaultRng = rand_xoshiro::Xoshiro256PlusPlus;
+
+#[cfg(all(feature = "small-rng", not(feature = "xoshiro")))]
+pub(crate) type DefaultRng = rand::rngs::SmallRng;
+
+#[cfg(not(any(feature = "small-rng", feature = "xoshiro")))]
+pub(crate) type DefaultRng = rand::rngs::ThreadRng;
+
+
+#[cfg(any(feature = "small-rng", feature = "xoshiro"))]
+thread_local! {
+    static DEFAULT_RNG: RefCell<DefaultRng> = RefCell::new(DefaultRng::from_entropy());
+}
+
+
+/// Calls `f` with the default generator of the current thread.
+///
+/// The thread-local generator stays borrowed while `f` runs. A nested call from `f` (such as an observer or a callback sampling from within a sample) gets a new generator seeded from the operating system instead, rather than failing to borrow it.
+#[cfg(any(feature = "small-rng", feature = "xoshiro"))]
+pub(crate) fn with_default_rng<O, F: FnOnce(&mut DefaultRng) -> O>(f:F) -> O {
+    DEFAULT_RNG.with(|rng| match rng.try_borrow_mut() {
+        Ok(mut rng) => f(&mut rng),
+        Err(_) => f(&mut DefaultRng::from_entropy()),
+    })
+}
+
+
+/// Calls `f` with the default generator of the current thread.
+#[cfg(not(any(feature = "small-rng", feature = "xoshiro")))]
+pub(crate) fn with_default_rng<O, F: FnOnce(&mut DefaultRng) -> O>(f:F) -> O {
+    f(&mut rand::thread_rng())
+}
+
+
+/// Returns a new generator of the default type, seeded from the thread-local generator of `rand`, for the code that needs to own its generator (such as the workers of a parallel iterator).
+#[cfg(all(feature = "rayon", any(feature = "small-rng", feature = "xoshiro")))]
+pub(crate) fn new_default_rng() -> DefaultRng {
+    match DefaultRng::from_rng(rand::thread_rng()) {
+        Ok(rng) => rng,
+        Err(e) => panic!("Internal error. The generator could not be seeded: {}. If this happened, please fill in an issue report.", e),
+    }
+}
+
+
+/// Returns a handle to the thread-local generator of `rand`, for the code that needs to own its generator (such as the workers of a parallel iterator).
+#[cfg(all(feature = "rayon", not(any(feature = "small-rng", feature = "xoshiro"))))]
+pub(crate) fn new_default_rng() -> DefaultRng {
+    rand::thread_rng()
+}
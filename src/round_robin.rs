@@ -0,0 +1,129 @@
+//! A deterministic schedule interleaving the elements proportionally to their weights (smooth weighted round-robin).
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{check_weights, SlotIndex, VoseAlias, VoseAliasError};
+
+
+/// An infinite iterator over the elements, following the smooth weighted round-robin of nginx: each element appears a number of times proportional to its weight, and its occurrences are spread as evenly as possible instead of being grouped.
+///
+/// At each step, every element gains its weight, the element with the highest current value is returned (the first one in case of a tie), and the total weight is taken from its value. The schedule does not use any randomness.
+/// With integer weights (such as the request shares of backends), the computation is exact: the schedule is periodic, and each period of W steps, W being the sum of the weights, returns each element exactly as many times as its weight. With fractional weights, the proportions are only approached, within the rounding errors of the floating-point sums, and the schedule may not be periodic.
+/// Elements with a weight of 0 are never returned.
+///
+/// # Examples
+/// ```
+/// use vose_alias::RoundRobin;
+///
+/// let backends = RoundRobin::new(vec!["a", "b", "c"], vec![5.0, 1.0, 1.0]).unwrap();
+/// let schedule:Vec<&str> = backends.take(7).collect();
+/// assert_eq!(schedule, vec!["a", "a", "b", "a", "c", "a", "a"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RoundRobin<T> where T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    weights:Vec<f64>,
+    current:Vec<f64>,
+    total:f64,
+}
+
+
+impl<T> RoundRobin<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns a schedule over the given elements and weights, which do not need to sum to 1.
+    ///
+    /// An error is returned if both vectors do not have the same size, if there is no element, if a weight is negative or not finite, or if all the weights are 0.
+    pub fn new(element_vector:Vec<T>, weight_vector:Vec<f64>) -> Result<RoundRobin<T>, VoseAliasError> {
+        let total = check_weights(element_vector.len(), &weight_vector)?;
+
+        // the elements of weight 0 are never returned, so they are left out of the schedule
+        let (elements, weights):(Vec<T>, Vec<f64>) = element_vector.into_iter().zip(weight_vector).filter(|(_, w)| *w > 0.0).unzip();
+        Ok(RoundRobin {
+            current: vec![0.0; elements.len()],
+            elements,
+            weights,
+            total,
+        })
+    }
+
+
+    /// Restarts the schedule from its first element.
+    pub fn reset(&mut self) {
+        self.current.iter_mut().for_each(|c| *c = 0.0);
+    }
+}
+
+
+impl<T> Iterator for RoundRobin<T>
+where T: Copy + Hash + Eq + Debug {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let mut selected = 0;
+        for i in 0..self.current.len() {
+            self.current[i] += self.weights[i];
+            if self.current[i] > self.current[selected] {
+                selected = i;
+            }
+        }
+        self.current[selected] -= self.total;
+        Some(self.elements[selected])
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns a deterministic schedule of the elements, in which each element appears proportionally to its probability, using the smooth weighted round-robin.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["primary", "replica"], vec![0.75, 0.25]);
+    /// let schedule:Vec<&str> = va.round_robin().take(4).collect();
+    /// assert_eq!(schedule, vec!["primary", "primary", "replica", "primary"]);
+    /// ```
+    ///
+    /// # Panics
+    /// This method panics if all the probabilities of the distribution are 0, which cannot happen for a distribution built by this crate.
+    pub fn round_robin(&self) -> RoundRobin<T> {
+        match RoundRobin::new(self.elements.clone(), self.probabilities.clone()) {
+            Ok(schedule) => schedule,
+            Err(e) => panic!("Internal error. The probabilities of the distribution were rejected: {}. If this happened, please fill in an issue report.", e),
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proportional_schedule() {
+        let mut schedule = RoundRobin::new(vec!['a', 'b', 'c', 'd'], vec![3.0, 2.0, 0.0, 1.0]).unwrap();
+        let first:Vec<char> = schedule.by_ref().take(6).collect();
+        assert_eq!(first, vec!['a', 'b', 'a', 'd', 'b', 'a']);
+        // the schedule is periodic, of period 3 + 2 + 1
+        assert_eq!(schedule.by_ref().take(6).collect::<Vec<char>>(), first);
+        schedule.next();
+        schedule.reset();
+        assert_eq!(schedule.take(6).collect::<Vec<char>>(), first);
+
+        let fractional = RoundRobin::new(vec![1, 2], vec![0.1, 0.2]).unwrap();
+        let ones = fractional.take(3000).filter(|e| *e == 1).count();
+        assert!((999..=1001).contains(&ones));
+
+        assert!(RoundRobin::new(vec![1], vec![0.0]).is_err());
+        assert!(RoundRobin::new(vec![1], vec![-1.0]).is_err());
+        assert!(RoundRobin::new(vec![1, 2], vec![1.0]).is_err());
+    }
+}
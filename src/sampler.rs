@@ -0,0 +1,148 @@
+//! A common interface to the weighted samplers of this crate.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::distributions::{Distribution, Standard};
+use rand_core::RngCore;
+
+use crate::{DynamicSampler, FixedPoint, QuantizedVoseAlias, SlotIndex, VoseAlias};
+
+
+/// A sampler drawing elements of type `T` according to a discrete probability distribution.
+///
+/// This trait is implemented by `VoseAlias`, `QuantizedVoseAlias` and `DynamicSampler`, so that code written against it can switch from one implementation to another without changes.
+///
+/// # Examples
+/// ```
+/// use vose_alias::{DynamicSampler, VoseAlias, WeightedSampler};
+///
+/// fn most_likely<S: WeightedSampler<&'static str>>(sampler:&S) -> &'static str {
+///     let mut best = sampler.support()[0];
+///     for e in sampler.support() {
+///         if sampler.prob_of(e) > sampler.prob_of(&best) {
+///             best = *e;
+///         }
+///     }
+///     best
+/// }
+///
+/// let va = VoseAlias::new(vec!["common", "rare"], vec![0.7, 0.3]);
+/// let dynamic = DynamicSampler::from_weights(vec!["common", "rare"], vec![1.0, 9.0]).unwrap();
+/// assert_eq!(most_likely(&va), "common");
+/// assert_eq!(most_likely(&dynamic), "rare");
+/// ```
+pub trait WeightedSampler<T> {
+    /// Returns a reference to a sampled element, drawn using `rng`, or `None` if the sampler cannot sample (because it is empty or all its weights are 0).
+    fn sample<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<&T>;
+
+    /// Returns the probability of sampling `element`, or 0 if it is not part of the support of the sampler.
+    fn prob_of(&self, element:&T) -> f64;
+
+    /// Returns the elements that can be sampled from.
+    fn support(&self) -> &[T];
+
+    /// Returns the number of elements that can be sampled from.
+    fn len(&self) -> usize {
+        self.support().len()
+    }
+
+    /// Returns `true` if the sampler does not contain any element.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+
+impl<T, I> WeightedSampler<T> for VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+    fn sample<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<&T> {
+        if !self.is_sampleable() {
+            return None;
+        }
+        let (die, coin) = self.roll_die_and_flip_coin(rng);
+        Some(&self.elements[self.select_index(die, coin)])
+    }
+
+    fn prob_of(&self, element:&T) -> f64 {
+        match self.index.get(element) {
+            Some(i) => self.probabilities[*i],
+            None => 0.0,
+        }
+    }
+
+    fn support(&self) -> &[T] {
+        &self.elements
+    }
+}
+
+
+impl<T, Q, I> WeightedSampler<T> for QuantizedVoseAlias<T, Q, I>
+where T: Copy + Hash + Eq + Debug, Q: FixedPoint, I: SlotIndex, Standard: Distribution<Q> {
+    fn sample<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<&T> {
+        Some(&self.elements()[self.sample_index_with(rng)])
+    }
+
+    fn prob_of(&self, element:&T) -> f64 {
+        match self.elements().iter().position(|e| e == element) {
+            Some(i) => self.table_probability(i),
+            None => 0.0,
+        }
+    }
+
+    fn support(&self) -> &[T] {
+        self.elements()
+    }
+}
+
+
+impl<T> WeightedSampler<T> for DynamicSampler<T>
+where T: Copy + Hash + Eq + Debug {
+    fn sample<R: RngCore + ?Sized>(&self, rng:&mut R) -> Option<&T> {
+        self.sample_index_with(rng).map(|i| &self.elements()[i])
+    }
+
+    fn prob_of(&self, element:&T) -> f64 {
+        let total = self.total_weight();
+        match self.weight(element) {
+            Some(w) if total > 0.0 => w / total,
+            _ => 0.0,
+        }
+    }
+
+    fn support(&self) -> &[T] {
+        self.elements()
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_sampler<S: WeightedSampler<u32>>(sampler:&S) {
+        let expected = [0.5, 0.2, 0.2, 0.1];
+        assert_eq!(sampler.len(), 4);
+        for (e, p) in sampler.support().iter().zip(expected.iter()) {
+            assert!((sampler.prob_of(e) - p).abs() < 0.0001);
+        }
+        assert_eq!(sampler.prob_of(&42), 0.0);
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0x9e37_79b9_7f4a_7c15);
+        for _ in 0..100 {
+            let e = sampler.sample(&mut rng).unwrap();
+            assert!(sampler.support().contains(e));
+        }
+    }
+
+    #[test]
+    fn implementations() {
+        let va:VoseAlias<u32> = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+        check_sampler(&va);
+        check_sampler(&va.quantize::<u16>());
+        check_sampler(&DynamicSampler::from_vose_alias(&va).unwrap());
+    }
+}
@@ -0,0 +1,250 @@
+//! A weighted sampler whose weights vary over time, following a schedule per element.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{check_weights, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A function of time modulating the weight of an element: the effective weight of the element is its base weight multiplied by the factor of its schedule.
+///
+/// The time is the number of ticks elapsed since the schedule was set. Negative or non-finite factors are treated as 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    /// Always returns 1.
+    Constant,
+    /// Goes linearly from `from` to `to` over `ticks` ticks, then stays at `to`.
+    Linear { from:f64, to:f64, ticks:u64 },
+    /// Starts at 1 and is halved every `half_life` ticks.
+    ExponentialDecay { half_life:f64 },
+    /// Returns `before` during the first `ticks` ticks, then `after`.
+    Step { ticks:u64, before:f64, after:f64 },
+}
+
+
+impl Schedule {
+
+    /// Returns the factor of the schedule after `elapsed` ticks.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::Schedule;
+    ///
+    /// assert_eq!(Schedule::Linear { from: 0.0, to: 1.0, ticks: 4 }.factor(1), 0.25);
+    /// assert_eq!(Schedule::ExponentialDecay { half_life: 10.0 }.factor(20), 0.25);
+    /// assert_eq!(Schedule::Step { ticks: 5, before: 0.0, after: 1.0 }.factor(5), 1.0);
+    /// ```
+    pub fn factor(&self, elapsed:u64) -> f64 {
+        let factor = match *self {
+            Schedule::Constant => 1.0,
+            Schedule::Linear { from, to, ticks } => {
+                if elapsed >= ticks {
+                    to
+                }
+                else {
+                    from + (to - from) * elapsed as f64 / ticks as f64
+                }
+            },
+            Schedule::ExponentialDecay { half_life } => 0.5_f64.powf(elapsed as f64 / half_life),
+            Schedule::Step { ticks, before, after } => if elapsed < ticks { before } else { after },
+        };
+        if factor.is_finite() && factor > 0.0 { factor } else { 0.0 }
+    }
+}
+
+
+/// A weighted sampler where the weight of each element is modulated by a `Schedule`, as a function of a tick counter.
+///
+/// Advancing the counter or changing a schedule only marks the alias tables as outdated: they are rebuilt, in O(n), on the next sample. Consecutive samples between two changes are therefore drawn in constant time.
+/// Schedules are relative to the tick at which they are set, so restarting a schedule on an element just sampled implements a cooldown.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::{Schedule, ScheduledSampler};
+///
+/// let mut sampler = ScheduledSampler::new(vec!["easy", "hard"], vec![1.0, 1.0]).unwrap();
+/// // the hard examples are only introduced after 100 ticks
+/// sampler.set_schedule(&"hard", Schedule::Linear { from: 0.0, to: 1.0, ticks: 100 });
+/// assert_eq!(sampler.sample(), Some("easy"));
+///
+/// sampler.advance(100);
+/// assert_eq!(sampler.weight(&"hard"), Some(1.0));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScheduledSampler<T> where T: Copy + Hash + Eq + Debug {
+    elements:Vec<T>,
+    index:HashMap<T, usize>,
+    weights:Vec<f64>,
+    // the schedule of each element, with the tick at which it was set
+    schedules:Vec<(Schedule, u64)>,
+    tick:u64,
+    // built over the elements of positive weight only, `None` when all the effective weights are 0
+    table:Option<VoseAlias<T>>,
+    outdated:bool,
+}
+
+
+impl<T> ScheduledSampler<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns a sampler over the given elements and base weights, all with a constant schedule.
+    ///
+    /// An error is returned if both vectors do not have the same size, if the element vector is empty or contains duplicates, or if a weight is negative or not finite.
+    pub fn new(element_vector:Vec<T>, weight_vector:Vec<f64>) -> Result<ScheduledSampler<T>, VoseAliasError> {
+        // all the weights can be 0
+        match check_weights(element_vector.len(), &weight_vector) {
+            Ok(_) | Err(VoseAliasError::ZeroTotalWeight) => (),
+            Err(e) => return Err(e),
+        }
+        let mut index:HashMap<T, usize> = HashMap::with_capacity(element_vector.len());
+        for (i, e) in element_vector.iter().enumerate() {
+            if index.insert(*e, i).is_some() {
+                return Err(VoseAliasError::DuplicateElement(format!("{:?}", e)));
+            }
+        }
+
+        let size = element_vector.len();
+        Ok(ScheduledSampler {
+            elements: element_vector,
+            index,
+            weights: weight_vector,
+            schedules: vec![(Schedule::Constant, 0); size],
+            tick: 0,
+            table: None,
+            outdated: true,
+        })
+    }
+
+
+    /// Returns the elements of the sampler.
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+
+    /// Returns the current value of the tick counter.
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
+
+    /// Advances the tick counter by one.
+    pub fn tick(&mut self) {
+        self.advance(1);
+    }
+
+
+    /// Advances the tick counter by `ticks`.
+    pub fn advance(&mut self, ticks:u64) {
+        if ticks > 0 {
+            self.tick = self.tick.saturating_add(ticks);
+            self.outdated = true;
+        }
+    }
+
+
+    /// Sets the schedule of an element, starting at the current tick. Returns `false` if the element is not in the sampler.
+    pub fn set_schedule(&mut self, element:&T, schedule:Schedule) -> bool {
+        match self.index.get(element) {
+            Some(i) => {
+                self.schedules[*i] = (schedule, self.tick);
+                self.outdated = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+
+    /// Returns the effective weight of an element at the current tick, or `None` if it is not in the sampler.
+    pub fn weight(&self, element:&T) -> Option<f64> {
+        self.index.get(element).map(|i| self.effective_weight(*i))
+    }
+
+
+    /// Returns an element sampled according to the effective weights at the current tick, or `None` if they are all 0.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&mut self) -> Option<T> {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns an element sampled according to the effective weights at the current tick using the given generator, or `None` if they are all 0.
+    pub fn sample_with<R: RngCore + ?Sized>(&mut self, rng:&mut R) -> Option<T> {
+        if self.outdated {
+            self.rebuild();
+        }
+        self.table.as_ref().map(|table| table.sample_with(rng))
+    }
+
+
+    fn effective_weight(&self, i:usize) -> f64 {
+        let (schedule, start) = self.schedules[i];
+        self.weights[i] * schedule.factor(self.tick - start)
+    }
+
+
+    fn rebuild(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("vose_alias.rebuild", sampler = "scheduled", size = self.elements.len()).entered();
+        let weights:Vec<f64> = (0..self.elements.len()).map(|i| self.effective_weight(i)).collect();
+        self.table = VoseAlias::from_positive_weights(&self.elements, &weights);
+        self.outdated = false;
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules() {
+        assert_eq!(Schedule::Constant.factor(1000), 1.0);
+        assert_eq!(Schedule::Linear { from: 1.0, to: 0.0, ticks: 10 }.factor(5), 0.5);
+        assert_eq!(Schedule::Linear { from: 1.0, to: -1.0, ticks: 10 }.factor(8), 0.0);
+        assert_eq!(Schedule::Linear { from: 0.0, to: 2.0, ticks: 0 }.factor(0), 2.0);
+        assert_eq!(Schedule::ExponentialDecay { half_life: 2.0 }.factor(0), 1.0);
+        assert_eq!(Schedule::ExponentialDecay { half_life: 2.0 }.factor(2), 0.5);
+        assert_eq!(Schedule::Step { ticks: 3, before: 2.0, after: 0.5 }.factor(2), 2.0);
+        assert_eq!(Schedule::Step { ticks: 3, before: 2.0, after: 0.5 }.factor(3), 0.5);
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn cooldown() {
+        let mut sampler = ScheduledSampler::new(vec![1, 2], vec![1.0, 1.0]).unwrap();
+        let cooldown = Schedule::Step { ticks: 3, before: 0.0, after: 1.0 };
+        let mut rng = rand::thread_rng();
+        let first = sampler.sample_with(&mut rng).unwrap();
+        sampler.set_schedule(&first, cooldown);
+        sampler.tick();
+        let second = sampler.sample_with(&mut rng).unwrap();
+        assert_ne!(first, second);
+        sampler.set_schedule(&second, cooldown);
+        sampler.tick();
+        assert_eq!(sampler.sample_with(&mut rng), None);
+        sampler.tick();
+        assert_eq!(sampler.sample_with(&mut rng), Some(first));
+        assert!(!sampler.set_schedule(&42, cooldown));
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(ScheduledSampler::<u32>::new(vec![], vec![]).is_err());
+        assert!(ScheduledSampler::new(vec![1, 2], vec![1.0]).is_err());
+        assert!(ScheduledSampler::new(vec![1, 1], vec![1.0, 1.0]).is_err());
+        assert!(ScheduledSampler::new(vec![1, 2], vec![1.0, f64::NAN]).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Serialization of `VoseAlias` objects with `serde`, enabled by the `serde` feature.
+//!
+//! The tables are written with a format version, and are fully validated when read back: deserializing corrupt or incompatible data returns an error, and never panics.
+//! The serialized form contains the following fields:
+//! 1. `version`: the format version, currently `3`
+//! 2. `elements`: the elements to sample from
+//! 3. `prob`: the probability table, in the order of `elements`
+//! 4. `alias`: the alias table, given as the index in `elements` of the alias of each element (or nothing if the element has no alias)
+//! 5. `probabilities`: the probabilities given at construction, in full precision (`f64`) and in the order of `elements` (see `VoseAlias::probabilities()`)
+//!
+//! The version `2` stores `probabilities` as `f32`, and the version `1` has none: they are computed back from the tables when it is read. Stored probabilities which do not sum to 1, or do not match the tables, are rejected.
+//! The self-describing formats, such as JSON, read the three versions with `Deserialize`. The other formats cannot change the type of a field with the version: the `bytes` feature records the version in its frame header to read the older ones.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+
+
+/// The version of the serialized format written by this version of the crate.
+pub(crate) const FORMAT_VERSION:u32 = 3;
+
+/// The second version of the serialized format, with the requested probabilities stored as `f32`, which can still be read.
+pub(crate) const F32_FORMAT_VERSION:u32 = 2;
+
+/// The first version of the serialized format, without the requested probabilities, which can still be read.
+pub(crate) const LEGACY_FORMAT_VERSION:u32 = 1;
+
+
+#[derive(Serialize, Deserialize)]
+struct VoseAliasRepr<T> {
+    version: u32,
+    elements: Vec<T>,
+    prob: Vec<f32>,
+    alias: Vec<Option<usize>>,
+    #[serde(default)]
+    probabilities: Vec<f64>,
+}
+
+
+/// The serialized form of the version `2`, for the formats which cannot read its `f32` probabilities as `f64`, such as the one of the `bytes` feature.
+#[cfg(feature = "bytes")]
+#[derive(Deserialize)]
+pub(crate) struct F32VoseAliasRepr<T> {
+    version: u32,
+    elements: Vec<T>,
+    prob: Vec<f32>,
+    alias: Vec<Option<usize>>,
+    probabilities: Vec<f32>,
+}
+
+
+/// The serialized form of the version `1`, for the formats which cannot omit a field, such as the one of the `bytes` feature.
+#[cfg(feature = "bytes")]
+#[derive(Deserialize)]
+pub(crate) struct LegacyVoseAliasRepr<T> {
+    version: u32,
+    elements: Vec<T>,
+    prob: Vec<f32>,
+    alias: Vec<Option<usize>>,
+}
+
+
+#[cfg(feature = "bytes")]
+impl<T> LegacyVoseAliasRepr<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Rebuilds the object, with the probabilities computed back from the tables.
+    pub(crate) fn into_vose_alias<I: SlotIndex>(self) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        if self.version != LEGACY_FORMAT_VERSION {
+            return Err(VoseAliasError::UnsupportedVersion(self.version));
+        }
+        VoseAlias::from_tables(self.elements, self.prob, self.alias)
+    }
+}
+
+
+#[cfg(feature = "bytes")]
+impl<T> F32VoseAliasRepr<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Rebuilds the object, with the stored probabilities widened to `f64`.
+    pub(crate) fn into_vose_alias<I: SlotIndex>(self) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        if self.version != F32_FORMAT_VERSION {
+            return Err(VoseAliasError::UnsupportedVersion(self.version));
+        }
+        let probabilities = self.probabilities.iter().map(|p| f64::from(*p)).collect();
+        VoseAlias::from_tables_with_probabilities(self.elements, self.prob, self.alias, Some(probabilities))
+    }
+}
+
+
+impl<T, I> Serialize for VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug + Serialize, I: SlotIndex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = VoseAliasRepr {
+            version: FORMAT_VERSION,
+            elements: self.elements.clone(),
+            prob: self.slot_prob.clone(),
+            alias: self.slot_alias.iter().enumerate().map(|(i, a)| Some(a.to_usize()).filter(|a| *a != i)).collect(),
+            probabilities: self.probabilities.clone(),
+        };
+        repr.serialize(serializer)
+    }
+}
+
+
+impl<'de, T, I> Deserialize<'de> for VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug + Deserialize<'de>, I: SlotIndex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = VoseAliasRepr::<T>::deserialize(deserializer)?;
+        if ![FORMAT_VERSION, F32_FORMAT_VERSION, LEGACY_FORMAT_VERSION].contains(&repr.version) {
+            return Err(D::Error::custom(VoseAliasError::UnsupportedVersion(repr.version)));
+        }
+        // the probabilities are always written since the version 2
+        let written = repr.version != LEGACY_FORMAT_VERSION;
+        let probabilities = Some(repr.probabilities).filter(|p| written || !p.is_empty());
+        VoseAlias::from_tables_with_probabilities(repr.elements, repr.prob, repr.alias, probabilities).map_err(D::Error::custom)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let va = VoseAlias::new(vec!["orange", "yellow", "green", "turquoise", "grey", "blue", "pink"], vec![0.125, 0.2, 0.1, 0.25, 0.1, 0.1, 0.125]);
+        let json = serde_json::to_string(&va).unwrap();
+        let va2:VoseAlias<&str> = serde_json::from_str(&json).unwrap();
+        assert!(va == va2);
+        assert!(va.prob == va2.prob);
+        assert!(va.elements == va2.elements);
+        assert_eq!(va.probabilities(), va2.probabilities());
+
+        // the probabilities are kept in full precision
+        let va:VoseAlias<u32> = VoseAlias::from_weights(vec![1, 2, 3], vec![1.0, 1.0, 1.0]).unwrap();
+        let va2:VoseAlias<u32> = serde_json::from_str(&serde_json::to_string(&va).unwrap()).unwrap();
+        assert_eq!(va2.probabilities, vec![1.0 / 3.0; 3]);
+
+        // the version 2 stores them as `f32`
+        let json = r#"{"version":2,"elements":[1,2],"prob":[0.2,1.0],"alias":[1,null],"probabilities":[0.1,0.9]}"#;
+        let va:VoseAlias<u32> = serde_json::from_str(json).unwrap();
+        assert_eq!(va.probabilities(), &[0.1, 0.9]);
+
+        // the version 1 has no probabilities
+        let json = r#"{"version":1,"elements":[1,2],"prob":[0.5,1.0],"alias":[1,null]}"#;
+        let va:VoseAlias<u32> = serde_json::from_str(json).unwrap();
+        assert_eq!(va.probabilities(), &[0.25, 0.75]);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let json = r#"{"version":4,"elements":[1,2],"prob":[1.0,1.0],"alias":[null,null]}"#;
+        let err = serde_json::from_str::<VoseAlias<u32>>(json).unwrap_err();
+        assert!(err.to_string().contains("Unsupported format version 4"));
+    }
+
+    #[test]
+    fn corrupt_tables() {
+        // alias out of bounds
+        let json = r#"{"version":1,"elements":[1,2],"prob":[0.5,1.0],"alias":[7,null]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).is_err());
+        // missing alias
+        let json = r#"{"version":1,"elements":[1,2],"prob":[0.5,1.0],"alias":[null,null]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).is_err());
+        // probability out of range
+        let json = r#"{"version":1,"elements":[1,2],"prob":[1.5,1.0],"alias":[1,null]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).is_err());
+        // duplicate elements
+        let json = r#"{"version":1,"elements":[1,1],"prob":[1.0,1.0],"alias":[null,null]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).is_err());
+        // tables of different sizes
+        let json = r#"{"version":1,"elements":[1,2],"prob":[1.0],"alias":[null,null]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).is_err());
+        // empty
+        let json = r#"{"version":1,"elements":[],"prob":[],"alias":[]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).is_err());
+        // probabilities missing or out of range
+        let json = r#"{"version":2,"elements":[1,2],"prob":[1.0,1.0],"alias":[null,null],"probabilities":[]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).is_err());
+        let json = r#"{"version":2,"elements":[1,2],"prob":[1.0,1.0],"alias":[null,null],"probabilities":[0.5,1.5]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).is_err());
+        // probabilities which do not sum to 1, or are not the ones of the tables
+        let json = r#"{"version":3,"elements":[1,2],"prob":[1.0,1.0],"alias":[null,null],"probabilities":[0.5,0.4]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).unwrap_err().to_string().contains("sum to"));
+        let json = r#"{"version":3,"elements":[1,2],"prob":[1.0,1.0],"alias":[null,null],"probabilities":[0.9,0.1]}"#;
+        assert!(serde_json::from_str::<VoseAlias<u32>>(json).unwrap_err().to_string().contains("does not match the tables"));
+    }
+}
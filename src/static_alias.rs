@@ -0,0 +1,183 @@
+//! Alias tables built at compile time, for distributions known in advance.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::Rng;
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A Vose-Alias distribution over `N` elements whose tables are stored in fixed-size arrays, and can be built by a `const fn`.
+///
+/// Declared as a `static` or a `const`, the tables are computed by the compiler and stored in the binary: they cost nothing at startup and never touch the heap, which suits embedded targets and latency-critical code. Invalid weights make the constant evaluation fail, so they are reported when compiling.
+/// Sampling draws a column and a coin in [0, 1), and keeps the element of the column if the coin is strictly lower than its probability, so elements with a weight of 0 are never sampled.
+///
+/// The constructor cannot compare the elements in a constant context, so it does not check that they are distinct: duplicated elements are sampled with the sum of their weights. `StaticVoseAlias::to_vose_alias()` performs this check.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::StaticVoseAlias;
+///
+/// static LOOT:StaticVoseAlias<&str, 3> = StaticVoseAlias::new(["common", "rare", "epic"], [70.0, 25.0, 5.0]);
+///
+/// let item = LOOT.sample();
+/// assert!(LOOT.elements().contains(&item));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StaticVoseAlias<T: 'static, const N: usize> {
+    elements:[T; N],
+    prob:[f32; N],
+    alias:[usize; N],
+}
+
+
+impl<T: Copy + 'static, const N: usize> StaticVoseAlias<T, N> {
+
+    /// Builds the tables of the distribution sampling each element proportionally to its weight. The weights do not need to sum to 1.
+    ///
+    /// # Panics
+    /// This function panics if `N` is 0, if a weight is negative or not finite, or if all the weights are 0. In a `static` or a `const`, the panic is a compilation error.
+    pub const fn new(elements:[T; N], weights:[f64; N]) -> StaticVoseAlias<T, N> {
+        if N == 0 {
+            panic!("A distribution needs at least one element");
+        }
+        let mut total = 0.0;
+        let mut i = 0;
+        while i < N {
+            let w = weights[i];
+            if !w.is_finite() || w < 0.0 {
+                panic!("The weights must be non-negative and finite");
+            }
+            total += w;
+            i += 1;
+        }
+        if total <= 0.0 || !total.is_finite() {
+            panic!("The sum of the weights must be positive and finite");
+        }
+
+        // the usual construction, with fixed-size stacks for the small and large columns
+        let mut scaled = [0.0; N];
+        let mut small = [0; N];
+        let mut large = [0; N];
+        let (mut small_len, mut large_len) = (0, 0);
+        let mut i = 0;
+        while i < N {
+            scaled[i] = weights[i] * N as f64 / total;
+            if scaled[i] < 1.0 {
+                small[small_len] = i;
+                small_len += 1;
+            }
+            else {
+                large[large_len] = i;
+                large_len += 1;
+            }
+            i += 1;
+        }
+
+        let mut prob = [1.0; N];
+        let mut alias = [0; N];
+        let mut i = 0;
+        while i < N {
+            alias[i] = i;
+            i += 1;
+        }
+        while small_len > 0 && large_len > 0 {
+            small_len -= 1;
+            large_len -= 1;
+            let (l, g) = (small[small_len], large[large_len]);
+            prob[l] = scaled[l] as f32;
+            alias[l] = g;
+            scaled[g] = (scaled[g] + scaled[l]) - 1.0;
+            if scaled[g] < 1.0 {
+                small[small_len] = g;
+                small_len += 1;
+            }
+            else {
+                large[large_len] = g;
+                large_len += 1;
+            }
+        }
+        // the columns left are full, up to rounding errors, and keep their own element
+
+        StaticVoseAlias { elements, prob, alias }
+    }
+
+
+    /// Returns the elements of the distribution.
+    pub const fn elements(&self) -> &[T; N] {
+        &self.elements
+    }
+
+
+    /// Returns a sampled element using the default generator of the crate.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample(&self) -> T {
+        rng::with_default_rng(|rng| self.sample_with(rng))
+    }
+
+
+    /// Returns a sampled element using the given generator, in constant time.
+    pub fn sample_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> T {
+        self.elements[self.sample_index_with(rng)]
+    }
+
+
+    /// Returns the index in `elements()` of a sampled element, using the given generator.
+    pub fn sample_index_with<R: RngCore + ?Sized>(&self, rng:&mut R) -> usize {
+        let die = rng.gen_range(0, N);
+        let coin:f32 = rng.gen();
+        if coin < self.prob[die] {
+            die
+        }
+        else {
+            self.alias[die]
+        }
+    }
+}
+
+
+impl<T, const N: usize> StaticVoseAlias<T, N>
+where T: Copy + Hash + Eq + Debug + 'static {
+
+    /// Returns a `VoseAlias` object with the same tables, for the features of the crate working on heap-allocated distributions.
+    ///
+    /// An error is returned if an element appears twice, or if there are more elements than `I` can index.
+    pub fn to_vose_alias<I: SlotIndex>(&self) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let alias:Vec<Option<usize>> = self.alias.iter().enumerate().map(|(i, a)| if *a == i { None } else { Some(*a) }).collect();
+        VoseAlias::from_tables(self.elements.to_vec(), self.prob.to_vec(), alias)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+
+    const DICE:StaticVoseAlias<u8, 4> = StaticVoseAlias::new([1, 2, 3, 4], [4.0, 2.0, 2.0, 0.0]);
+
+    #[test]
+    fn compile_time_tables() {
+        let mut rng = rand::thread_rng();
+        let mut counts = [0; 4];
+        for _ in 0..10000 {
+            counts[DICE.sample_index_with(&mut rng)] += 1;
+        }
+        assert_eq!(counts[3], 0);
+        assert!(counts[0] > 4500 && counts[0] < 5500);
+
+        let va:VoseAlias<u8> = DICE.to_vose_alias().unwrap();
+        assert!((va.probabilities[0] - 0.5).abs() < 1e-6);
+        assert!((va.probabilities[3]).abs() < 1e-6);
+        assert!(StaticVoseAlias::new([1, 1], [1.0, 1.0]).to_vose_alias::<u32>().is_err());
+    }
+}
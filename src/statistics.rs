@@ -0,0 +1,333 @@
+//! Summary statistics computed from the probabilities of the distribution.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the expected value of `value(X)`, where X follows the distribution.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["small", "large"], vec![0.75, 0.25]);
+    /// let size = |e:&&str| if *e == "small" { 1.0 } else { 5.0 };
+    /// assert_eq!(va.mean_by(size), 2.0);
+    /// assert_eq!(va.variance_by(size), 3.0);
+    /// ```
+    pub fn mean_by<F: Fn(&T) -> f64>(&self, value:F) -> f64 {
+        self.elements.iter().zip(self.probabilities.iter()).map(|(e, p)| p * value(e)).sum()
+    }
+
+
+    /// Returns the variance of `value(X)`, where X follows the distribution.
+    pub fn variance_by<F: Fn(&T) -> f64>(&self, value:F) -> f64 {
+        let mean = self.mean_by(&value);
+        self.mean_by(|e| (value(e) - mean).powi(2))
+    }
+
+
+    /// Returns the standard error of the Monte Carlo estimate of E[`value(X)`] computed as the mean of `value` over `n` independent samples: √(Var[`value(X)`] / n).
+    ///
+    /// The error is computed from the probabilities, before any sample is drawn, to choose the number of samples of a simulation: about 95% of the estimates are within 2 standard errors of the true mean. Dividing the error by k takes k² times more samples.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let payoffs = VoseAlias::new(vec![0, 10, 100], vec![0.9, 0.09, 0.01]);
+    /// let payoff = |e:&i32| f64::from(*e);
+    /// let error = payoffs.mc_standard_error(payoff, 100_000);
+    /// // the estimate of the mean payoff (1.9) is within ±0.1 with 100,000 samples
+    /// assert!(2.0 * error < 0.1);
+    /// ```
+    ///
+    /// # Panics
+    /// This function panics if `n` is 0. See `VoseAlias::try_mc_standard_error()` for a non-panicking version.
+    pub fn mc_standard_error<F: Fn(&T) -> f64>(&self, value:F, n:usize) -> f64 {
+        match self.try_mc_standard_error(value, n) {
+            Ok(error) => error,
+            Err(_) => panic!("The standard error is not defined for 0 samples"),
+        }
+    }
+
+
+    /// Returns the standard error of the Monte Carlo estimate of E[`value(X)`] computed over `n` samples, like `VoseAlias::mc_standard_error()`.
+    ///
+    /// # Errors
+    /// An error (`VoseAliasError::InvalidParameter`) is returned if `n` is 0.
+    pub fn try_mc_standard_error<F: Fn(&T) -> f64>(&self, value:F, n:usize) -> Result<f64, VoseAliasError> {
+        if n == 0 {
+            return Err(VoseAliasError::InvalidParameter { name: "n", value: 0.0 });
+        }
+        Ok((self.variance_by(value) / n as f64).sqrt())
+    }
+
+
+    /// Returns the most probable element, with its probability. When several elements are the most probable, the first one in `elements` is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["a", "b", "c"], vec![0.25, 0.5, 0.25]);
+    /// assert_eq!(va.mode(), ("b", 0.5));
+    ///
+    /// let tie = VoseAlias::new(vec!["x", "y"], vec![0.5, 0.5]);
+    /// assert_eq!(tie.mode().0, "x");
+    /// ```
+    pub fn mode(&self) -> (T, f64) {
+        let mut best = 0;
+        for (i, p) in self.probabilities.iter().enumerate() {
+            if *p > self.probabilities[best] {
+                best = i;
+            }
+        }
+        (self.elements[best], self.probabilities[best])
+    }
+
+
+    /// Returns the perplexity of the distribution, 2 to the power of its entropy in bits: the number of equally likely elements of a uniform distribution that would be as unpredictable.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let uniform = VoseAlias::new(vec!['a', 'b', 'c', 'd'], vec![0.25, 0.25, 0.25, 0.25]);
+    /// assert!((uniform.perplexity() - 4.0).abs() < 1e-9);
+    /// let skewed = VoseAlias::new(vec!['a', 'b', 'c', 'd'], vec![0.7, 0.1, 0.1, 0.1]);
+    /// assert!(skewed.perplexity() < 3.0);
+    /// ```
+    pub fn perplexity(&self) -> f64 {
+        let entropy = self.probabilities.iter().filter(|p| **p > 0.0).fold(0.0, |h:f64, p| h + p * (1.0 / p).log2());
+        entropy.exp2()
+    }
+
+
+    /// Returns the Gini coefficient of the probabilities: 0 if all the elements are equally likely, and close to 1 if almost all the mass is on one element.
+    ///
+    /// It is the mean absolute difference between the probabilities of two elements, divided by twice their mean. The elements of probability 0 count, so adding impossible elements increases the coefficient.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let uniform = VoseAlias::new(vec!['a', 'b'], vec![0.5, 0.5]);
+    /// assert_eq!(uniform.gini(), 0.0);
+    /// let concentrated = VoseAlias::new(vec!['a', 'b', 'c', 'd'], vec![1.0, 0.0, 0.0, 0.0]);
+    /// assert!((concentrated.gini() - 0.75).abs() < 1e-9);
+    /// ```
+    pub fn gini(&self) -> f64 {
+        let size = self.probabilities.len() as f64;
+        let mut sorted = self.probabilities.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let total = sorted.iter().fold(0.0, |acc, p| acc + p);
+        let ranked = sorted.iter().enumerate().fold(0.0, |acc, (i, p)| acc + (i + 1) as f64 * p);
+        (2.0 * ranked / (size * total) - (size + 1.0) / size).max(0.0)
+    }
+
+
+    /// Returns the effective number of elements of the distribution, 1 / Σ p² (the inverse Simpson index): the number of equally likely elements of a uniform distribution with the same probability of drawing the same element twice.
+    ///
+    /// It is between 1 and the number of elements of positive probability, and weighs the most probable elements more than `VoseAlias::perplexity()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["a", "b", "c"], vec![0.5, 0.5, 0.0]);
+    /// assert!((va.effective_support_size() - 2.0).abs() < 1e-9);
+    /// ```
+    pub fn effective_support_size(&self) -> f64 {
+        1.0 / self.probabilities.iter().fold(0.0, |acc, p| acc + p * p)
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug + Into<f64>, I: SlotIndex {
+
+    /// Returns the expected value of the distribution, for numeric elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// // a loaded die
+    /// let va = VoseAlias::new(vec![1, 2, 3, 4, 5, 6], vec![0.1, 0.1, 0.1, 0.1, 0.1, 0.5]);
+    /// assert!((va.mean() - 4.5).abs() < 1e-6);
+    /// assert!((va.variance() - 3.25).abs() < 1e-6);
+    /// ```
+    pub fn mean(&self) -> f64 {
+        self.mean_by(|e| (*e).into())
+    }
+
+
+    /// Returns the variance of the distribution, for numeric elements.
+    pub fn variance(&self) -> f64 {
+        self.variance_by(|e| (*e).into())
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug + Ord, I: SlotIndex {
+
+    /// Returns the `q`-quantile of the distribution: the smallest element x such that P(X ≤ x) ≥ `q`. The elements of probability 0 are ignored.
+    ///
+    /// The elements are sorted on each call, in O(n log n).
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let damage = VoseAlias::new(vec![10, 20, 50, 100], vec![0.4, 0.3, 0.2, 0.1]);
+    /// assert_eq!(damage.quantile(0.0), 10);
+    /// assert_eq!(damage.median(), 20);
+    /// assert_eq!(damage.quantile(0.8), 50);
+    /// assert_eq!(damage.quantile(0.95), 100);
+    /// ```
+    ///
+    /// # Panics
+    /// This function panics if `q` is not between 0 and 1. See `VoseAlias::try_quantile()` for a non-panicking version.
+    pub fn quantile(&self, q:f64) -> T {
+        match self.try_quantile(q) {
+            Ok(e) => e,
+            Err(VoseAliasError::InvalidParameter { .. }) => panic!("Invalid quantile {}: it should be between 0 and 1", q),
+            Err(_) => panic!("Internal error. The distribution does not contain any element of positive probability. If this happened, please fill in an issue report."),
+        }
+    }
+
+
+    /// Returns the `q`-quantile of the distribution, like `VoseAlias::quantile()`.
+    ///
+    /// # Errors
+    /// An error is returned if `q` is not between 0 and 1 (`VoseAliasError::InvalidParameter`), or if no element of `elements` has a positive probability, which only happens if `elements` was emptied (`VoseAliasError::EmptyDistribution`).
+    pub fn try_quantile(&self, q:f64) -> Result<T, VoseAliasError> {
+        if !(0.0..=1.0).contains(&q) {
+            return Err(VoseAliasError::InvalidParameter { name: "q", value: q });
+        }
+
+        let mut sorted:Vec<(T, f64)> = self.elements.iter().copied().zip(self.probabilities.iter().copied())
+            .filter(|(_, p)| *p > 0.0)
+            .collect();
+        sorted.sort_by_key(|(e, _)| *e);
+
+        let mut cumulative = 0.0;
+        for (e, p) in &sorted {
+            cumulative += p;
+            if cumulative >= q {
+                return Ok(*e);
+            }
+        }
+        // rounding errors can leave the cumulative probability slightly below 1
+        match sorted.last() {
+            Some((e, _)) => Ok(*e),
+            None => Err(VoseAliasError::EmptyDistribution),
+        }
+    }
+
+
+    /// Returns the median of the distribution, which is its 0.5-quantile.
+    pub fn median(&self) -> T {
+        self.quantile(0.5)
+    }
+
+
+    /// Returns the median of the distribution like `VoseAlias::median()`, or an error if `elements` was emptied (see `VoseAlias::try_quantile()`).
+    pub fn try_median(&self) -> Result<T, VoseAliasError> {
+        self.try_quantile(0.5)
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moments() {
+        let va = VoseAlias::new(vec![-1, 1], vec![0.5, 0.5]);
+        assert!(va.mean().abs() < 1e-9);
+        assert!((va.variance() - 1.0).abs() < 1e-9);
+        let va = VoseAlias::new(vec![7u8], vec![1.0]);
+        assert_eq!((va.mean(), va.variance()), (7.0, 0.0));
+    }
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn standard_error() {
+        let va = VoseAlias::new(vec![-1, 1], vec![0.5, 0.5]);
+        assert!((va.mc_standard_error(|e| f64::from(*e), 100) - 0.1).abs() < 1e-9);
+        assert_eq!(va.mc_standard_error(|_| 3.0, 10), 0.0);
+
+        let mut rng = rand::thread_rng();
+        let va = VoseAlias::new(vec![0, 10, 100], vec![0.9, 0.09, 0.01]);
+        let error = va.mc_standard_error(|e| f64::from(*e), 1000);
+        let estimates:Vec<f64> = (0..200).map(|_| (0..1000).map(|_| f64::from(va.sample_with(&mut rng))).sum::<f64>() / 1000.0).collect();
+        let mean = estimates.iter().sum::<f64>() / 200.0;
+        let spread = (estimates.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / 199.0).sqrt();
+        assert!((spread - error).abs() < 0.25 * error);
+    }
+
+    #[test]
+    fn mode() {
+        let va = VoseAlias::new(vec![3, 1, 2], vec![0.25, 0.25, 0.5]);
+        assert_eq!(va.mode(), (2, 0.5));
+        let va = VoseAlias::new(vec![3, 1, 2], vec![0.5, 0.5, 0.0]);
+        assert_eq!(va.mode(), (3, 0.5));
+    }
+
+    #[test]
+    fn concentration() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.25, 0.25, 0.25, 0.25]);
+        assert!((va.perplexity() - 4.0).abs() < 1e-9);
+        assert!(va.gini().abs() < 1e-9);
+        assert!((va.effective_support_size() - 4.0).abs() < 1e-9);
+
+        let va = VoseAlias::new(vec![1, 2, 3], vec![0.5, 0.25, 0.25]);
+        assert!((va.perplexity() - 1.5_f64.exp2()).abs() < 1e-9);
+        assert!((va.gini() - 1.0 / 6.0).abs() < 1e-9);
+        assert!((va.effective_support_size() - 8.0 / 3.0).abs() < 1e-9);
+
+        let va = VoseAlias::new(vec![1], vec![1.0]);
+        assert_eq!((va.perplexity(), va.gini(), va.effective_support_size()), (1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn quantiles() {
+        let va = VoseAlias::new(vec!['c', 'a', 'd', 'b'], vec![0.25, 0.0, 0.5, 0.25]);
+        assert_eq!(va.quantile(0.0), 'b');
+        assert_eq!(va.quantile(0.25), 'b');
+        assert_eq!(va.median(), 'c');
+        assert_eq!(va.quantile(0.51), 'd');
+        assert_eq!(va.quantile(1.0), 'd');
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_quantile() {
+        VoseAlias::new(vec![1, 2], vec![0.5, 0.5]).quantile(1.5);
+    }
+
+    #[test]
+    fn non_panicking_variants() {
+        let mut va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        assert_eq!((va.try_quantile(1.0), va.try_median()), (Ok(2), Ok(1)));
+        assert!(matches!(va.try_quantile(1.5), Err(VoseAliasError::InvalidParameter { name: "q", .. })));
+        assert!(matches!(va.try_quantile(f64::NAN), Err(VoseAliasError::InvalidParameter { name: "q", .. })));
+        assert!((va.try_mc_standard_error(|e| f64::from(*e), 25).unwrap() - 0.1).abs() < 1e-12);
+        assert!(matches!(va.try_mc_standard_error(|e| f64::from(*e), 0), Err(VoseAliasError::InvalidParameter { name: "n", .. })));
+        va.elements.clear();
+        assert_eq!(va.try_median(), Err(VoseAliasError::EmptyDistribution));
+    }
+}
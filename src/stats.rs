@@ -0,0 +1,208 @@
+//! Running tallies of the samples drawn from a distribution, compared to the expected frequencies.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{SampleEvent, SampleObserver, SlotIndex, VoseAlias};
+
+
+/// A thread-safe tally of the samples drawn from a `VoseAlias` object, to monitor that the observed frequencies match the distribution.
+///
+/// The stats can be attached to the sampler as an observer, wrapped in an `Arc` (see `VoseAlias::set_observer()`), or fed manually with `SamplingStats::record()`, for instance when the sampling goes through another sampler of the same distribution.
+/// Counting a sample takes one atomic increment.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use std::sync::Arc;
+/// use vose_alias::{SamplingStats, VoseAlias};
+///
+/// let mut va = VoseAlias::new(vec!["win", "lose"], vec![0.3, 0.7]);
+/// let stats = Arc::new(SamplingStats::new(&va));
+/// va.set_observer(stats.clone());
+///
+/// for _ in 0..1000 {
+///     va.sample();
+/// }
+/// let report = stats.report();
+/// assert_eq!(report.total, 1000);
+/// assert!(report.total_variation < 0.1);
+/// println!("{}", report);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SamplingStats<T> {
+    elements:Vec<T>,
+    index:HashMap<T, usize>,
+    expected:Vec<f64>,
+    counts:Vec<AtomicU64>,
+}
+
+
+/// A snapshot of a `SamplingStats` tally, returned by `SamplingStats::report()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsReport<T> {
+    /// The total number of samples.
+    pub total:u64,
+    /// The tally of each element, in the order of the distribution.
+    pub entries:Vec<StatsEntry<T>>,
+    /// The total variation distance between the observed and the expected frequencies, between 0 and 1 (0 if no sample has been counted).
+    pub total_variation:f64,
+    /// Pearson's chi-squared statistic of the counts, over the elements of positive expected probability. It is infinite if an element of probability 0 was sampled.
+    pub chi_squared:f64,
+}
+
+
+/// The tally of one element in a `StatsReport`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsEntry<T> {
+    /// The element.
+    pub element:T,
+    /// The number of samples of the element.
+    pub count:u64,
+    /// The observed frequency of the element (0 if no sample has been counted).
+    pub observed:f64,
+    /// The probability of the element in the distribution.
+    pub expected:f64,
+}
+
+
+impl<T> SamplingStats<T>
+where T: Copy + Hash + Eq + Debug {
+
+    /// Returns an empty tally for the distribution of `va`.
+    pub fn new<I: SlotIndex>(va:&VoseAlias<T, I>) -> SamplingStats<T> {
+        SamplingStats {
+            elements: va.elements.clone(),
+            index: va.index.clone(),
+            expected: va.probabilities.clone(),
+            counts: va.elements.iter().map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+
+    /// Counts a sample of `element`. Returns `false`, without counting anything, if the element is not part of the distribution.
+    pub fn record(&self, element:&T) -> bool {
+        match self.index.get(element) {
+            Some(i) => {
+                self.counts[*i].fetch_add(1, Ordering::Relaxed);
+                true
+            },
+            None => false,
+        }
+    }
+
+
+    /// Returns the number of samples of `element`.
+    pub fn count(&self, element:&T) -> u64 {
+        self.index.get(element).map(|i| self.counts[*i].load(Ordering::Relaxed)).unwrap_or(0)
+    }
+
+
+    /// Returns the total number of samples.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|c| c.load(Ordering::Relaxed)).sum()
+    }
+
+
+    /// Resets all the counts to 0.
+    pub fn reset(&self) {
+        for c in &self.counts {
+            c.store(0, Ordering::Relaxed);
+        }
+    }
+
+
+    /// Returns a snapshot of the counts, with the drift of the observed frequencies from the expected ones.
+    ///
+    /// Samples counted while the report is computed may be missing from it.
+    pub fn report(&self) -> StatsReport<T> {
+        let counts:Vec<u64> = self.counts.iter().map(|c| c.load(Ordering::Relaxed)).collect();
+        let total:u64 = counts.iter().sum();
+        let n = total as f64;
+
+        let mut entries:Vec<StatsEntry<T>> = Vec::with_capacity(self.elements.len());
+        let mut total_variation = 0.0;
+        let mut chi_squared = 0.0;
+        for ((element, count), expected) in self.elements.iter().zip(counts).zip(self.expected.iter().copied()) {
+            let observed = if total > 0 { count as f64 / n } else { 0.0 };
+            if total > 0 {
+                total_variation += (observed - expected).abs() / 2.0;
+                if expected > 0.0 {
+                    chi_squared += (count as f64 - n * expected).powi(2) / (n * expected);
+                }
+                else if count > 0 {
+                    chi_squared = f64::INFINITY;
+                }
+            }
+            entries.push(StatsEntry { element: *element, count, observed, expected });
+        }
+
+        StatsReport {
+            total,
+            entries,
+            total_variation,
+            chi_squared,
+        }
+    }
+}
+
+
+impl<T> SampleObserver for SamplingStats<T>
+where T: Send + Sync {
+    fn on_sample(&self, event:&SampleEvent) {
+        if let Some(c) = self.counts.get(event.index) {
+            c.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+
+impl<T> Display for StatsReport<T>
+where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} samples, total variation {:.4}, chi-squared {:.2}", self.total, self.total_variation, self.chi_squared)?;
+        for entry in &self.entries {
+            writeln!(f, "{}: {} ({:.4} observed, {:.4} expected)", entry.element, entry.count, entry.observed, entry.expected)?;
+        }
+        Ok(())
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_records() {
+        let va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        let stats = SamplingStats::new(&va);
+        assert_eq!(stats.report().total_variation, 0.0);
+        assert!(stats.record(&1));
+        assert!(!stats.record(&3));
+        stats.record(&1);
+        stats.record(&2);
+        stats.record(&2);
+        let report = stats.report();
+        assert_eq!(report.total, 4);
+        assert_eq!(stats.count(&1), 2);
+        assert_eq!(report.total_variation, 0.0);
+        assert_eq!(report.chi_squared, 0.0);
+
+        stats.record(&1);
+        stats.record(&1);
+        let report = stats.report();
+        assert!((report.total_variation - 1.0 / 6.0).abs() < 1e-12);
+        assert!((report.chi_squared - 2.0 / 3.0).abs() < 1e-12);
+        stats.reset();
+        assert_eq!(stats.total(), 0);
+    }
+}
@@ -0,0 +1,136 @@
+//! Asynchronous streams of samples, optionally paced by a tokio interval.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::time::Interval;
+
+use crate::{rng, SlotIndex, VoseAlias};
+
+
+/// An endless `Stream` of elements sampled from a distribution, returned by `VoseAlias::into_stream()` and `VoseAlias::into_stream_with_interval()`.
+///
+/// The samples are drawn with the default generator of the crate, which is never held across polls, so the stream can be sent between the threads of a tokio runtime.
+#[derive(Debug)]
+pub struct SampleStream<T, I = u32> where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+    va:VoseAlias<T, I>,
+    interval:Option<Interval>,
+}
+
+
+impl<T, I> SampleStream<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns the distribution the samples are drawn from.
+    pub fn distribution(&self) -> &VoseAlias<T, I> {
+        &self.va
+    }
+
+
+    /// Stops the stream and returns its distribution.
+    pub fn into_inner(self) -> VoseAlias<T, I> {
+        self.va
+    }
+}
+
+
+// the fields are never pinned, so the stream can be moved even if the elements cannot
+impl<T, I> Unpin for SampleStream<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {}
+
+
+impl<T, I> Stream for SampleStream<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+    type Item = T;
+
+    fn poll_next(self:Pin<&mut Self>, cx:&mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        if let Some(interval) = &mut this.interval {
+            if interval.poll_tick(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(Some(rng::with_default_rng(|rng| this.va.sample_with(rng))))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Turns the distribution into an endless stream of samples, each immediately available.
+    ///
+    /// Since the stream never waits, a task consuming it in a loop never yields to the runtime: bound it with `take()`, or pace it with `VoseAlias::into_stream_with_interval()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use futures::StreamExt;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["GET", "POST"], vec![0.8, 0.2]);
+    /// let requests:Vec<&str> = futures::executor::block_on(va.into_stream().take(10).collect());
+    /// assert_eq!(requests.len(), 10);
+    /// ```
+    pub fn into_stream(self) -> SampleStream<T, I> {
+        SampleStream { va: self, interval: None }
+    }
+
+
+    /// Turns the distribution into an endless stream yielding one sample per tick of `interval`, for load generators and traffic shapers emitting one weighted event per tick.
+    ///
+    /// The first sample is available immediately, as the first tick of a tokio interval. What happens when the consumer falls behind depends on the `tokio::time::MissedTickBehavior` of the interval.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use futures::StreamExt;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    /// runtime.block_on(async {
+    ///     let va = VoseAlias::new(vec!["GET", "POST"], vec![0.8, 0.2]);
+    ///     let mut requests = va.into_stream_with_interval(tokio::time::interval(Duration::from_millis(5)));
+    ///     for _ in 0..3 {
+    ///         let method = requests.next().await.unwrap();
+    ///         assert!(method == "GET" || method == "POST");
+    ///     }
+    /// });
+    /// ```
+    pub fn into_stream_with_interval(self, interval:Interval) -> SampleStream<T, I> {
+        SampleStream { va: self, interval: Some(interval) }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use futures::StreamExt;
+
+    #[test]
+    fn paced_stream() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().start_paused(true).build().unwrap();
+        runtime.block_on(async {
+            let va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+            let start = tokio::time::Instant::now();
+            let stream = va.into_stream_with_interval(tokio::time::interval(Duration::from_secs(1)));
+            let samples:Vec<i32> = stream.take(4).collect().await;
+            assert_eq!(samples.len(), 4);
+            assert!(samples.iter().all(|s| *s == 1 || *s == 2));
+            // the first tick is immediate
+            assert_eq!(start.elapsed(), Duration::from_secs(3));
+        });
+    }
+}
@@ -0,0 +1,101 @@
+//! A sum tree over non-negative values, sampling a leaf proportionally to its value in O(log n), shared by the samplers updating their weights one at a time.
+
+
+/// A binary tree over `leaves` leaves (leaf i is the node leaves + i), each node holding the sum of the values below it. The root is the node 1.
+///
+/// The sums are recomputed, rather than incremented, on each update: the total does not drift over millions of updates.
+#[derive(Debug, Clone)]
+pub(crate) struct SumTree {
+    leaves:usize,
+    sums:Vec<f64>,
+}
+
+
+impl SumTree {
+
+    /// Returns a tree of `size` leaves, all 0.
+    pub(crate) fn new(size:usize) -> SumTree {
+        let leaves = size.next_power_of_two();
+        SumTree { leaves, sums: vec![0.0; 2 * leaves] }
+    }
+
+
+    /// Returns a tree whose leaves are `values`, built in O(n).
+    pub(crate) fn from_values(values:&[f64]) -> SumTree {
+        let mut tree = SumTree::new(values.len());
+        let leaves = tree.leaves;
+        tree.sums[leaves..leaves + values.len()].copy_from_slice(values);
+        for node in (1..leaves).rev() {
+            tree.sums[node] = tree.sums[2 * node] + tree.sums[2 * node + 1];
+        }
+        tree
+    }
+
+
+    /// Returns the value of a leaf.
+    pub(crate) fn leaf(&self, index:usize) -> f64 {
+        self.sums[self.leaves + index]
+    }
+
+
+    /// Returns the sum of all the leaves.
+    pub(crate) fn total(&self) -> f64 {
+        self.sums[1]
+    }
+
+
+    /// Sets the value of a leaf, and updates its ancestors, in O(log n). Returns the previous value of the leaf.
+    pub(crate) fn set(&mut self, index:usize, value:f64) -> f64 {
+        let mut node = self.leaves + index;
+        let previous = self.sums[node];
+        self.sums[node] = value;
+        while node > 1 {
+            node /= 2;
+            // recomputing the sums instead of adding the difference avoids accumulating rounding errors
+            self.sums[node] = self.sums[2 * node] + self.sums[2 * node + 1];
+        }
+        previous
+    }
+
+
+    /// Returns the index of the leaf at which the prefix sum of the leaves reaches `target`, in O(log n).
+    pub(crate) fn find(&self, mut target:f64) -> usize {
+        let mut node = 1;
+        while node < self.leaves {
+            let left = 2 * node;
+            // going left when the right subtree is empty keeps rounding errors from reaching leaves of value 0
+            if target < self.sums[left] || self.sums[left + 1] <= 0.0 {
+                node = left;
+            }
+            else {
+                target -= self.sums[left];
+                node = left + 1;
+            }
+        }
+        node - self.leaves
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_and_descent() {
+        let mut tree = SumTree::from_values(&[1.0, 0.0, 3.0]);
+        assert_eq!(tree.total(), 4.0);
+        assert_eq!(tree.find(0.5), 0);
+        assert_eq!(tree.find(1.0), 2);
+        // a target rounded up past the total never reaches the leaves of value 0
+        assert_eq!(tree.find(4.5), 2);
+        assert_eq!(tree.set(2, 0.0), 3.0);
+        assert_eq!(tree.find(1.5), 0);
+        assert_eq!(tree.leaf(0), 1.0);
+        assert_eq!(SumTree::new(5).total(), 0.0);
+    }
+}
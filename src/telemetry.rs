@@ -0,0 +1,123 @@
+//! Metrics of the samplers, emitted through the `metrics` facade.
+//!
+//! Once a recorder is installed (for instance a Prometheus exporter), the following metrics are available:
+//! - `vose_alias.samples` (counter): the number of samples drawn from a distribution, with the label `sampler`
+//! - `vose_alias.alias_samples` (counter): the number of samples for which the coin selected the alias of the slot, with the label `sampler`
+//! - `vose_alias.element_samples` (counter): the number of samples of each element, with the labels `sampler` and `element` (the `Display` form of the element), for distributions of at most 64 elements
+//! - `vose_alias.build_seconds` (histogram): the time taken to build the tables of a distribution, at construction and rebuild
+//!
+//! The sample metrics are emitted by a `MetricsObserver`, attached with `VoseAlias::enable_metrics()`. The build duration is recorded for every distribution, as there is no observer yet when the tables are built.
+
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::sync::Arc;
+
+use metrics::{counter, Counter};
+
+use crate::{SampleEvent, SampleObserver, SlotIndex, VoseAlias};
+
+
+/// Largest number of elements for which a counter is registered per element.
+const MAX_LABELED_ELEMENTS:usize = 64;
+
+
+/// A `SampleObserver` counting the samples of a distribution through the `metrics` facade.
+///
+/// The counters are registered when the observer is created, using the recorder installed at that time, so that sampling only increments atomic counters.
+pub struct MetricsObserver {
+    samples:Counter,
+    alias_samples:Counter,
+    // empty if the distribution has too many elements
+    element_samples:Vec<Counter>,
+}
+
+
+impl MetricsObserver {
+
+    /// Returns an observer counting the samples of a distribution over `elements`, labeled with `sampler`.
+    ///
+    /// A counter is registered per element only if there are at most 64 elements, to keep the number of time series bounded.
+    pub fn new<T: Display>(sampler:&str, elements:&[T]) -> MetricsObserver {
+        let element_samples = if elements.len() <= MAX_LABELED_ELEMENTS {
+            elements.iter().map(|e| counter!("vose_alias.element_samples", "sampler" => sampler.to_string(), "element" => e.to_string())).collect()
+        }
+        else {
+            Vec::new()
+        };
+        MetricsObserver {
+            samples: counter!("vose_alias.samples", "sampler" => sampler.to_string()),
+            alias_samples: counter!("vose_alias.alias_samples", "sampler" => sampler.to_string()),
+            element_samples,
+        }
+    }
+}
+
+
+impl SampleObserver for MetricsObserver {
+    fn on_sample(&self, event:&SampleEvent) {
+        self.samples.increment(1);
+        if event.alias_used {
+            self.alias_samples.increment(1);
+        }
+        if let Some(counter) = self.element_samples.get(event.index) {
+            counter.increment(1);
+        }
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Display + Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Attaches a `MetricsObserver` to this object, which counts its samples under the label `sampler`. It replaces the previous observer, if any.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let mut va = VoseAlias::new(vec!["common", "rare"], vec![0.9, 0.1]);
+    /// va.enable_metrics("loot");
+    /// // increments vose_alias.samples{sampler="loot"} in the installed recorder, if any
+    /// va.sample();
+    /// # }
+    /// ```
+    pub fn enable_metrics(&mut self, sampler:&str) {
+        let observer = MetricsObserver::new(sampler, &self.elements);
+        self.set_observer(Arc::new(observer));
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    #[test]
+    fn sample_counters() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let mut va = metrics::with_local_recorder(&recorder, || {
+            let mut va = VoseAlias::new(vec!["a", "b"], vec![1.0, 0.0]);
+            va.enable_metrics("test");
+            va
+        });
+        for _ in 0..10 {
+            va.sample();
+        }
+
+        let counters:Vec<(String, Vec<String>, u64)> = snapshotter.snapshot().into_vec().into_iter().filter_map(|(key, _, _, value)| match value {
+            DebugValue::Counter(c) => Some((key.key().name().to_string(), key.key().labels().map(|l| l.value().to_string()).collect(), c)),
+            _ => None,
+        }).collect();
+        let value = |name:&str, labels:&[&str]| counters.iter().find(|(n, l, _)| n == name && l == labels).map(|(_, _, c)| *c);
+        assert_eq!(value("vose_alias.samples", &["test"]), Some(10));
+        assert_eq!(value("vose_alias.element_samples", &["test", "a"]).unwrap() + value("vose_alias.element_samples", &["test", "b"]).unwrap(), 10);
+        va.clear_observer();
+    }
+}
@@ -0,0 +1,115 @@
+//! Statistical checks of the samples of a distribution, for the unit tests of the crates using it, enabled by the `test-support` feature.
+//!
+//! The samples are compared to the probabilities of the distribution through the Wilson confidence interval of the frequency of each element (see `VoseAlias::confidence_intervals()`).
+//! The confidence level of each interval is corrected for the number of elements (Bonferroni correction), so that a correct sampler fails a check with a probability of at most 1 in a million: the tests using them are not flaky, and a failure points to a real bug.
+//! The tolerance shrinks with the number of samples: with 10,000 samples, a difference of about 0.025 between the frequency and the probability of an element is detected, and of about 0.0025 with 1,000,000 samples.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{rng, FrequencyInterval, SlotIndex, VoseAlias};
+
+
+/// The probability that a check of a correct sampler fails.
+const FALSE_ALARM_PROBABILITY:f64 = 1e-6;
+
+
+/// Asserts that `n` samples of a distribution match its probabilities, as checked by `VoseAlias::check_distribution()`.
+///
+/// The macro panics with the observed and expected frequencies of the elements that do not match.
+///
+/// # Examples
+/// ```
+/// use vose_alias::{assert_distribution, VoseAlias};
+///
+/// let va = VoseAlias::new(vec!["a", "b", "c"], vec![0.5, 0.25, 0.25]);
+/// assert_distribution!(va, 10_000);
+/// ```
+#[macro_export]
+macro_rules! assert_distribution {
+    ($va:expr, $n:expr $(,)?) => {
+        if let Err(inconsistent) = $va.check_distribution($n) {
+            let details:Vec<String> = inconsistent.iter()
+                .map(|i| format!("{:?}: {:.5} observed in [{:.5}, {:.5}], {:.5} expected", i.element, i.observed, i.lower, i.upper, i.expected))
+                .collect();
+            panic!("The samples do not match the distribution:\n{}", details.join("\n"));
+        }
+    };
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Draws `n` samples with the default generator of the crate, and checks that their frequencies match the probabilities of the distribution. See `VoseAlias::check_samples()`.
+    pub fn check_distribution(&self, n:usize) -> Result<(), Vec<FrequencyInterval<T>>> {
+        let samples:Vec<T> = rng::with_default_rng(|rng| (0..n).map(|_| self.sample_with(rng)).collect());
+        self.check_samples(samples)
+    }
+
+
+    /// Checks that the frequencies of `samples`, drawn from any sampler, match the probabilities of the distribution. The elements which do not match are returned as errors, with their confidence intervals, in the order of `elements`.
+    ///
+    /// A sample that is not part of the distribution is returned as an inconsistent element, with a probability of 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+    /// let biased = (0..1000).map(|i| if i % 4 == 0 { 2 } else { 1 });
+    /// let inconsistent = va.check_samples(biased).unwrap_err();
+    /// assert_eq!(inconsistent.len(), 2);
+    /// assert!(va.check_samples((0..1000).map(|i| i % 2 + 1)).is_ok());
+    /// ```
+    pub fn check_samples<S: IntoIterator<Item = T>>(&self, samples:S) -> Result<(), Vec<FrequencyInterval<T>>> {
+        let mut counts:HashMap<T, u64> = HashMap::new();
+        let mut n:u64 = 0;
+        for s in samples {
+            *counts.entry(s).or_insert(0) += 1;
+            n += 1;
+        }
+        let unknown:Vec<FrequencyInterval<T>> = counts.iter().filter(|(e, _)| !self.index.contains_key(*e))
+            .map(|(e, c)| FrequencyInterval { element: *e, count: *c, observed: *c as f64 / n as f64, expected: 0.0, lower: 0.0, upper: 1.0, consistent: false })
+            .collect();
+        if !unknown.is_empty() {
+            return Err(unknown);
+        }
+
+        let confidence = 1.0 - FALSE_ALARM_PROBABILITY / self.elements.len() as f64;
+        let intervals = match self.confidence_intervals(&counts, n, confidence) {
+            Ok(intervals) => intervals,
+            Err(e) => panic!("Internal error. The confidence intervals of the samples cannot be computed ({}). If this happened, please fill in an issue report.", e),
+        };
+        let inconsistent:Vec<FrequencyInterval<T>> = intervals.into_iter().filter(|i| !i.consistent).collect();
+        if inconsistent.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(inconsistent)
+        }
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution_checks() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.25, 0.25, 0.25, 0.25]);
+        assert_distribution!(va, 20_000);
+        assert!(va.check_samples((0..4000).map(|i| i % 4 + 1)).is_ok());
+
+        let inconsistent = va.check_samples((0..4000).map(|i| if i % 5 == 0 { 1 } else { i % 4 + 1 })).unwrap_err();
+        assert!(inconsistent.iter().any(|i| i.element == 1));
+        let unknown = va.check_samples(vec![1, 2, 3, 4, 5]).unwrap_err();
+        assert_eq!((unknown[0].element, unknown[0].count), (5, 1));
+    }
+}
@@ -0,0 +1,144 @@
+//! Token id samplers built from the vocabulary of a tokenizer and token frequencies (feature `tokenizer`).
+//!
+//! The vocabulary is read from a Hugging Face `tokenizer.json` file, or from the `vocab.json` file of a BPE tokenizer (the merges are not needed to map tokens to ids).
+//! Sampling ids from the unigram distribution of a corpus is the usual source of negative samples and random token corruptions in language-model tooling.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::{VoseAlias, VoseAliasError};
+
+
+impl VoseAlias<u32> {
+
+    /// Returns the Vose-Alias object sampling the ids of the tokens of the vocabulary file at `path`, each proportionally to the count of the token in `counts`.
+    ///
+    /// The file can be a `tokenizer.json` file, whose vocabulary is the `model.vocab` field (an object mapping the tokens to their ids, or for Unigram models a list of `[token, score]` pairs whose ids are their positions), or a `vocab.json` file mapping the tokens to their ids.
+    /// Only the tokens with a positive count can be sampled, and the counts of the tokens missing from the vocabulary are ignored. If several tokens have the same id, their counts are added. The ids are stored in increasing order in `elements`.
+    ///
+    /// An error is returned if the file cannot be read or does not contain a vocabulary, or if no token of the vocabulary has a positive count.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # #[cfg(feature = "thread-rng")] {
+    /// use std::collections::HashMap;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let mut counts:HashMap<String, u64> = HashMap::new();
+    /// counts.insert(String::from("the"), 5_000);
+    /// counts.insert(String::from("cat"), 12);
+    /// let va = VoseAlias::from_tokenizer_vocab("tokenizer.json", &counts).unwrap();
+    /// let negative_sample:u32 = va.sample();
+    /// # }
+    /// ```
+    pub fn from_tokenizer_vocab<P: AsRef<Path>>(path:P, counts:&HashMap<String, u64>) -> Result<VoseAlias<u32>, VoseAliasError> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(json) => VoseAlias::from_tokenizer_vocab_str(&json, counts),
+            Err(e) => Err(VoseAliasError::InvalidFile(format!("{} cannot be read ({})", path.display(), e))),
+        }
+    }
+
+
+    /// Works like `VoseAlias::from_tokenizer_vocab()`, with the content of the vocabulary file.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::collections::HashMap;
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let vocab = r#"{"<unk>": 0, "hello": 1, "world": 2}"#;
+    /// let counts:HashMap<String, u64> = vec![("hello", 3), ("world", 1)].into_iter().map(|(t, c)| (t.to_string(), c)).collect();
+    /// let va = VoseAlias::from_tokenizer_vocab_str(vocab, &counts).unwrap();
+    /// assert_eq!(va.elements, vec![1, 2]);
+    /// ```
+    pub fn from_tokenizer_vocab_str(json:&str, counts:&HashMap<String, u64>) -> Result<VoseAlias<u32>, VoseAliasError> {
+        let value:Value = match serde_json::from_str(json) {
+            Ok(value) => value,
+            Err(e) => return Err(VoseAliasError::InvalidFile(format!("invalid JSON ({})", e))),
+        };
+        // a `vocab.json` file can have a "model" token, whose id is a number: only an object is a tokenizer model
+        let vocab = match value.get("model") {
+            Some(model) if model.is_object() => model.get("vocab").ok_or_else(|| VoseAliasError::InvalidFile(String::from("the tokenizer model has no vocabulary")))?,
+            _ => &value,
+        };
+
+        let mut id_counts:BTreeMap<u32, u64> = BTreeMap::new();
+        for (token, id) in vocabulary(vocab)? {
+            if let Some(c) = counts.get(token).filter(|c| **c > 0) {
+                *id_counts.entry(id).or_insert(0) += c;
+            }
+        }
+        if id_counts.is_empty() {
+            return Err(VoseAliasError::ZeroTotalWeight);
+        }
+        let (ids, weights):(Vec<u32>, Vec<f64>) = id_counts.into_iter().map(|(id, c)| (id, c as f64)).unzip();
+        VoseAlias::from_weights(ids, weights)
+    }
+}
+
+
+/// Returns the tokens of a vocabulary with their ids.
+fn vocabulary(vocab:&Value) -> Result<Vec<(&str, u32)>, VoseAliasError> {
+    let invalid = |entry:&Value| VoseAliasError::InvalidFile(format!("invalid vocabulary entry {}", entry));
+    match vocab {
+        Value::Object(map) => map.iter()
+            .map(|(token, id)| match id.as_u64() {
+                Some(id) if id <= u64::from(u32::MAX) => Ok((token.as_str(), id as u32)),
+                _ => Err(invalid(id)),
+            })
+            .collect(),
+        // Unigram models list [token, score] pairs, in the order of the ids
+        Value::Array(entries) if entries.len() <= u32::MAX as usize => entries.iter().enumerate()
+            .map(|(id, entry)| match entry.get(0).and_then(Value::as_str) {
+                Some(token) => Ok((token, id as u32)),
+                None => Err(invalid(entry)),
+            })
+            .collect(),
+        _ => Err(VoseAliasError::InvalidFile(String::from("the vocabulary should be an object or a list"))),
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(pairs:&[(&str, u64)]) -> HashMap<String, u64> {
+        pairs.iter().map(|(t, c)| (t.to_string(), *c)).collect()
+    }
+
+    #[test]
+    fn tokenizer_models() {
+        let bpe = r#"{"version": "1.0", "model": {"type": "BPE", "vocab": {"a": 0, "b": 1, "ab": 2}, "merges": ["a b"]}}"#;
+        let va = VoseAlias::from_tokenizer_vocab_str(bpe, &counts(&[("ab", 3), ("a", 1), ("zzz", 7)])).unwrap();
+        assert_eq!(va.elements, vec![0, 2]);
+        assert!((va.probabilities[1] - 0.75).abs() < 1e-6);
+
+        let unigram = r#"{"model": {"type": "Unigram", "vocab": [["<unk>", 0.0], ["x", -1.5], ["y", -2.0]]}}"#;
+        let va = VoseAlias::from_tokenizer_vocab_str(unigram, &counts(&[("y", 2), ("x", 2)])).unwrap();
+        assert_eq!(va.elements, vec![1, 2]);
+
+        // a `vocab.json` file with a "model" token
+        let vocab = r#"{"model": 0, "data": 1}"#;
+        let va = VoseAlias::from_tokenizer_vocab_str(vocab, &counts(&[("model", 1), ("data", 3)])).unwrap();
+        assert_eq!(va.elements, vec![0, 1]);
+        assert!((va.probabilities[0] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn invalid_vocabularies() {
+        let c = counts(&[("a", 1)]);
+        assert!(matches!(VoseAlias::from_tokenizer_vocab_str("{", &c), Err(VoseAliasError::InvalidFile(_))));
+        assert!(matches!(VoseAlias::from_tokenizer_vocab_str(r#"{"model": {}}"#, &c), Err(VoseAliasError::InvalidFile(_))));
+        assert!(matches!(VoseAlias::from_tokenizer_vocab_str(r#"{"a": -1}"#, &c), Err(VoseAliasError::InvalidFile(_))));
+        assert_eq!(VoseAlias::from_tokenizer_vocab_str(r#"{"b": 0}"#, &c).err(), Some(VoseAliasError::ZeroTotalWeight));
+        assert!(matches!(VoseAlias::from_tokenizer_vocab("missing/tokenizer.json", &c), Err(VoseAliasError::InvalidFile(_))));
+    }
+}
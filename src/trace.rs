@@ -0,0 +1,157 @@
+//! Recording of the random draws made while sampling, to replay the exact same samples later.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand_core::RngCore;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError, COIN_BITS, COIN_SCALE};
+#[cfg(feature = "thread-rng")]
+use crate::rng;
+
+
+/// A compact record of the (die, coin) pairs drawn by `VoseAlias::sample_recorded()`.
+///
+/// Each draw is packed into a single `u64`, the die in the high 40 bits and the coin in the low 24 bits. The packed form, returned by `SamplingTrace::packed()`, can be written to a log and loaded back with `SamplingTrace::from_packed()`.
+/// Replaying a trace with `VoseAlias::replay()` on the same tables reproduces the exact same sequence of samples, whatever generator was used to record it.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "thread-rng")] {
+/// use vose_alias::{SamplingTrace, VoseAlias};
+///
+/// let va = VoseAlias::new(vec!["common", "rare", "legendary"], vec![0.89, 0.1, 0.01]);
+/// let mut trace = SamplingTrace::new();
+/// let drops:Vec<&str> = (0..10).map(|_| va.sample_recorded(&mut trace)).collect();
+///
+/// let logged:Vec<u64> = trace.packed().to_vec();
+/// let replayed = va.replay(&SamplingTrace::from_packed(logged)).unwrap();
+/// assert_eq!(replayed, drops);
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SamplingTrace {
+    draws:Vec<u64>,
+}
+
+
+impl SamplingTrace {
+
+    /// Returns an empty trace.
+    pub fn new() -> SamplingTrace {
+        SamplingTrace::default()
+    }
+
+
+    /// Returns a trace from its packed form.
+    pub fn from_packed(draws:Vec<u64>) -> SamplingTrace {
+        SamplingTrace { draws }
+    }
+
+
+    /// Returns the packed draws of the trace.
+    pub fn packed(&self) -> &[u64] {
+        &self.draws
+    }
+
+
+    /// Returns the number of draws in the trace.
+    pub fn len(&self) -> usize {
+        self.draws.len()
+    }
+
+
+    /// Returns `true` if the trace does not contain any draw.
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+
+
+    /// Removes all the draws of the trace.
+    pub fn clear(&mut self) {
+        self.draws.clear();
+    }
+
+
+    /// Returns an iterator over the (die, coin) pairs of the trace.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, u32)> + '_ {
+        self.draws.iter().map(|d| ((d >> COIN_BITS) as usize, (*d as u32) & ((1 << COIN_BITS) - 1)))
+    }
+
+
+    fn push(&mut self, die:usize, coin:u32) {
+        self.draws.push(((die as u64) << COIN_BITS) | u64::from(coin));
+    }
+}
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns a sampled element like `VoseAlias::sample()`, and records the draw in `trace`.
+    #[cfg(feature = "thread-rng")]
+    pub fn sample_recorded(&self, trace:&mut SamplingTrace) -> T {
+        rng::with_default_rng(|rng| self.sample_recorded_with(rng, trace))
+    }
+
+
+    /// Returns a sampled element like `VoseAlias::sample_with()`, and records the draw in `trace`.
+    pub fn sample_recorded_with<R: RngCore + ?Sized>(&self, rng:&mut R, trace:&mut SamplingTrace) -> T {
+        let (die, coin) = self.roll_die_and_flip_coin(rng);
+        trace.push(die, coin);
+        self.select_element(die, coin)
+    }
+
+
+    /// Returns the elements sampled by the draws of `trace`, in order.
+    ///
+    /// An error is returned if a draw could not have been made on these tables, which means that the trace was recorded on a different distribution.
+    pub fn replay(&self, trace:&SamplingTrace) -> Result<Vec<T>, VoseAliasError> {
+        trace.iter().enumerate().map(|(position, (die, coin))| {
+            if die >= self.elements.len() {
+                return Err(VoseAliasError::InvalidTrace { position });
+            }
+            if (coin as f32) < self.slot_prob[die] * COIN_SCALE {
+                return Ok(self.elements[die]);
+            }
+            match self.slot_alias[die].to_usize() {
+                alias if alias == die => Err(VoseAliasError::InvalidTrace { position }),
+                alias => Ok(self.elements[alias]),
+            }
+        }).collect()
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "thread-rng")]
+    #[test]
+    fn record_and_replay() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+        let mut trace = SamplingTrace::new();
+        let mut rng = rand::thread_rng();
+        let samples:Vec<i32> = (0..1000).map(|_| va.sample_recorded_with(&mut rng, &mut trace)).collect();
+        assert_eq!(trace.len(), 1000);
+        assert_eq!(va.replay(&trace).unwrap(), samples);
+        assert!(trace.iter().all(|(die, coin)| die < 4 && coin < 1 << COIN_BITS));
+    }
+
+    #[test]
+    fn invalid_trace() {
+        let va = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        let trace = SamplingTrace::from_packed(vec![0, 2 << COIN_BITS]);
+        assert_eq!(va.replay(&trace), Err(VoseAliasError::InvalidTrace { position: 1 }));
+        // the second column of these tables has an alias, which the first distribution does not
+        let skewed = VoseAlias::new(vec![1, 2], vec![0.75, 0.25]);
+        let trace = SamplingTrace::from_packed(vec![(1 << COIN_BITS) | ((1 << COIN_BITS) - 1)]);
+        assert_eq!(skewed.replay(&trace), Ok(vec![1]));
+        assert_eq!(va.replay(&trace), Ok(vec![2]));
+    }
+}
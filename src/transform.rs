@@ -0,0 +1,320 @@
+//! Transformations building a new distribution from an existing one.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{SlotIndex, VoseAlias, VoseAliasError};
+
+
+/// The result of `VoseAlias::partition()`: the left and right conditional distributions, and the probability of the left one.
+pub type Partition<T, I = u32> = (VoseAlias<T, I>, VoseAlias<T, I>, f32);
+
+
+impl<T, I> VoseAlias<T, I>
+where T: Copy + Hash + Eq + Debug, I: SlotIndex {
+
+    /// Returns a new distribution keeping only the elements for which `keep` returns `true`, given each element and its probability. The probabilities of the kept elements are renormalized.
+    ///
+    /// An error is returned if no element is kept (`VoseAliasError::EmptyDistribution`), or if all the kept elements have a probability of 0 (`VoseAliasError::ZeroTotalWeight`).
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let va = VoseAlias::new(vec!["apple", "banana", "cherry"], vec![0.5, 0.3, 0.2]);
+    /// let no_banana = va.retain(|e, _| *e != "banana").unwrap();
+    /// assert_eq!(no_banana.elements, vec!["apple", "cherry"]);
+    ///
+    /// assert!(va.retain(|_, p| p > 0.9).is_err());
+    /// ```
+    pub fn retain<F: Fn(&T, f32) -> bool>(&self, keep:F) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let (elements, weights):(Vec<T>, Vec<f64>) = self.elements.iter().copied().zip(self.probabilities.iter().copied())
+            .filter(|(e, p)| keep(e, *p as f32))
+            .unzip();
+        VoseAlias::from_weights(elements, weights)
+    }
+
+
+    /// Returns the mixture of this distribution and `other`, with the weights `self_weight` and `other_weight`: the probability of each element is `self_weight * p_self + other_weight * p_other`, renormalized.
+    ///
+    /// The elements of the mixture are the elements of this distribution, followed by the elements that only appear in `other`. An error is returned if a weight is negative or not finite, if both weights are 0, or if the mixture has too many elements for the index type.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let europe = VoseAlias::new(vec!["football", "tennis"], vec![0.75, 0.25]);
+    /// let america = VoseAlias::new(vec!["football", "baseball"], vec![0.5, 0.5]);
+    /// let global = europe.merge(&america, 1.0, 1.0).unwrap();
+    /// assert_eq!(global.elements, vec!["football", "tennis", "baseball"]);
+    /// assert!((global.prob_of(&"football") - 0.625).abs() < 1e-6);
+    /// ```
+    pub fn merge<J: SlotIndex>(&self, other:&VoseAlias<T, J>, self_weight:f64, other_weight:f64) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        for w in [self_weight, other_weight].iter() {
+            if !w.is_finite() || *w < 0.0 {
+                return Err(VoseAliasError::InvalidWeight(*w));
+            }
+        }
+
+        let mut elements = self.elements.clone();
+        let mut weights:Vec<f64> = self.probabilities.iter().map(|p| p * self_weight).collect();
+        for (e, p) in other.elements.iter().zip(other.probabilities.iter()) {
+            match self.index.get(e) {
+                Some(i) => weights[*i] += p * other_weight,
+                None => {
+                    elements.push(*e);
+                    weights.push(p * other_weight);
+                },
+            }
+        }
+        VoseAlias::from_weights(elements, weights)
+    }
+
+
+    /// Returns a new distribution where the weight of each element is `weight(element, probability)`, renormalized.
+    ///
+    /// This is the general form of the transformations boosting, damping or removing some elements. An error is returned if a new weight is negative or not finite, or if all the new weights are 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let va = VoseAlias::new(vec!["a", "b", "c"], vec![0.5, 0.3, 0.2]);
+    /// // sharpening the distribution, with a temperature of 0.5
+    /// let sharp = va.reweight_by(|_, p| p.powf(1.0 / 0.5)).unwrap();
+    /// assert!(sharp.prob_of(&"a") > 0.6);
+    ///
+    /// // boosting "c"
+    /// let boosted = va.reweight_by(|e, p| if *e == "c" { 4.0 * p } else { p }).unwrap();
+    /// assert!((boosted.prob_of(&"c") - 0.5).abs() < 1e-6);
+    /// ```
+    pub fn reweight_by<F: Fn(&T, f64) -> f64>(&self, weight:F) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let weights:Vec<f64> = self.elements.iter().zip(self.probabilities.iter()).map(|(e, p)| weight(e, *p)).collect();
+        VoseAlias::from_weights(self.elements.clone(), weights)
+    }
+
+
+    /// Returns a new distribution where `alpha` is added to the probability of every element, then renormalized (additive smoothing of the probabilities). The elements of `extended_support` that are not part of the distribution are added to it with the weight `alpha`.
+    ///
+    /// Since `alpha` is added to probabilities and not to counts, this is not the Laplace smoothing of the counts the distribution was built from: see `VoseAlias::from_counts_smoothed()` for it.
+    ///
+    /// An error is returned if `alpha` is negative or not finite, or if the smoothed distribution has too many elements for the index type.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let va = VoseAlias::new(vec!["cat", "dog", "cow"], vec![0.75, 0.25, 0.0]);
+    /// let smoothed = va.smooth(1.0, &["unicorn"]).unwrap();
+    /// // (0 + 1) / (1 + 4)
+    /// assert!((smoothed.prob_of(&"cow") - 0.2).abs() < 1e-6);
+    /// assert!((smoothed.prob_of(&"unicorn") - 0.2).abs() < 1e-6);
+    /// ```
+    pub fn smooth(&self, alpha:f64, extended_support:&[T]) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        if !alpha.is_finite() || alpha < 0.0 {
+            return Err(VoseAliasError::InvalidParameter { name: "alpha", value: alpha });
+        }
+
+        let mut elements = self.elements.clone();
+        let mut weights:Vec<f64> = self.probabilities.iter().map(|p| p + alpha).collect();
+        let mut added:HashSet<T> = HashSet::new();
+        for e in extended_support {
+            if !self.index.contains_key(e) && added.insert(*e) {
+                elements.push(*e);
+                weights.push(alpha);
+            }
+        }
+        VoseAlias::from_weights(elements, weights)
+    }
+
+
+    /// Returns a new distribution that explores uniformly with probability `epsilon`: sampling it is equivalent to sampling the support uniformly with probability `epsilon`, and this distribution otherwise.
+    ///
+    /// The two distributions are merged into a single table, with the probabilities `(1 - epsilon) * p + epsilon / n` for n elements, so that sampling still takes one draw.
+    /// An error is returned if `epsilon` is not between 0 and 1.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let arms = VoseAlias::new(vec!["A", "B", "C", "D"], vec![0.7, 0.3, 0.0, 0.0]);
+    /// let policy = arms.with_exploration(0.2).unwrap();
+    /// assert!((policy.prob_of(&"A") - 0.61).abs() < 1e-6);
+    /// assert!((policy.prob_of(&"D") - 0.05).abs() < 1e-6);
+    /// ```
+    pub fn with_exploration(&self, epsilon:f64) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        if !(0.0..=1.0).contains(&epsilon) {
+            return Err(VoseAliasError::InvalidParameter { name: "epsilon", value: epsilon });
+        }
+        let uniform = epsilon / self.elements.len() as f64;
+        let weights:Vec<f64> = self.probabilities.iter().map(|p| (1.0 - epsilon) * p + uniform).collect();
+        VoseAlias::from_weights(self.elements.clone(), weights)
+    }
+
+
+    /// Returns a new distribution where every element has a probability of at least `epsilon`. The elements below `epsilon` are raised to it, and the probabilities of the other elements are scaled down proportionally to keep a total of 1.
+    ///
+    /// An error is returned if `epsilon` is negative, not a number, or larger than 1 / n for n elements, since the floor could not be respected.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::{VoseAlias, WeightedSampler};
+    ///
+    /// let drops = VoseAlias::new(vec!["common", "rare", "legendary"], vec![0.9, 0.1, 0.0]);
+    /// let fair = drops.clamp_min_prob(0.01).unwrap();
+    /// assert!((fair.prob_of(&"legendary") - 0.01).abs() < 1e-6);
+    /// assert!((fair.prob_of(&"common") - 0.891).abs() < 1e-6);
+    /// ```
+    pub fn clamp_min_prob(&self, epsilon:f32) -> Result<VoseAlias<T, I>, VoseAliasError> {
+        let size = self.elements.len();
+        let floor = f64::from(epsilon);
+        if epsilon.is_nan() || epsilon < 0.0 || epsilon * size as f32 > 1.0 {
+            return Err(VoseAliasError::InvalidProbability(epsilon));
+        }
+
+        // raising the clamped elements to the floor can push other elements below it, so the clamped set grows until it is stable
+        let mut clamped:Vec<bool> = self.probabilities.iter().map(|p| *p < floor).collect();
+        let mut weights:Vec<f64>;
+        loop {
+            let free = (1.0 - floor * clamped.iter().filter(|c| **c).count() as f64).max(0.0);
+            let unclamped_total:f64 = self.probabilities.iter().zip(clamped.iter()).filter(|(_, c)| !**c).map(|(p, _)| p).sum();
+            weights = self.probabilities.iter().zip(clamped.iter())
+                .map(|(p, c)| if *c || unclamped_total <= 0.0 { floor } else { p * free / unclamped_total })
+                .collect();
+            let mut stable = true;
+            for (w, c) in weights.iter().zip(clamped.iter_mut()) {
+                if !*c && *w < floor {
+                    *c = true;
+                    stable = false;
+                }
+            }
+            if stable {
+                break;
+            }
+        }
+        VoseAlias::from_weights(self.elements.clone(), weights)
+    }
+
+
+    /// Splits the distribution into the conditional distributions of the elements for which `predicate` returns `true` (left) and `false` (right), and returns them with the probability mass of the left part.
+    ///
+    /// Sampling the left part with the returned probability and the right part otherwise is equivalent to sampling this distribution. An error is returned if one of the parts is empty or has a probability of 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use vose_alias::VoseAlias;
+    ///
+    /// let requests = VoseAlias::new(vec!["GET /", "GET /img", "POST /login"], vec![0.5, 0.3, 0.2]);
+    /// let (reads, writes, read_probability) = requests.partition(|r| r.starts_with("GET")).unwrap();
+    /// assert_eq!(reads.elements, vec!["GET /", "GET /img"]);
+    /// assert_eq!(writes.elements, vec!["POST /login"]);
+    /// assert!((read_probability - 0.8).abs() < 1e-6);
+    /// ```
+    pub fn partition<F: Fn(&T) -> bool>(&self, predicate:F) -> Result<Partition<T, I>, VoseAliasError> {
+        let (mut left, mut right) = ((Vec::new(), Vec::new()), (Vec::new(), Vec::new()));
+        for (e, p) in self.elements.iter().zip(self.probabilities.iter()) {
+            let part = if predicate(e) { &mut left } else { &mut right };
+            part.0.push(*e);
+            part.1.push(*p);
+        }
+
+        let left_mass:f64 = left.1.iter().sum();
+        let right_mass:f64 = right.1.iter().sum();
+        let left_probability = left_mass / (left_mass + right_mass);
+        Ok((VoseAlias::from_weights(left.0, left.1)?, VoseAlias::from_weights(right.0, right.1)?, left_probability as f32))
+    }
+}
+
+
+
+///////////
+// Tests //
+///////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WeightedSampler;
+
+    #[test]
+    fn retain() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.5, 0.2, 0.2, 0.1]);
+        let odd = va.retain(|e, _| e % 2 == 1).unwrap();
+        assert_eq!(odd.elements, vec![1, 3]);
+        assert!((odd.prob_of(&1) - 5.0 / 7.0).abs() < 1e-6);
+        assert_eq!(va.retain(|_, _| false), Err(VoseAliasError::EmptyDistribution));
+        let va = VoseAlias::new(vec![1, 2], vec![1.0, 0.0]);
+        assert_eq!(va.retain(|e, _| *e == 2), Err(VoseAliasError::ZeroTotalWeight));
+    }
+
+    #[test]
+    fn merge() {
+        let a = VoseAlias::new(vec![1, 2], vec![0.5, 0.5]);
+        let b = VoseAlias::new(vec![2, 3], vec![0.5, 0.5]);
+        let merged = a.merge(&b, 3.0, 1.0).unwrap();
+        assert_eq!(merged.elements, vec![1, 2, 3]);
+        for (e, p) in [(1, 0.375), (2, 0.5), (3, 0.125)].iter() {
+            assert!((merged.prob_of(e) - p).abs() < 1e-6);
+        }
+        assert_eq!(a.merge(&b, 0.0, 0.0), Err(VoseAliasError::ZeroTotalWeight));
+        assert_eq!(a.merge(&b, -1.0, 1.0), Err(VoseAliasError::InvalidWeight(-1.0)));
+    }
+
+    #[test]
+    fn reweight_by() {
+        let va = VoseAlias::new(vec![1, 2, 3], vec![0.25, 0.25, 0.5]);
+        let uniform = va.reweight_by(|_, _| 1.0).unwrap();
+        for e in 1..=3 {
+            assert!((uniform.prob_of(&e) - 1.0 / 3.0).abs() < 1e-6);
+        }
+        let zeroed = va.reweight_by(|e, p| if *e == 3 { 0.0 } else { p }).unwrap();
+        assert!((zeroed.prob_of(&1) - 0.5).abs() < 1e-6);
+        assert!(matches!(va.reweight_by(|_, _| f64::NAN), Err(VoseAliasError::InvalidWeight(_))));
+    }
+
+    #[test]
+    fn smooth() {
+        let va = VoseAlias::from_counts(vec![1, 2], vec![2, 0]).unwrap();
+        let smoothed = va.smooth(0.5, &[2, 3, 3]).unwrap();
+        assert_eq!(smoothed.elements, vec![1, 2, 3]);
+        assert!((smoothed.prob_of(&1) - 0.6).abs() < 1e-6);
+        assert!((smoothed.prob_of(&3) - 0.2).abs() < 1e-6);
+        assert_eq!(va.smooth(0.0, &[]).unwrap().elements, vec![1, 2]);
+        assert_eq!(va.smooth(-1.0, &[]).err(), Some(VoseAliasError::InvalidParameter { name: "alpha", value: -1.0 }));
+    }
+
+    #[test]
+    fn with_exploration() {
+        let va = VoseAlias::new(vec![1, 2], vec![1.0, 0.0]);
+        assert!((va.with_exploration(0.5).unwrap().prob_of(&2) - 0.25).abs() < 1e-6);
+        assert!((va.with_exploration(1.0).unwrap().prob_of(&1) - 0.5).abs() < 1e-6);
+        assert_eq!(va.with_exploration(0.0).unwrap().prob_of(&1), 1.0);
+        assert_eq!(va.with_exploration(1.5).err(), Some(VoseAliasError::InvalidParameter { name: "epsilon", value: 1.5 }));
+    }
+
+    #[test]
+    fn clamp_min_prob() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.85, 0.1, 0.05, 0.0]);
+        let clamped = va.clamp_min_prob(0.1).unwrap();
+        // raising 3 and 4 to 0.1 pushes 2 below the floor, so it is clamped too
+        for (e, p) in [(1, 0.7), (2, 0.1), (3, 0.1), (4, 0.1)].iter() {
+            assert!((clamped.prob_of(e) - p).abs() < 1e-6);
+        }
+        let uniform = va.clamp_min_prob(0.25).unwrap();
+        assert!((uniform.prob_of(&1) - 0.25).abs() < 1e-6);
+        assert!(va.clamp_min_prob(0.3).is_err());
+        let va = VoseAlias::new((0..10).collect(), vec![0.1; 10]);
+        assert!(va.clamp_min_prob(0.1).is_ok());
+    }
+
+    #[test]
+    fn partition() {
+        let va = VoseAlias::new(vec![1, 2, 3, 4], vec![0.1, 0.2, 0.3, 0.4]);
+        let (even, odd, p) = va.partition(|e| e % 2 == 0).unwrap();
+        assert_eq!(even.elements, vec![2, 4]);
+        assert_eq!(odd.elements, vec![1, 3]);
+        assert!((p - 0.6).abs() < 1e-6);
+        assert!((even.prob_of(&4) - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(va.partition(|_| true).unwrap_err(), VoseAliasError::EmptyDistribution);
+    }
+}
@@ -0,0 +1,44 @@
+//! Numeric types that can be used as the weights of a distribution.
+
+use std::num::{NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize};
+
+
+/// A numeric type that can be used as a weight by `VoseAlias::try_from_weights()`.
+///
+/// The weights are converted to `f64` before being normalized, so counts, probabilities and non-zero weights can be given as they are, without a lossy conversion to `f32` by the caller. Integers beyond 2^53 lose their lowest bits in the conversion, which does not change the distribution by more than the precision of the tables.
+///
+/// The trait is implemented for the unsigned integers, the floats, and the non-zero unsigned integers. The floats are checked by the constructor: a negative, infinite or NaN weight is an error.
+pub trait Weight: Copy {
+    /// Converts the weight to an `f64`.
+    fn to_f64(self) -> f64;
+}
+
+
+macro_rules! impl_weight {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_weight!(u8, u16, u32, u64, usize, f32, f64);
+
+
+macro_rules! impl_non_zero_weight {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn to_f64(self) -> f64 {
+                    self.get() as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_non_zero_weight!(NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize);